@@ -0,0 +1,136 @@
+//! Just enough ANSI styling for the debug output, built up in `const`s.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Yellow,
+    Blue,
+    Purple,
+    Cyan,
+    White,
+    LightRed,
+    LightCyan,
+    Fixed(u8),
+}
+
+impl Color {
+    pub const fn bold(self) -> Style {
+        Style::new().fg(self).bold()
+    }
+
+    pub fn paint<T: fmt::Display>(self, item: T) -> Painted<T> {
+        Style::new().fg(self).paint(item)
+    }
+
+    fn write_code(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Red => f.write_str("31"),
+            Color::Yellow => f.write_str("33"),
+            Color::Blue => f.write_str("34"),
+            Color::Purple => f.write_str("35"),
+            Color::Cyan => f.write_str("36"),
+            Color::White => f.write_str("37"),
+            Color::LightRed => f.write_str("91"),
+            Color::LightCyan => f.write_str("96"),
+            Color::Fixed(n) => write!(f, "38;5;{}", n),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    foreground: Option<Color>,
+    bold: bool,
+    underline: bool,
+    reset_prefix: bool,
+}
+
+impl Style {
+    pub const fn new() -> Self {
+        Style {
+            foreground: None,
+            bold: false,
+            underline: false,
+            reset_prefix: false,
+        }
+    }
+
+    pub const fn fg(self, color: Color) -> Self {
+        Style {
+            foreground: Some(color),
+            ..self
+        }
+    }
+
+    pub const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    pub const fn underline(self) -> Self {
+        Style {
+            underline: true,
+            ..self
+        }
+    }
+
+    /// Resets whatever styling came before, before applying this one.
+    pub const fn reset_prefix(self) -> Self {
+        Style {
+            reset_prefix: true,
+            ..self
+        }
+    }
+
+    pub fn paint<T: fmt::Display>(self, item: T) -> Painted<T> {
+        Painted { style: self, item }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.foreground.is_none() && !self.bold && !self.underline
+    }
+
+    fn write_prefix(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.reset_prefix {
+            f.write_str("\x1b[0m")?;
+        }
+        if self.is_plain() {
+            return Ok(());
+        }
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1");
+        }
+        if self.underline {
+            codes.push("4");
+        }
+        f.write_str("\x1b[")?;
+        f.write_str(&codes.join(";"))?;
+        if let Some(color) = self.foreground {
+            if !codes.is_empty() {
+                f.write_str(";")?;
+            }
+            color.write_code(f)?;
+        }
+        f.write_str("m")
+    }
+}
+
+/// An item which is written out in a [`Style`].
+#[derive(Clone, Copy, Debug)]
+pub struct Painted<T> {
+    style: Style,
+    item: T,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.style.write_prefix(f)?;
+        self.item.fmt(f)?;
+        if !self.style.is_plain() {
+            f.write_str("\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
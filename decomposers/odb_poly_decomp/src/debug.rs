@@ -1,11 +1,11 @@
 use id_arena::Arena;
 use itertools::Itertools;
-use procr_ansi_term::{Color, Style};
 use std::fmt;
 
 use crate::{
     active::ActiveEdges,
     active::{ActiveNodes, ActiveVec},
+    ansi::{Color, Style},
     decomposer::{Decomposer, EdgeScans},
     edge::{Edge, EdgeId},
     geometry::{Geometry, Side},
@@ -63,7 +63,7 @@ macro_rules! type_id {
     };
     (@id_expr $id:expr) => {
         STYLE_TYPE_ID
-            .paint::<fmt::Arguments, str>(format_args!("[{}]", $id.index())).to_string()
+            .paint(format_args!("[{}]", $id.index())).to_string()
     };
 }
 
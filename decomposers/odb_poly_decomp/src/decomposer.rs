@@ -1,12 +1,16 @@
 use std::{error::Error, fmt::Display};
 
-use procr_ansi_term::{Color, Style};
+use doug_geometry::shapes::PolyLike;
 use tracing::info;
 
 use crate::active::{ActiveEdges, ActiveNodes, ActiveVec};
+use crate::ansi::{Color, Style};
 use crate::debug::COLOR_ORANGE;
+use crate::geometry::Split;
+use crate::node::NodeOrigin;
 use crate::point::Point;
 use crate::rect::Rect;
+use crate::trapezoid::{self, Trapezoid};
 use crate::{
     active::Cursor,
     geometry::{Geometry, Side},
@@ -23,6 +27,11 @@ pub enum DecompErr {
     NotEnoughPoints,
     FailedScanlineUpdate,
     IsAlreadySimple,
+    /// A rect produced has a coordinate which does not fit in an `i32`.
+    OutOfRange,
+    /// Two sides of the polygon cross or touch, other than neighbouring
+    /// sides at the vertex they share.
+    SelfIntersecting,
 }
 
 impl Display for DecompErr {
@@ -48,12 +57,94 @@ pub struct EdgeScans {
     rc: Option<Cursor>,
 }
 
+/// A rect produced by the decomposer, with where it came from.
+#[derive(Clone, Copy, Debug)]
+pub struct ScannedRect {
+    pub rect: Rect,
+    /// The scanline at which the rect was emitted. Rects are emitted once
+    /// the scanline reaches their upper side, so this is its y coordinate.
+    pub scanline: isize,
+    /// The edge forming the left side of the rect.
+    pub left: EdgeId,
+    /// The edge forming the right side of the rect.
+    pub right: EdgeId,
+    pub provenance: Provenance,
+}
+
+/// Where a rect came from in the input polygon, so that it can be mapped back
+/// to the polygon's boundary without the [`Geometry`].
+///
+/// Each corner is either a vertex of the polygon, or the point at which the
+/// scanline split one of its sides, along with the [`Split`] that made it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// The side of the polygon which the left side of the rect lies on, by
+    /// the index of the vertex it starts from.
+    pub left_side: usize,
+    /// The side of the polygon which the right side of the rect lies on.
+    pub right_side: usize,
+    /// Where the corner at [`Rect::left`] came from.
+    pub lower_left: NodeOrigin,
+    /// Where the corner above [`Rect::left`] came from.
+    pub upper_left: NodeOrigin,
+    /// Where the corner at [`Rect::right`] came from.
+    pub upper_right: NodeOrigin,
+    /// Where the corner below [`Rect::right`] came from.
+    pub lower_right: NodeOrigin,
+}
+
+impl Provenance {
+    /// The corners, anticlockwise from [`lower_left`](Self::lower_left).
+    pub fn corners(&self) -> [NodeOrigin; 4] {
+        [
+            self.lower_left,
+            self.lower_right,
+            self.upper_right,
+            self.upper_left,
+        ]
+    }
+
+    /// The splits which made corners of the rect, in the order of
+    /// [`corners`](Self::corners).
+    pub fn splits(&self) -> impl Iterator<Item = Split> {
+        self.corners()
+            .into_iter()
+            .filter_map(|corner| match corner {
+                NodeOrigin::Vertex(_) => None,
+                NodeOrigin::Split(split) => Some(split),
+            })
+    }
+
+    /// Renumber the sides and vertices as they would be if the `n` points of
+    /// the polygon were in reverse order. Side `ix` runs from vertex `ix` to
+    /// vertex `ix + 1`, so once reversed it starts from the vertex before
+    /// `n - 1 - ix`.
+    fn reversed(self, n: usize) -> Self {
+        let side = |ix: usize| (2 * n - 2 - ix) % n;
+        let corner = |origin| match origin {
+            NodeOrigin::Vertex(ix) => NodeOrigin::Vertex(n - 1 - ix),
+            NodeOrigin::Split(split) => NodeOrigin::Split(Split {
+                polygon_side: side(split.polygon_side),
+                ..split
+            }),
+        };
+        Provenance {
+            left_side: side(self.left_side),
+            right_side: side(self.right_side),
+            lower_left: corner(self.lower_left),
+            upper_left: corner(self.upper_left),
+            upper_right: corner(self.upper_right),
+            lower_right: corner(self.lower_right),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ScanResult {
     ReturnRects,
     ContinueLoop(EdgeScans),
     ContinueSplit(EdgeScans),
-    NewRect(Rect),
+    NewRect(ScannedRect),
 }
 
 macro_rules! check_return {
@@ -180,9 +271,8 @@ impl EdgeScans {
     }
 
     fn check_both_splittable(
-        mut self,
+        self,
         geometry: &Geometry,
-        active_edges: &ActiveEdges,
         scanline: isize,
     ) -> ScanResult {
         // Based on:
@@ -190,18 +280,15 @@ impl EdgeScans {
         if self.le().scanline_strictly_inside(geometry, scanline)
             && self.re().scanline_strictly_inside(geometry, scanline)
         {
-            // Current interpretation of (++itr) == right.cursor is that
-            // itr is incremented first, and then the comparison takes
-            // place. https://stackoverflow.com/a/1813008/3486684
-            if let Some(c) = self.lc.as_mut() {
-                *c += 1;
-            }
-            if self.lc == self.rc {
+            // The cursors point just past the edges they were found at, so
+            // the edges are next to each other if the cursors are. Nothing
+            // starts or stops between them on the scanline, so the region
+            // between them carries on past it, and there is no rect yet.
+            //
+            // Otherwise, edges start between them on the scanline, and both
+            // have to be split to close off the rect below it.
+            if self.lc.map(|c| c + 1) == self.rc {
                 return self.continue_loop();
-            } else if let Some(id) =
-                self.lc.and_then(|c| active_edges.peek_at(c))
-            {
-                self.le.replace(geometry[id]);
             }
         }
         self.continue_split()
@@ -228,7 +315,7 @@ impl EdgeScans {
             geometry,
             active_edges,
             self,
-            self.check_both_splittable(geometry, active_edges, scanline)
+            self.check_both_splittable(geometry, scanline)
         );
 
         if self.le().scanline_strictly_inside(geometry, scanline) {
@@ -240,7 +327,8 @@ impl EdgeScans {
                 fmt:"left strictly contains scanline, so performed a split: {:#?}\n" |
                 dbg_active_edges!(geometry, active_edges, &self)
             );
-        } else if self.re().scanline_strictly_inside(geometry, scanline) {
+        }
+        if self.re().scanline_strictly_inside(geometry, scanline) {
             // Based on:
             // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L299-L303
             let new_edge = geometry.split_edge(self.re().id(), scanline);
@@ -249,16 +337,27 @@ impl EdgeScans {
                 fmt:"right strictly contains scanline, so performed a split: {:#?}\n" |
                 dbg_active_edges!(geometry, active_edges, &self)
             );
-        };
+        }
 
-        ScanResult::NewRect(
+        ScanResult::NewRect(ScannedRect {
             // Based on:
             // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L317
-            Rect::new(
+            rect: Rect::new(
                 self.le().source(geometry).point,
                 self.re().source(geometry).point,
             ),
-        )
+            scanline,
+            left: self.le().id(),
+            right: self.re().id(),
+            provenance: Provenance {
+                left_side: self.le().polygon_side,
+                right_side: self.re().polygon_side,
+                lower_left: self.le().source(geometry).origin,
+                upper_left: self.le().target(geometry).origin,
+                upper_right: self.re().source(geometry).origin,
+                lower_right: self.re().target(geometry).origin,
+            },
+        })
     }
 }
 
@@ -362,22 +461,41 @@ impl Decomposer {
         }
     }
 
-    fn scan_and_split(
-        &mut self,
-        geometry: &mut Geometry,
-        mut rects: Vec<Rect>,
-    ) -> Vec<Rect> {
+    /// Prepare to scan along the next scanline.
+    fn start_scanline(&mut self, geometry: &Geometry) {
+        // Based on (see also, by CTRL+F for "SCANLINE_COMMENT" below):
+        // DECOMP_SCANLINE_UPDATE https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L215
+        self.update_scanline(geometry);
+        // Based on (but note: purges must happen *after* the scanline has
+        // been updated, we need to update the scanline first,
+        // because we do not do the following): https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L205):
+        // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L216
+        self.purge_active_edges(geometry);
+        // Based on:
+        // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L208
+        self.add_active_edges(geometry);
+        emit_info!(
+            fmt:"state after adding active edges: {:#?}" |
+            dbg_decomposer!(&*self, geometry, None)
+        );
+
         // Based on:
         // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L258-L320
         // See also the comment by CTRL+F for PURGE_ACTIVE_EDGES
         self.active_edges.reset_cursor();
+    }
 
-        let mut edge_scan = EdgeScans::default();
-
+    /// Continue scanning along the current scanline from `edge_scan`, until
+    /// the next rect is found. Returns `None` once the scanline is finished.
+    fn next_rect(
+        &mut self,
+        geometry: &mut Geometry,
+        edge_scan: &mut EdgeScans,
+    ) -> Option<ScannedRect> {
         while !self.active_edges.finished() {
             emit_info!(sty:COLOR_ORANGE.bold(),
                 fmt:"INITIAL STATE (split loop): {:#?}\n" |
-                dbg_decomposer!(self, geometry, Some(&edge_scan))
+                dbg_decomposer!(self, geometry, Some(&*edge_scan))
             );
 
             match edge_scan.scan_and_split(
@@ -386,21 +504,21 @@ impl Decomposer {
                 self.scanline,
             ) {
                 ScanResult::ReturnRects => {
-                    return rects;
+                    return None;
                 }
                 ScanResult::ContinueLoop(s) => {
-                    edge_scan = s;
+                    *edge_scan = s;
                 }
                 ScanResult::NewRect(rect) => {
                     emit_info!(
-                        fmt:"pushing rect: {:?}" | rect
+                        fmt:"emitting rect: {:?}" | rect
                     );
-                    rects.push(rect);
+                    return Some(rect);
                 }
                 ScanResult::ContinueSplit(_) => unreachable!(),
             }
         }
-        rects
+        None
     }
 
     #[inline]
@@ -449,8 +567,7 @@ impl Decomposer {
     /// Based on:
     /// https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L183
     pub fn decompose(points: Vec<Point>) -> Result<Vec<Rect>, DecompErr> {
-        let mut geometry = Geometry::new(points)?;
-        let mut decomposer = Self::new(&geometry)?;
+        let rects = Self::decompose_iter(points)?;
 
         // TODO: figure out whether its worth pre-allocating rects. If yes, then
         // what value should we pick? Currently just picked 2 * n_points...
@@ -471,29 +588,133 @@ impl Decomposer {
         // * n_nodes = (6 * n_points) rects
         //
         // Worth pre-allocating? Not sure.
-        let mut rects = Vec::with_capacity(geometry.len_nodes());
-        loop {
-            // Based on (see also, by CTRL+F for "SCANLINE_COMMENT" below):
-            // DECOMP_SCANLINE_UPDATE https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L215
-            decomposer.update_scanline(&geometry);
-            // Based on (but note: purges must happen *after* the scanline has
-            // been updated, we need to update the scanline first,
-            // because we do not do the following): https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L205):
-            // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L216
-            decomposer.purge_active_edges(&geometry);
-            // Based on:
-            // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L208
-            decomposer.add_active_edges(&geometry);
-            emit_info!(
-                fmt:"state after adding active edges: {:#?}" |
-                dbg_decomposer!(&decomposer, &geometry, None)
-            );
+        let mut result = Vec::with_capacity(rects.geometry.len_nodes());
+        result.extend(rects.map(|r| r.rect));
+        Ok(result)
+    }
 
-            rects = decomposer.scan_and_split(&mut geometry, rects);
+    /// Decompose as with [`decompose`](Self::decompose), but lazily: each
+    /// rect is yielded as soon as the scanline reaches it, along with the
+    /// scanline and the edges which formed it. Dropping the iterator stops
+    /// the decomposition early.
+    pub fn decompose_iter(
+        points: Vec<Point>,
+    ) -> Result<DecomposeIter, DecompErr> {
+        let geometry = Geometry::new(points)?;
+        let decomposer = Self::new(&geometry)?;
+        Ok(DecomposeIter {
+            geometry,
+            decomposer,
+            edge_scan: None,
+            finished: false,
+        })
+    }
 
-            if decomposer.active_nodes.finished() {
-                break;
+    /// Decompose a rectilinear polygon from `doug_geometry` into rects on its
+    /// layer. Unlike [`decompose`](Self::decompose), the points may be in
+    /// either order.
+    pub fn decompose_poly<P: PolyLike>(
+        poly: &P,
+    ) -> Result<Vec<doug_geometry::shapes::Rect>, DecompErr> {
+        let rects = Self::decompose_poly_with_provenance(poly)?;
+        Ok(rects.into_iter().map(|(rect, _)| rect).collect())
+    }
+
+    /// Decompose as with [`decompose_poly`](Self::decompose_poly), along
+    /// with where each rect came from. The sides and vertices named by the
+    /// [`Provenance`] are numbered as in `poly`, whichever order its points
+    /// are in.
+    pub fn decompose_poly_with_provenance<P: PolyLike>(
+        poly: &P,
+    ) -> Result<Vec<(doug_geometry::shapes::Rect, Provenance)>, DecompErr> {
+        let mut points: Vec<Point> =
+            poly.points().iter().map(Point::from).collect();
+        let n = points.len();
+        let doubled_area: i128 = (0..n)
+            .map(|ix| {
+                let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+                p0.x as i128 * p1.y as i128 - p1.x as i128 * p0.y as i128
+            })
+            .sum();
+        let reversed = doubled_area > 0;
+        if reversed {
+            points.reverse();
+        }
+
+        Self::decompose_iter(points)?
+            .map(|r| {
+                let rect = r
+                    .rect
+                    .to_rect(poly.layer())
+                    .map_err(|_| DecompErr::OutOfRange)?;
+                let provenance = if reversed {
+                    r.provenance.reversed(n)
+                } else {
+                    r.provenance
+                };
+                Ok((rect, provenance))
+            })
+            .collect()
+    }
+
+    /// Fracture a simple polygon into trapezoids. Unlike
+    /// [`decompose`](Self::decompose), the polygon may have edges at any
+    /// angle, and its points may be in either order. See
+    /// [`trapezoid::decompose`] for how this is done.
+    pub fn decompose_trapezoids(
+        points: &[Point],
+    ) -> Result<Vec<Trapezoid>, DecompErr> {
+        trapezoid::decompose(points)
+    }
+
+    /// Fracture a simple polygon from `doug_geometry` into trapezoids, as
+    /// with [`decompose_trapezoids`](Self::decompose_trapezoids).
+    pub fn decompose_poly_trapezoids<P: PolyLike>(
+        poly: &P,
+    ) -> Result<Vec<Trapezoid>, DecompErr> {
+        let points: Vec<Point> =
+            poly.points().iter().map(Point::from).collect();
+        trapezoid::decompose(&points)
+    }
+}
+
+/// The rects of a polygon, produced one at a time by
+/// [`Decomposer::decompose_iter`].
+pub struct DecomposeIter {
+    geometry: Geometry,
+    decomposer: Decomposer,
+    /// The scan along the current scanline, or `None` if the next scanline
+    /// has not been started.
+    edge_scan: Option<EdgeScans>,
+    finished: bool,
+}
+
+impl DecomposeIter {
+    /// The geometry being decomposed, in which the edges of each
+    /// [`ScannedRect`] can be looked up. Edges are split as the scanline
+    /// passes them, so it grows as the iterator is advanced.
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+}
+
+impl Iterator for DecomposeIter {
+    type Item = ScannedRect;
+
+    fn next(&mut self) -> Option<ScannedRect> {
+        while !self.finished {
+            let edge_scan = self.edge_scan.get_or_insert_with(|| {
+                self.decomposer.start_scanline(&self.geometry);
+                EdgeScans::default()
+            });
+            if let Some(rect) =
+                self.decomposer.next_rect(&mut self.geometry, edge_scan)
+            {
+                return Some(rect);
             }
+
+            self.edge_scan = None;
+            self.finished = self.decomposer.active_nodes.finished();
             // TODO: do we need something that does what line 214 does?:
             // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L214
             // Answer: I don't think so, because it's manually advancing the
@@ -501,6 +722,6 @@ impl Decomposer {
             // should make sure that updating of the scanline happens first
             // in the loop (SCANLINE_COMMENT)
         }
-        Ok(rects)
+        None
     }
 }
@@ -25,6 +25,10 @@ pub struct Edge {
     pub source: NodeId,
     pub target: NodeId,
     pub side: Side,
+    /// The side of the input polygon which this edge lies on, by the index of
+    /// the vertex it starts from. Edges created by splits keep the side of
+    /// the edge they were split from.
+    pub polygon_side: usize,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -34,12 +38,19 @@ pub enum Containment {
 }
 
 impl Edge {
-    pub fn new(id: EdgeId, source: NodeId, target: NodeId, ty: Side) -> Self {
+    pub fn new(
+        id: EdgeId,
+        source: NodeId,
+        target: NodeId,
+        ty: Side,
+        polygon_side: usize,
+    ) -> Self {
         Self {
             id,
             source,
             target,
             side: ty,
+            polygon_side,
         }
     }
 
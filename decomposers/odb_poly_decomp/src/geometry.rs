@@ -8,13 +8,25 @@ use crate::{
     dbg_edge, dbg_edges,
     decomposer::DecompErr,
     edge::{Edge, EdgeId},
-    node::{Node, NodeId},
+    node::{Node, NodeId, NodeOrigin},
     point::Point,
 };
 
 pub struct Geometry {
     pub nodes: Arena<Node>,
     pub edges: Arena<Edge>,
+    /// Every call to [`split_edge`](Geometry::split_edge), in order.
+    pub splits: Vec<Split>,
+}
+
+/// A record of a call to [`Geometry::split_edge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Split {
+    /// The number of splits made before this one.
+    pub index: usize,
+    /// The side of the input polygon which the split edge lies on.
+    pub polygon_side: usize,
+    pub scanline: isize,
 }
 
 impl Index<EdgeId> for Geometry {
@@ -63,6 +75,7 @@ impl Geometry {
         Self {
             nodes: Arena::with_capacity(capacity),
             edges: Arena::with_capacity(capacity),
+            splits: Vec::new(),
         }
     }
 
@@ -72,9 +85,10 @@ impl Geometry {
         point: Point,
         in_edge: Option<EdgeId>,
         out_edge: Option<EdgeId>,
+        origin: NodeOrigin,
     ) -> NodeId {
         self.nodes
-            .alloc_with_id(|id| Node::new(point, id, in_edge, out_edge))
+            .alloc_with_id(|id| Node::new(point, id, in_edge, out_edge, origin))
     }
 
     #[inline]
@@ -83,10 +97,11 @@ impl Geometry {
         source: NodeId,
         target: NodeId,
         ty: Side,
+        polygon_side: usize,
     ) -> Edge {
-        let new_edge_id = self
-            .edges
-            .alloc_with_id(|id| Edge::new(id, source, target, ty));
+        let new_edge_id = self.edges.alloc_with_id(|id| {
+            Edge::new(id, source, target, ty, polygon_side)
+        });
         self[source].set_out_edge(new_edge_id);
         self[target].set_inc_edge(new_edge_id);
         self[new_edge_id]
@@ -100,7 +115,10 @@ impl Geometry {
     fn initialize_nodes(&mut self, points: &[Point]) -> Vec<NodeId> {
         points
             .iter()
-            .map(|&p| self.new_node(p, None, None))
+            .enumerate()
+            .map(|(ix, &p)| {
+                self.new_node(p, None, None, NodeOrigin::Vertex(ix))
+            })
             .collect::<Vec<NodeId>>()
     }
 
@@ -135,10 +153,10 @@ impl Geometry {
                 break;
             }
             if let Some(side) = source_node.which_side(&self[target]) {
-                self.new_edge(source, target, side);
+                self.new_edge(source, target, side, s);
                 info!(
                     "new edge: {:?}",
-                    dbg_edge!(self, self.edges.iter().last().unwrap().1)
+                    dbg_edge!(self, self.edges.iter().next_back().unwrap().1)
                 );
             }
             (s, t) = (t, (t + 1) % n_nodes);
@@ -215,8 +233,19 @@ impl Geometry {
         // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L300
         //  https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L310
         // TODO: confirm that the edge should not be added to active nodes list
-        let new_node_id =
-            self.new_node(Point::new(existing_x, scanline), None, None);
+        let polygon_side = self[split_target].polygon_side;
+        let split = Split {
+            index: self.splits.len(),
+            polygon_side,
+            scanline,
+        };
+        self.splits.push(split);
+        let new_node_id = self.new_node(
+            Point::new(existing_x, scanline),
+            None,
+            None,
+            NodeOrigin::Split(split),
+        );
 
         // Based on:
         // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L301-L303
@@ -237,7 +266,7 @@ impl Geometry {
                 // into the active edge list, if it still needs to be split.)
                 // Based on:
                 // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L304
-                self.new_edge(input_node, new_node_id, side)
+                self.new_edge(input_node, new_node_id, side, polygon_side)
             }
             Side::Right => {
                 // input edge gets its target replaced by new node
@@ -253,7 +282,7 @@ impl Geometry {
                 // into the active edge list, if it still needs to be split.)
                 // Based on:
                 // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/src/zutil/poly_decomp.cpp#L314
-                self.new_edge(new_node_id, input_node, side)
+                self.new_edge(new_node_id, input_node, side, polygon_side)
             }
         }
     }
@@ -1,4 +1,5 @@
 pub mod active;
+mod ansi;
 mod debug;
 pub mod decomposer;
 pub mod edge;
@@ -6,3 +7,4 @@ pub mod geometry;
 pub mod node;
 pub mod point;
 pub mod rect;
+pub mod trapezoid;
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 
 use crate::{
     edge::EdgeId,
-    geometry::{GeometricId, Side},
+    geometry::{GeometricId, Side, Split},
     point::Point,
 };
 
@@ -16,12 +16,22 @@ impl GeometricId for NodeId {
     }
 }
 
+/// Where a node came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeOrigin {
+    /// The vertex of the input polygon at this index.
+    Vertex(usize),
+    /// The point at which an edge was split by the scanline.
+    Split(Split),
+}
+
 #[derive(Clone, Copy)]
 pub struct Node {
     pub id: NodeId,
     pub point: Point,
     pub inc_edge: Option<EdgeId>,
     pub out_edge: Option<EdgeId>,
+    pub origin: NodeOrigin,
 }
 
 impl Node {
@@ -30,12 +40,14 @@ impl Node {
         id: NodeId,
         inc_edge: Option<EdgeId>,
         out_edge: Option<EdgeId>,
+        origin: NodeOrigin,
     ) -> Self {
         Self {
             id,
             point,
             inc_edge,
             out_edge,
+            origin,
         }
     }
 
@@ -1,4 +1,7 @@
 use std::cmp::Ordering;
+use std::num::TryFromIntError;
+
+use doug_geometry::shapes::PointLike;
 
 use crate::geometry::Side;
 
@@ -90,3 +93,29 @@ impl Ord for Point {
         )
     }
 }
+
+/// Every `i32` coordinate fits in an `isize`, so points from `doug_geometry`
+/// convert without loss.
+impl<P: PointLike> From<&P> for Point {
+    fn from(p: &P) -> Self {
+        Point::new(p.x() as isize, p.y() as isize)
+    }
+}
+
+impl From<doug_geometry::shapes::Point> for Point {
+    fn from(p: doug_geometry::shapes::Point) -> Self {
+        Point::from(&p)
+    }
+}
+
+/// Fails if either coordinate does not fit in an `i32`.
+impl TryFrom<Point> for doug_geometry::shapes::Point {
+    type Error = TryFromIntError;
+
+    fn try_from(p: Point) -> Result<Self, Self::Error> {
+        Ok(doug_geometry::shapes::Point {
+            x: p.x.try_into()?,
+            y: p.y.try_into()?,
+        })
+    }
+}
@@ -1,9 +1,11 @@
 // Based on:
 // https://github.com/bzm3r/OpenROAD/blob/ecc03c290346823a66fec78669dacc8a85aabb05/src/odb/include/odb/geom.h#L188
 
+use std::num::TryFromIntError;
+
 use crate::point::Point;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Rect {
     _left: Point,
     _right: Point,
@@ -16,4 +18,27 @@ impl Rect {
             _right: right,
         }
     }
+
+    /// The lower left corner.
+    pub fn left(&self) -> Point {
+        self._left
+    }
+
+    /// The upper right corner.
+    pub fn right(&self) -> Point {
+        self._right
+    }
+
+    /// Convert into a `doug_geometry` rect on `layer`. Fails if a coordinate
+    /// does not fit in an `i32`.
+    pub fn to_rect(
+        &self,
+        layer: u8,
+    ) -> Result<doug_geometry::shapes::Rect, TryFromIntError> {
+        Ok(doug_geometry::shapes::Rect {
+            p0: self._left.try_into()?,
+            p1: self._right.try_into()?,
+            layer,
+        })
+    }
 }
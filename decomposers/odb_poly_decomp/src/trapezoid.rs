@@ -0,0 +1,388 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use tracing::trace;
+
+use crate::decomposer::DecompErr;
+use crate::point::Point;
+
+/// An exact coordinate, `num / den`, kept in lowest terms with a positive
+/// denominator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "Rational denominator must not be zero");
+        let divisor = gcd(num, den) * den.signum();
+        Self {
+            num: num / divisor,
+            den: den / divisor,
+        }
+    }
+
+    #[inline]
+    pub fn integer(n: isize) -> Self {
+        Self {
+            num: n as i128,
+            den: 1,
+        }
+    }
+
+    #[inline]
+    pub fn num(&self) -> i128 {
+        self.num
+    }
+
+    #[inline]
+    pub fn den(&self) -> i128 {
+        self.den
+    }
+
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    /// The value, if it is a whole number which fits in an `isize`.
+    pub fn to_integer(&self) -> Option<isize> {
+        if self.is_integer() {
+            self.num.try_into().ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i128
+}
+
+/// Compare `a / b` with `c / d`, for positive `b` and `d`, without
+/// multiplying them out (which could overflow). The whole parts are compared
+/// first, and then the fractional parts by comparing their reciprocals, as in
+/// a continued fraction expansion.
+fn cmp_fractions(a: i128, b: i128, c: i128, d: i128) -> Ordering {
+    let (qa, ra) = (a.div_euclid(b), a.rem_euclid(b));
+    let (qc, rc) = (c.div_euclid(d), c.rem_euclid(d));
+    match (qa.cmp(&qc), ra, rc) {
+        (Ordering::Equal, 0, 0) => Ordering::Equal,
+        (Ordering::Equal, 0, _) => Ordering::Less,
+        (Ordering::Equal, _, 0) => Ordering::Greater,
+        (Ordering::Equal, _, _) => cmp_fractions(d, rc, b, ra),
+        (o, _, _) => o,
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_fractions(self.num, self.den, other.num, other.den)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// A trapezoid with horizontal top and bottom sides. Its left and right sides
+/// lie on edges of the polygon it was cut from, so their ends may fall between
+/// integer coordinates.
+///
+/// A trapezoid whose top or bottom has shrunk to a point is a triangle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Trapezoid {
+    pub bottom: isize,
+    pub top: isize,
+    pub bottom_left: Rational,
+    pub bottom_right: Rational,
+    pub top_left: Rational,
+    pub top_right: Rational,
+}
+
+impl Trapezoid {
+    /// Whether every corner lies on integer coordinates.
+    pub fn is_integral(&self) -> bool {
+        [
+            self.bottom_left,
+            self.bottom_right,
+            self.top_left,
+            self.top_right,
+        ]
+        .iter()
+        .all(Rational::is_integer)
+    }
+
+    /// Convert into a counter-clockwise `doug_geometry` polygon on `layer`,
+    /// leaving out the repeated corner of a triangle. Returns `None` if a
+    /// corner is not on integer coordinates, or does not fit in an `i32`.
+    pub fn to_poly(&self, layer: u8) -> Option<doug_geometry::shapes::Poly> {
+        let corner = |x: Rational, y: isize| {
+            let x = x.to_integer()?;
+            Some(doug_geometry::shapes::Point {
+                x: x.try_into().ok()?,
+                y: y.try_into().ok()?,
+            })
+        };
+        let mut points = vec![
+            corner(self.bottom_left, self.bottom)?,
+            corner(self.bottom_right, self.bottom)?,
+            corner(self.top_right, self.top)?,
+            corner(self.top_left, self.top)?,
+        ];
+        points.dedup();
+        Some(doug_geometry::shapes::Poly { points, layer })
+    }
+}
+
+/// A non-horizontal edge of the polygon, from its lower end to its upper end.
+#[derive(Clone, Copy, Debug)]
+struct SweepEdge {
+    low: Point,
+    high: Point,
+}
+
+impl SweepEdge {
+    /// The x coordinate at which the edge crosses the scanline `y`.
+    fn x_at(&self, y: isize) -> Rational {
+        let (dx, dy) = (
+            self.high.x as i128 - self.low.x as i128,
+            self.high.y as i128 - self.low.y as i128,
+        );
+        Rational::new(
+            self.low.x as i128 * dy + (y as i128 - self.low.y as i128) * dx,
+            dy,
+        )
+    }
+}
+
+/// The sign of the cross product of `b - a` and `c - a`: `Greater` if `c` lies
+/// to the left of the line from `a` to `b`.
+fn orientation(a: Point, b: Point, c: Point) -> Ordering {
+    let cross = (b.x as i128 - a.x as i128) * (c.y as i128 - a.y as i128)
+        - (b.y as i128 - a.y as i128) * (c.x as i128 - a.x as i128);
+    cross.cmp(&0)
+}
+
+/// Whether `p`, which is collinear with `a` and `b`, lies between them.
+fn within(a: Point, b: Point, p: Point) -> bool {
+    a.x.min(b.x) <= p.x
+        && p.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= p.y
+        && p.y <= a.y.max(b.y)
+}
+
+/// Whether the segments `a`-`b` and `c`-`d` have any point in common.
+fn segments_meet(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    (o1 != o2 && o3 != o4)
+        || (o1 == Ordering::Equal && within(a, b, c))
+        || (o2 == Ordering::Equal && within(a, b, d))
+        || (o3 == Ordering::Equal && within(c, d, a))
+        || (o4 == Ordering::Equal && within(c, d, b))
+}
+
+/// Check that no two sides of the polygon meet, other than neighbouring sides
+/// at their shared vertex. The points must not repeat one after the other.
+///
+/// Sides are visited by their lowest point, and only compared with the sides
+/// still reaching that high, so that polygons which are not tall and thin
+/// are checked in much less than quadratic time.
+fn check_simple(points: &[Point]) -> Result<(), DecompErr> {
+    let n = points.len();
+    let side = |ix: usize| (points[ix], points[(ix + 1) % n]);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&ix| {
+        let (a, b) = side(ix);
+        a.y.min(b.y)
+    });
+
+    let mut reaching: Vec<usize> = Vec::new();
+    for &ix in order.iter() {
+        let (a, b) = side(ix);
+        reaching.retain(|&other| {
+            let (c, d) = side(other);
+            c.y.max(d.y) >= a.y.min(b.y)
+        });
+        for &other in reaching.iter() {
+            let (c, d) = side(other);
+            if a.x.max(b.x) < c.x.min(d.x) || c.x.max(d.x) < a.x.min(b.x) {
+                continue;
+            }
+            let meet = if other == (ix + 1) % n {
+                // Neighbours share `b`, and fold back over each other if the
+                // far end of either lies on the other.
+                orientation(a, b, d) == Ordering::Equal && within(a, b, d)
+                    || orientation(c, d, a) == Ordering::Equal
+                        && within(c, d, a)
+            } else if ix == (other + 1) % n {
+                orientation(c, d, b) == Ordering::Equal && within(c, d, b)
+                    || orientation(a, b, c) == Ordering::Equal
+                        && within(a, b, c)
+            } else {
+                segments_meet(a, b, c, d)
+            };
+            if meet {
+                return Err(DecompErr::SelfIntersecting);
+            }
+        }
+        reaching.push(ix);
+    }
+    Ok(())
+}
+
+/// Fracture a simple polygon, which may have edges at any angle, into
+/// trapezoids with horizontal tops and bottoms.
+///
+/// Like [`Decomposer::decompose`](crate::decomposer::Decomposer::decompose),
+/// this sweeps a scanline upwards through the y coordinates of the vertices.
+/// Between two scanlines no edges begin, end or cross, so the edges crossing
+/// the band between them can be sorted from left to right, and each pair of
+/// them bounds a trapezoid inside the polygon. A trapezoid is carried on
+/// through the next band if it is bounded by the same pair of edges there.
+///
+/// The points may be in either order, and repeated points are ignored.
+/// Polygons whose sides cross or touch are rejected, as the sweep would
+/// silently fill them in wrongly. Intermediate values are computed exactly in
+/// `i128`, which is enough for coordinates up to `2^62` in magnitude.
+pub fn decompose(points: &[Point]) -> Result<Vec<Trapezoid>, DecompErr> {
+    let mut points = points.to_vec();
+    points.dedup();
+    while points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return Err(DecompErr::NotEnoughPoints);
+    }
+    check_simple(&points)?;
+
+    let n = points.len();
+    let mut edges: Vec<SweepEdge> = (0..n)
+        .filter_map(|ix| {
+            let (a, b) = (points[ix], points[(ix + 1) % n]);
+            match a.y.cmp(&b.y) {
+                Ordering::Less => Some(SweepEdge { low: a, high: b }),
+                Ordering::Equal => None,
+                Ordering::Greater => Some(SweepEdge { low: b, high: a }),
+            }
+        })
+        .collect();
+    edges.sort_by_key(|e| e.low.y);
+
+    let mut scanlines: Vec<isize> = points.iter().map(|p| p.y).collect();
+    scanlines.sort_unstable();
+    scanlines.dedup();
+
+    let mut trapezoids: Vec<Trapezoid> = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    let mut next_edge = 0;
+    // The trapezoids reaching the current scanline, by their left and right
+    // edges.
+    let mut open: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for band in scanlines.windows(2) {
+        let (bottom, top) = (band[0], band[1]);
+        active.retain(|&e| edges[e].high.y > bottom);
+        while next_edge < edges.len() && edges[next_edge].low.y <= bottom {
+            active.push(next_edge);
+            next_edge += 1;
+        }
+        // Edges do not cross inside the band, so ordering them by where they
+        // cross its bottom, and then its top, orders them across all of it.
+        active.sort_by_cached_key(|&e| {
+            (edges[e].x_at(bottom), edges[e].x_at(top))
+        });
+        trace!("trapezoid band {}..{}: {} edges", bottom, top, active.len());
+
+        let mut reaching = HashMap::with_capacity(open.len());
+        for pair in active.chunks_exact(2) {
+            let (left, right) = (edges[pair[0]], edges[pair[1]]);
+            let (top_left, top_right) = (left.x_at(top), right.x_at(top));
+            let ix = match open.get(&(pair[0], pair[1])) {
+                Some(&ix) => {
+                    let trapezoid = &mut trapezoids[ix];
+                    trapezoid.top = top;
+                    trapezoid.top_left = top_left;
+                    trapezoid.top_right = top_right;
+                    ix
+                }
+                None => {
+                    let (bottom_left, bottom_right) =
+                        (left.x_at(bottom), right.x_at(bottom));
+                    if bottom_left == bottom_right && top_left == top_right {
+                        continue;
+                    }
+                    trapezoids.push(Trapezoid {
+                        bottom,
+                        top,
+                        bottom_left,
+                        bottom_right,
+                        top_left,
+                        top_right,
+                    });
+                    trapezoids.len() - 1
+                }
+            };
+            reaching.insert((pair[0], pair[1]), ix);
+        }
+        open = reaching;
+    }
+    Ok(trapezoids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractions_compare_by_value() {
+        assert_eq!(cmp_fractions(1, 3, 1, 2), Ordering::Less);
+        assert_eq!(cmp_fractions(2, 4, 1, 2), Ordering::Equal);
+        assert_eq!(cmp_fractions(-1, 3, -1, 2), Ordering::Greater);
+        assert_eq!(cmp_fractions(-7, 2, -3, 1), Ordering::Less);
+        assert_eq!(cmp_fractions(5, 1, 5, 1), Ordering::Equal);
+        // Neighbouring Fibonacci ratios take the longest expansion.
+        assert_eq!(cmp_fractions(144, 89, 89, 55), Ordering::Less);
+        assert_eq!(cmp_fractions(233, 144, 144, 89), Ordering::Greater);
+    }
+
+    #[test]
+    fn fractions_near_the_i128_range_compare_without_overflow() {
+        let (max, min) = (i128::MAX, i128::MIN);
+        assert_eq!(
+            cmp_fractions(max, max - 1, max - 1, max - 2),
+            Ordering::Less
+        );
+        assert_eq!(
+            cmp_fractions(max - 1, max, max - 2, max - 1),
+            Ordering::Greater
+        );
+        assert_eq!(cmp_fractions(min, max, -1, 1), Ordering::Less);
+        assert_eq!(cmp_fractions(min + 1, max, -1, 1), Ordering::Equal);
+        assert_eq!(cmp_fractions(1, max, 0, 1), Ordering::Greater);
+    }
+}
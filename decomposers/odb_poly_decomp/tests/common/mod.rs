@@ -0,0 +1,70 @@
+//! Helpers shared by the integration tests. Each test binary uses only some
+//! of them.
+#![allow(dead_code)]
+
+use doug_geometry::boolean::Region;
+use doug_geometry::shapes::{self, PointLike, Poly};
+use odb_poly_decomp::point::Point;
+
+pub fn poly(points: &[(i32, i32)], layer: u8) -> Poly {
+    Poly {
+        points: points
+            .iter()
+            .map(|&(x, y)| shapes::Point::new(x, y))
+            .collect(),
+        layer,
+    }
+}
+
+/// The points of `poly` in clockwise order, as
+/// [`Decomposer::decompose`](odb_poly_decomp::decomposer::Decomposer::decompose)
+/// needs them.
+pub fn clockwise(poly: &Poly) -> Vec<Point> {
+    let mut points: Vec<Point> = poly.points.iter().map(Point::from).collect();
+    let n = points.len();
+    let doubled_area: i128 = (0..n)
+        .map(|ix| {
+            let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+            p0.x as i128 * p1.y as i128 - p1.x as i128 * p0.y as i128
+        })
+        .sum();
+    if doubled_area > 0 {
+        points.reverse();
+    }
+    points
+}
+
+/// The outlines of the polygons in unions of random rects. Polygons with holes
+/// are skipped, as are those which touch themselves at a corner.
+pub fn random_polygons(count: usize) -> Vec<Poly> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = |n: u64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n) as i32
+    };
+    let mut polys = Vec::new();
+    while polys.len() < count {
+        let rects: Vec<shapes::Rect> = (0..6)
+            .map(|_| {
+                let (x, y) = (next(20), next(20));
+                shapes::Rect {
+                    p0: shapes::Point::new(x, y),
+                    p1: shapes::Point::new(x + 1 + next(8), y + 1 + next(8)),
+                    layer: 1,
+                }
+            })
+            .collect();
+        for poly in Region::from_rects(&rects).polys(1) {
+            let points = &poly.outline.points;
+            let mut corners = points.clone();
+            corners.sort_by_key(|p| (p.x, p.y));
+            corners.dedup();
+            if poly.holes.is_empty() && corners.len() == points.len() {
+                polys.push(poly.outline);
+            }
+        }
+    }
+    polys
+}
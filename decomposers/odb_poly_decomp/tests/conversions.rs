@@ -0,0 +1,138 @@
+mod common;
+
+use common::{poly, random_polygons};
+use doug_geometry::boolean::Region;
+use doug_geometry::shapes::{self, PointLike, Poly};
+use odb_poly_decomp::decomposer::{DecompErr, Decomposer};
+use odb_poly_decomp::point::Point;
+use odb_poly_decomp::rect::Rect;
+
+/// Checks that `rects` tile `poly` exactly: they cover it, and do not
+/// overlap each other.
+fn assert_tiles(poly: &Poly, rects: &[shapes::Rect]) {
+    let region = Region::from_poly(poly).unwrap();
+    assert_eq!(Region::from_rects(rects), region);
+    let area: i128 = rects
+        .iter()
+        .map(|r| (r.p1.x - r.p0.x) as i128 * (r.p1.y - r.p0.y) as i128)
+        .sum();
+    assert_eq!(area, region.area());
+    assert!(rects.iter().all(|r| r.layer == poly.layer));
+}
+
+#[test]
+fn points_convert_both_ways() {
+    for (x, y) in [(0, 0), (-3, 7), (i32::MIN, i32::MAX)] {
+        let point = Point::from(shapes::Point::new(x, y));
+        assert_eq!((point.x, point.y), (x as isize, y as isize));
+        let back: shapes::Point = point.try_into().unwrap();
+        assert_eq!(back, shapes::Point::new(x, y));
+    }
+
+    let too_far = Point::new(i32::MAX as isize + 1, 0);
+    assert!(shapes::Point::try_from(too_far).is_err());
+    let too_low = Point::new(0, i32::MIN as isize - 1);
+    assert!(shapes::Point::try_from(too_low).is_err());
+}
+
+#[test]
+fn rects_convert_onto_a_layer() {
+    let rect = Rect::new(Point::new(-1, 2), Point::new(3, 4));
+    assert_eq!(
+        rect.to_rect(5),
+        Ok(shapes::Rect {
+            p0: shapes::Point::new(-1, 2),
+            p1: shapes::Point::new(3, 4),
+            layer: 5,
+        })
+    );
+
+    let too_wide = Rect::new(Point::new(0, 0), Point::new(1 << 40, 1));
+    assert!(too_wide.to_rect(5).is_err());
+}
+
+#[test]
+fn polygons_decompose_into_rects_which_tile_them() {
+    let shapes = [
+        // An L, clockwise and counterclockwise.
+        poly(&[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2), (2, 0)], 1),
+        poly(&[(0, 0), (2, 0), (2, 2), (1, 2), (1, 1), (0, 1)], 1),
+        // A U, away from the origin.
+        poly(
+            &[
+                (-50, -50),
+                (-20, -50),
+                (-20, -30),
+                (-25, -30),
+                (-25, -45),
+                (-45, -45),
+                (-45, -30),
+                (-50, -30),
+            ],
+            2,
+        ),
+        // A staircase.
+        poly(
+            &[
+                (0, 0),
+                (40, 0),
+                (40, 10),
+                (30, 10),
+                (30, 20),
+                (20, 20),
+                (20, 30),
+                (10, 30),
+                (10, 40),
+                (0, 40),
+            ],
+            3,
+        ),
+        // A plus, with coordinates near the edges of the i32 range.
+        poly(
+            &[
+                (i32::MIN + 10, i32::MIN + 20),
+                (i32::MIN + 20, i32::MIN + 20),
+                (i32::MIN + 20, i32::MIN + 10),
+                (i32::MIN + 30, i32::MIN + 10),
+                (i32::MIN + 30, i32::MIN + 20),
+                (i32::MIN + 40, i32::MIN + 20),
+                (i32::MIN + 40, i32::MIN + 30),
+                (i32::MIN + 30, i32::MIN + 30),
+                (i32::MIN + 30, i32::MIN + 40),
+                (i32::MIN + 20, i32::MIN + 40),
+                (i32::MIN + 20, i32::MIN + 30),
+                (i32::MIN + 10, i32::MIN + 30),
+            ],
+            4,
+        ),
+    ];
+    for shape in shapes.iter() {
+        let rects = Decomposer::decompose_poly(shape).unwrap();
+        assert_tiles(shape, &rects);
+    }
+}
+
+#[test]
+fn random_polygons_decompose_into_rects_which_tile_them() {
+    for shape in random_polygons(200) {
+        let rects = Decomposer::decompose_poly(&shape).unwrap();
+        assert_tiles(&shape, &rects);
+
+        let mut reversed = shape.clone();
+        reversed.points.reverse();
+        let rects = Decomposer::decompose_poly(&reversed).unwrap();
+        assert_tiles(&shape, &rects);
+    }
+}
+
+#[test]
+fn degenerate_polygons_are_rejected() {
+    assert_eq!(
+        Decomposer::decompose_poly(&poly(&[(0, 0), (1, 0)], 1)),
+        Err(DecompErr::NotEnoughPoints)
+    );
+    assert_eq!(
+        Decomposer::decompose_poly(&poly(&[(0, 0), (1, 0), (1, 1)], 1)),
+        Err(DecompErr::IsAlreadySimple)
+    );
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{clockwise, poly, random_polygons};
+use odb_poly_decomp::decomposer::Decomposer;
+use odb_poly_decomp::rect::Rect;
+
+fn staircase() -> Vec<odb_poly_decomp::point::Point> {
+    clockwise(&poly(
+        &[
+            (0, 0),
+            (40, 0),
+            (40, 10),
+            (30, 10),
+            (30, 20),
+            (20, 20),
+            (20, 30),
+            (10, 30),
+            (10, 40),
+            (0, 40),
+        ],
+        1,
+    ))
+}
+
+#[test]
+fn iterated_rects_match_the_decomposition() {
+    let mut shapes = vec![staircase()];
+    shapes.extend(random_polygons(200).iter().map(clockwise));
+    for points in shapes {
+        let rects: Vec<Rect> = Decomposer::decompose_iter(points.clone())
+            .unwrap()
+            .map(|r| r.rect)
+            .collect();
+        assert_eq!(rects, Decomposer::decompose(points).unwrap());
+    }
+}
+
+#[test]
+fn iterated_rects_are_emitted_at_their_upper_side() {
+    let mut rects = Decomposer::decompose_iter(staircase()).unwrap();
+    let mut scanline = isize::MIN;
+    while let Some(r) = rects.next() {
+        assert_eq!(r.scanline, r.rect.right().y);
+        assert!(r.scanline >= scanline);
+        scanline = r.scanline;
+
+        // The edges named are in the geometry, and run along the rect's
+        // sides.
+        let geometry = rects.geometry();
+        assert_eq!(geometry[r.left].src_x(geometry), r.rect.left().x);
+        assert_eq!(geometry[r.right].src_x(geometry), r.rect.right().x);
+    }
+}
+
+#[test]
+fn dropping_the_iterator_early_stops_cleanly() {
+    let all = Decomposer::decompose(staircase()).unwrap();
+    assert_eq!(all.len(), 4);
+    for taken in 0..=all.len() {
+        let rects = Decomposer::decompose_iter(staircase()).unwrap();
+        let first: Vec<Rect> = rects.take(taken).map(|r| r.rect).collect();
+        assert_eq!(first, all[..taken]);
+    }
+
+    // Stopping mid-scanline leaves the geometry as it was split so far.
+    let mut rects = Decomposer::decompose_iter(staircase()).unwrap();
+    let nodes = rects.geometry().len_nodes();
+    rects.next().unwrap();
+    assert!(rects.geometry().len_nodes() >= nodes);
+    drop(rects);
+
+    // A finished iterator stays finished.
+    let mut rects = Decomposer::decompose_iter(staircase()).unwrap();
+    assert_eq!(rects.by_ref().count(), all.len());
+    assert!(rects.next().is_none());
+    assert!(rects.next().is_none());
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{poly, random_polygons};
+use doug_geometry::shapes::{self, PointLike, Poly};
+use odb_poly_decomp::decomposer::{Decomposer, Provenance};
+use odb_poly_decomp::node::NodeOrigin;
+
+/// The ends of side `ix` of `poly`.
+fn side(poly: &Poly, ix: usize) -> (shapes::Point, shapes::Point) {
+    let n = poly.points.len();
+    (poly.points[ix], poly.points[(ix + 1) % n])
+}
+
+fn on_side(poly: &Poly, ix: usize, p: shapes::Point) -> bool {
+    let (a, b) = side(poly, ix);
+    (a.x == b.x && p.x == a.x || a.y == b.y && p.y == a.y)
+        && a.x.min(b.x) <= p.x
+        && p.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= p.y
+        && p.y <= a.y.max(b.y)
+}
+
+/// Checks that `provenance` names the parts of `poly` which `rect` came from.
+fn assert_traces_back(
+    poly: &Poly,
+    rect: &shapes::Rect,
+    provenance: &Provenance,
+) {
+    let (p0, p1) = (rect.p0, rect.p1);
+    let corners = [
+        p0,
+        shapes::Point::new(p1.x, p0.y),
+        p1,
+        shapes::Point::new(p0.x, p1.y),
+    ];
+    for (origin, corner) in provenance.corners().iter().zip(corners) {
+        match *origin {
+            NodeOrigin::Vertex(ix) => assert_eq!(poly.points[ix], corner),
+            NodeOrigin::Split(split) => {
+                assert_eq!(split.scanline, corner.y as isize);
+                assert!(on_side(poly, split.polygon_side, corner));
+            }
+        }
+    }
+
+    // The sides of the rect lie along the sides of the polygon named.
+    for (ix, x) in [(provenance.left_side, p0.x), (provenance.right_side, p1.x)]
+    {
+        assert!(on_side(poly, ix, shapes::Point::new(x, p0.y)));
+        assert!(on_side(poly, ix, shapes::Point::new(x, p1.y)));
+    }
+}
+
+#[test]
+fn provenance_traces_rects_back_to_the_polygon() {
+    for shape in random_polygons(200) {
+        let mut reversed = shape.clone();
+        reversed.points.reverse();
+        for poly in [shape, reversed] {
+            let traced =
+                Decomposer::decompose_poly_with_provenance(&poly).unwrap();
+            let rects: Vec<shapes::Rect> =
+                traced.iter().map(|(rect, _)| *rect).collect();
+            assert_eq!(rects, Decomposer::decompose_poly(&poly).unwrap());
+            for (rect, provenance) in traced.iter() {
+                assert_traces_back(&poly, rect, provenance);
+            }
+        }
+    }
+}
+
+#[test]
+fn splits_are_recorded_for_the_corners_they_made() {
+    // An L, clockwise, whose top corner is at index 3.
+    let l = poly(&[(0, 0), (0, 20), (10, 20), (10, 10), (20, 10), (20, 0)], 1);
+    let traced = Decomposer::decompose_poly_with_provenance(&l).unwrap();
+    assert_eq!(traced.len(), 2);
+
+    // The lower rect is cut from the left side at y = 10, and the upper
+    // one starts there.
+    let (lower, below) = traced.iter().find(|(r, _)| r.p0.y == 0).unwrap();
+    let (upper, above) = traced.iter().find(|(r, _)| r.p0.y == 10).unwrap();
+    assert_eq!(
+        (lower.p1, upper.p1),
+        (shapes::Point::new(20, 10), shapes::Point::new(10, 20))
+    );
+    assert_eq!(below.lower_left, NodeOrigin::Vertex(0));
+    assert_eq!(below.upper_right, NodeOrigin::Vertex(4));
+    assert_eq!(below.lower_right, NodeOrigin::Vertex(5));
+    assert_eq!(above.upper_left, NodeOrigin::Vertex(1));
+    assert_eq!(above.upper_right, NodeOrigin::Vertex(2));
+    assert_eq!(above.lower_right, NodeOrigin::Vertex(3));
+
+    let made: Vec<_> = below.splits().chain(above.splits()).collect();
+    assert!(!made.is_empty());
+    for split in made {
+        assert_eq!((split.polygon_side, split.scanline), (0, 10));
+    }
+
+    // Reversing the points renumbers the vertices and sides to match.
+    let mut reversed = l.clone();
+    reversed.points.reverse();
+    let traced = Decomposer::decompose_poly_with_provenance(&reversed).unwrap();
+    let (_, below) = traced.iter().find(|(r, _)| r.p0.y == 0).unwrap();
+    assert_eq!(below.lower_left, NodeOrigin::Vertex(5));
+    assert_eq!(below.upper_right, NodeOrigin::Vertex(1));
+    assert_eq!(below.left_side, 4);
+    assert_eq!(below.right_side, 0);
+}
@@ -0,0 +1,215 @@
+use odb_poly_decomp::decomposer::DecompErr;
+use odb_poly_decomp::point::Point;
+use odb_poly_decomp::trapezoid::{decompose, Rational, Trapezoid};
+
+fn points(points: &[(isize, isize)]) -> Vec<Point> {
+    points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+}
+
+/// A sum of fractions, kept exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Sum {
+    num: i128,
+    den: i128,
+}
+
+impl Sum {
+    fn add(self, value: Rational, times: i128) -> Sum {
+        let num = self.num * value.den() + value.num() * times * self.den;
+        let r = Rational::new(num, self.den * value.den());
+        Sum {
+            num: r.num(),
+            den: r.den(),
+        }
+    }
+}
+
+/// Twice the area of the trapezoids, exactly.
+fn area_doubled(trapezoids: &[Trapezoid]) -> Sum {
+    trapezoids.iter().fold(Sum { num: 0, den: 1 }, |sum, t| {
+        let height = (t.top - t.bottom) as i128;
+        sum.add(t.bottom_right, height)
+            .add(t.bottom_left, -height)
+            .add(t.top_right, height)
+            .add(t.top_left, -height)
+    })
+}
+
+/// Twice the area of the polygon, by the shoelace formula.
+fn polygon_area_doubled(points: &[Point]) -> Sum {
+    let n = points.len();
+    let twice: i128 = (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x as i128 * b.y as i128 - b.x as i128 * a.y as i128
+        })
+        .sum();
+    Sum {
+        num: twice.abs(),
+        den: 1,
+    }
+}
+
+#[test]
+fn rationals_are_kept_in_lowest_terms() {
+    let r = Rational::new(6, -4);
+    assert_eq!((r.num(), r.den()), (-3, 2));
+    assert_eq!(Rational::new(-6, -4), Rational::new(3, 2));
+    assert_eq!(Rational::new(0, -5), Rational::integer(0));
+    assert_eq!(Rational::new(10, 5).to_integer(), Some(2));
+    assert_eq!(Rational::new(10, 4).to_integer(), None);
+    assert_eq!(Rational::new(i128::MAX, 1).to_integer(), None);
+    assert_eq!(Rational::new(-7, 2).to_f64(), -3.5);
+    assert_eq!(Rational::new(-7, 2).to_string(), "-7/2");
+    assert_eq!(Rational::new(14, 2).to_string(), "7");
+}
+
+#[test]
+fn rationals_order_by_value() {
+    let mut values = vec![
+        Rational::new(1, 2),
+        Rational::new(-1, 3),
+        Rational::integer(1),
+        Rational::new(1, 3),
+        Rational::new(-1, 2),
+        Rational::new(3, 3),
+    ];
+    values.sort();
+    assert_eq!(
+        values,
+        vec![
+            Rational::new(-1, 2),
+            Rational::new(-1, 3),
+            Rational::new(1, 3),
+            Rational::new(1, 2),
+            Rational::integer(1),
+            Rational::integer(1),
+        ]
+    );
+    assert!(Rational::new(i128::MAX, i128::MAX - 1) > Rational::integer(1));
+    assert!(Rational::new(i128::MIN + 1, 3) < Rational::new(i128::MAX, -4));
+}
+
+#[test]
+fn trapezoids_cover_the_area_of_the_polygon() {
+    let shapes = [
+        points(&[(0, 0), (7, 0), (2, 5)]),
+        points(&[(0, -3), (3, 0), (0, 3), (-3, 0)]),
+        // A star, whose sides cross the scanlines at fractions.
+        points(&[
+            (0, 10),
+            (3, 3),
+            (10, 1),
+            (3, -2),
+            (5, -9),
+            (0, -4),
+            (-5, -9),
+            (-3, -2),
+            (-10, 1),
+            (-3, 3),
+        ]),
+        // An L with a slanted side, clockwise.
+        points(&[(0, 0), (0, 9), (4, 9), (4, 4), (11, 1), (11, 0)]),
+    ];
+    for shape in shapes.iter() {
+        let trapezoids = decompose(shape).unwrap();
+        assert_eq!(area_doubled(&trapezoids), polygon_area_doubled(shape));
+
+        let mut reversed = shape.clone();
+        reversed.reverse();
+        assert_eq!(decompose(&reversed).unwrap(), trapezoids);
+    }
+}
+
+#[test]
+fn trapezoids_of_random_polygons_cover_their_area() {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = |n: u64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n) as isize
+    };
+    for _ in 0..200 {
+        // Star shaped polygons, with a vertex in each of a ring of sectors,
+        // cannot cross themselves.
+        let sectors = 3 + next(10);
+        let shape: Vec<Point> = (0..sectors)
+            .map(|i| {
+                let angle = std::f64::consts::TAU
+                    * (i as f64 + next(100) as f64 / 100.0)
+                    / sectors as f64;
+                let radius = 10.0 + next(1000) as f64;
+                Point::new(
+                    (radius * angle.cos()).round() as isize,
+                    (radius * angle.sin()).round() as isize,
+                )
+            })
+            .collect();
+        let trapezoids = decompose(&shape).unwrap();
+        assert_eq!(area_doubled(&trapezoids), polygon_area_doubled(&shape));
+        assert!(trapezoids.iter().all(|t| t.bottom < t.top));
+    }
+}
+
+#[test]
+fn edge_pairs_which_continue_past_a_scanline_stay_one_trapezoid() {
+    // The left leg is cut at y = 5 and y = 10 by the right leg's corners, but
+    // its sides carry on unbroken.
+    let shape = points(&[
+        (0, 0),
+        (5, 0),
+        (5, 10),
+        (10, 10),
+        (10, 5),
+        (15, 5),
+        (15, 20),
+        (0, 20),
+    ]);
+    let trapezoids = decompose(&shape).unwrap();
+    let bands: Vec<(isize, isize)> =
+        trapezoids.iter().map(|t| (t.bottom, t.top)).collect();
+    assert_eq!(bands.len(), 3);
+    assert!(bands.contains(&(0, 10)));
+    assert!(bands.contains(&(5, 10)));
+    assert!(bands.contains(&(10, 20)));
+
+    // A side broken by a vertex starts a new trapezoid, even if it carries on
+    // in a straight line.
+    let shape = points(&[(0, 0), (10, 0), (10, 5), (10, 10), (0, 10)]);
+    assert_eq!(decompose(&shape).unwrap().len(), 2);
+}
+
+#[test]
+fn repeated_points_are_ignored() {
+    let shape = points(&[(0, 0), (0, 0), (4, 0), (2, 3), (2, 3), (0, 0)]);
+    assert_eq!(
+        decompose(&shape).unwrap(),
+        decompose(&points(&[(0, 0), (4, 0), (2, 3)])).unwrap()
+    );
+    assert_eq!(
+        decompose(&points(&[(1, 1), (1, 1), (2, 2), (1, 1)])),
+        Err(DecompErr::NotEnoughPoints)
+    );
+}
+
+#[test]
+fn self_intersecting_polygons_are_rejected() {
+    let shapes = [
+        // A bowtie, whose sides cross between scanlines.
+        points(&[(0, 0), (10, 10), (10, 0), (0, 10)]),
+        // Sides which cross on a scanline.
+        points(&[(0, 0), (10, 10), (10, 5), (0, 5), (0, 10), (10, 0)]),
+        // A vertical side crossing a horizontal one.
+        points(&[(0, 0), (10, 0), (10, 10), (5, 10), (5, -5)]),
+        // Two loops which touch at a vertex.
+        points(&[(0, 0), (5, 5), (10, 0), (10, 10), (5, 5), (0, 10)]),
+        // A vertex resting on another side.
+        points(&[(0, 0), (10, 0), (10, 10), (5, 0), (0, 10)]),
+        // A side which doubles back over the one before it.
+        points(&[(0, 0), (10, 0), (10, 10), (10, 5), (0, 5)]),
+    ];
+    for shape in shapes.iter() {
+        assert_eq!(decompose(shape), Err(DecompErr::SelfIntersecting));
+    }
+}
@@ -1,8 +1,20 @@
-use crate::shapes::{ArchivedShape, Point};
+use crate::shapes::{
+    CoordinateErr, PathLike, Point, PointLike, PolyLike, RectLike, ShapeLike, ShapeRef,
+    VarWidthPathLike,
+};
 use rkyv::{Archive, Deserialize, Serialize};
 
 pub trait CalculateBoundingBox {
-    fn bbox(&self) -> BoundingBox;
+    /// The bounding box, or `None` if there is nothing to bound or the box
+    /// would have no area.
+    fn try_bbox(&self) -> Option<BoundingBox>;
+
+    /// The bounding box. Panics if there is nothing to bound or the box would
+    /// have no area; see [`CalculateBoundingBox::try_bbox`].
+    fn bbox(&self) -> BoundingBox {
+        self.try_bbox()
+            .expect("bounding boxes need something with an area to bound")
+    }
 }
 
 #[derive(Debug)]
@@ -12,6 +24,9 @@ pub struct UnvalidatedBoundingBox {
 }
 
 impl UnvalidatedBoundingBox {
+    /// A box containing nothing, which grows to fit whatever it is updated
+    /// with. Until then its corners are the `i32::MAX`/`i32::MIN` sentinels,
+    /// which must not be validated; see [`UnvalidatedBoundingBox::validate`].
     pub fn invalid() -> Self {
         UnvalidatedBoundingBox {
             min: Point {
@@ -24,44 +39,57 @@ impl UnvalidatedBoundingBox {
             },
         }
     }
-    pub fn update(&mut self, shape: &ArchivedShape) {
-        match shape {
-            ArchivedShape::Rect(r) => {
-                self.min.x = r.p0.x.min(self.min.x);
-                self.min.y = r.p0.y.min(self.min.y);
-                self.max.x = r.p0.x.max(self.max.x);
-                self.max.y = r.p0.y.max(self.max.y);
-                self.min.x = r.p1.x.min(self.min.x);
-                self.min.y = r.p1.y.min(self.min.y);
-                self.max.x = r.p1.x.max(self.max.x);
-                self.max.y = r.p1.y.max(self.max.y);
+    pub fn update<S: ShapeLike>(&mut self, shape: &S) {
+        match shape.shape_ref() {
+            ShapeRef::Rect(r) => {
+                self.update_point(r.p0());
+                self.update_point(r.p1());
             }
-            ArchivedShape::Poly(p) => {
-                for pt in p.points.iter() {
-                    self.min.x = pt.x.min(self.min.x);
-                    self.min.y = pt.y.min(self.min.y);
-                    self.max.x = pt.x.max(self.max.x);
-                    self.max.y = pt.y.max(self.max.y);
+            ShapeRef::Poly(p) => {
+                for &pt in p.points() {
+                    self.update_point(pt);
                 }
             }
-            ArchivedShape::Path(p) => {
-                let pts = p.as_poly();
-
-                for pt in pts.points.iter() {
-                    self.min.x = pt.x.min(self.min.x);
-                    self.min.y = pt.y.min(self.min.y);
-                    self.max.x = pt.x.max(self.max.x);
-                    self.max.y = pt.y.max(self.max.y);
+            ShapeRef::Path(p) => {
+                for pt in p.extent_points() {
+                    self.update_point(pt);
+                }
+            }
+            ShapeRef::VarWidthPath(p) => {
+                for pt in p.extent_points() {
+                    self.update_point(pt);
                 }
             }
         }
     }
+    pub fn update_point<P: PointLike>(&mut self, pt: P) {
+        self.min.x = pt.x().min(self.min.x);
+        self.min.y = pt.y().min(self.min.y);
+        self.max.x = pt.x().max(self.max.x);
+        self.max.y = pt.y().max(self.max.y);
+    }
     pub fn update_bbox(&mut self, bbox: &UnvalidatedBoundingBox) {
         self.min.x = self.min.x.min(bbox.min.x);
         self.min.y = self.min.y.min(bbox.min.y);
         self.max.x = self.max.x.max(bbox.max.x);
         self.max.y = self.max.y.max(bbox.max.y);
     }
+    /// Whether nothing has been added since [`UnvalidatedBoundingBox::invalid`].
+    ///
+    /// Any point added makes `min` no greater than `max`, even at the edges
+    /// of the `i32` range, so this does not mistake a real coordinate for a
+    /// sentinel.
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+    /// The bounding box, or `None` if nothing has been added or it has no
+    /// area.
+    pub fn validate(self) -> Option<BoundingBox> {
+        if self.is_empty() || self.min.x == self.max.x || self.min.y == self.max.y {
+            return None;
+        }
+        Some(BoundingBox::new(self))
+    }
 }
 
 #[derive(
@@ -123,6 +151,8 @@ impl BoundingBox {
         self.max.x = self.max.x.max(bbox.max.x);
         self.max.y = self.max.y.max(bbox.max.y);
     }
+    /// Shift by `p`. Panics if any coordinate overflows; see
+    /// [`BoundingBox::checked_shift`].
     #[must_use = "This used to mutate the BoundingBox it was called on, it now instead returns a new BoundingBox"]
     pub fn shift(&self, p: Point) -> Self {
         Self {
@@ -130,10 +160,36 @@ impl BoundingBox {
             max: self.max.shift(p),
         }
     }
+    /// Shift by `p`, or `None` if any coordinate would overflow.
+    pub fn checked_shift(&self, p: Point) -> Option<Self> {
+        Some(Self {
+            min: self.min.checked_shift(p)?,
+            max: self.max.checked_shift(p)?,
+        })
+    }
+    /// Shift by `p`, reporting overflow as an error.
+    pub fn try_shift(&self, p: Point) -> Result<Self, CoordinateErr> {
+        self.checked_shift(p).ok_or(CoordinateErr::Overflow)
+    }
     pub fn min(&self) -> Point {
         self.min
     }
     pub fn max(&self) -> Point {
         self.max
     }
+    /// The width, which may not fit in an `i32`.
+    pub fn width(&self) -> i64 {
+        self.max.x as i64 - self.min.x as i64
+    }
+    /// The height, which may not fit in an `i32`.
+    pub fn height(&self) -> i64 {
+        self.max.y as i64 - self.min.y as i64
+    }
+    /// The area, which may not fit in an `i64`.
+    pub fn area(&self) -> i128 {
+        self.width() as i128 * self.height() as i128
+    }
+    pub fn perimeter(&self) -> i64 {
+        2 * (self.width() + self.height())
+    }
 }
@@ -0,0 +1,736 @@
+use std::collections::{BTreeMap, HashMap};
+use std::{error::Error, fmt::Display};
+
+use crate::bbox::{BoundingBox, UnvalidatedBoundingBox};
+use crate::shapes::{
+    PathErr, PathLike, Point, PointLike, Poly, PolyLike, Rect, RectLike, Shape, ShapeLike,
+    ShapeRef, VarWidthPathLike,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionErr {
+    /// A polygon edge or path segment is not horizontal or vertical.
+    NotRectilinear,
+    /// A path is malformed, for instance a variable width path without one
+    /// width per segment, or with no segments at all.
+    InvalidPath(PathErr),
+}
+
+impl From<PathErr> for RegionErr {
+    fn from(err: PathErr) -> Self {
+        match err {
+            PathErr::NotRectilinear | PathErr::NotOctilinear => RegionErr::NotRectilinear,
+            err => RegionErr::InvalidPath(err),
+        }
+    }
+}
+
+impl Display for RegionErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for RegionErr {}
+
+/// The boolean operations that can be performed between two [`Region`]s.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BooleanOp {
+    /// Union (OR)
+    Or,
+    /// Intersection (AND)
+    And,
+    /// Difference (AND NOT)
+    Not,
+    /// Symmetric difference (XOR)
+    Xor,
+}
+
+impl BooleanOp {
+    #[inline]
+    fn apply(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            BooleanOp::Or => in_a || in_b,
+            BooleanOp::And => in_a && in_b,
+            BooleanOp::Not => in_a && !in_b,
+            BooleanOp::Xor => in_a != in_b,
+        }
+    }
+}
+
+/// A set of points on the plane covered by rectilinear geometry.
+///
+/// The region is stored as a stack of horizontal slabs, where each slab holds
+/// the disjoint x-intervals covered between its two y coordinates. Slabs never
+/// overlap, and two adjacent slabs never cover the same x-intervals (they are
+/// merged), so the representation of a set of points is unique.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Region {
+    slabs: Vec<Slab>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+struct Slab {
+    y0: i32,
+    y1: i32,
+    // Sorted interval boundaries: the slab covers [xs[0], xs[1]), [xs[2],
+    // xs[3]), and so on.
+    xs: Vec<i32>,
+}
+
+/// A vertical polygon edge, spanning `y0 < y1`. Crossing it from left to
+/// right changes the winding number by `winding`.
+#[derive(Debug, Clone, Copy)]
+struct VerticalEdge {
+    x: i32,
+    y0: i32,
+    y1: i32,
+    winding: i32,
+}
+
+/// A rectilinear polygon, along with any holes it may contain.
+///
+/// Outlines are wound counter-clockwise and holes clockwise, so the covered
+/// area is always on the left of an edge.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PolyWithHoles {
+    pub outline: Poly,
+    pub holes: Vec<Poly>,
+}
+
+impl PolyWithHoles {
+    /// The region covered by the outline, minus its holes.
+    pub fn region(&self) -> Result<Region, RegionErr> {
+        let mut edges = Vec::new();
+        push_poly_edges(&self.outline.points, &mut edges)?;
+        for hole in self.holes.iter() {
+            // Holes are wound in reverse, so that they cancel out the outline.
+            let start = edges.len();
+            push_poly_edges(&hole.points, &mut edges)?;
+            for edge in edges[start..].iter_mut() {
+                edge.winding = -edge.winding;
+            }
+        }
+        Ok(Region::from_edges(edges))
+    }
+
+    /// The region of a polygon traced from a region, which is always
+    /// rectilinear.
+    fn traced_region(&self) -> Region {
+        self.region().expect("traced outlines are rectilinear")
+    }
+}
+
+/// Twice the signed area of a polygon (positive if it is counter-clockwise).
+pub(crate) fn signed_area_doubled<P: PointLike>(points: &[P]) -> i128 {
+    let n = points.len();
+    (0..n)
+        .map(|ix| {
+            let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+            p0.x() as i128 * p1.y() as i128 - p1.x() as i128 * p0.y() as i128
+        })
+        .sum()
+}
+
+fn push_poly_edges<P: PointLike>(
+    points: &[P],
+    edges: &mut Vec<VerticalEdge>,
+) -> Result<(), RegionErr> {
+    let n = points.len();
+    if n < 3 {
+        return Ok(());
+    }
+    if (0..n).any(|ix| {
+        let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+        p0.x() != p1.x() && p0.y() != p1.y()
+    }) {
+        return Err(RegionErr::NotRectilinear);
+    }
+    // Edges are oriented so that the interior of the polygon always has a
+    // winding number of +1, regardless of how its points were given.
+    let orientation = if signed_area_doubled(points) < 0 {
+        -1
+    } else {
+        1
+    };
+
+    for ix in 0..n {
+        let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+        if p0.x() != p1.x() || p0.y() == p1.y() {
+            continue;
+        }
+        // Going down a counter-clockwise polygon means the interior is to the
+        // east, so crossing this edge from left to right enters the polygon.
+        let (y0, y1, winding) = if p0.y() > p1.y() {
+            (p1.y(), p0.y(), orientation)
+        } else {
+            (p0.y(), p1.y(), -orientation)
+        };
+        edges.push(VerticalEdge {
+            x: p0.x(),
+            y0,
+            y1,
+            winding,
+        });
+    }
+    Ok(())
+}
+
+fn push_rect_edges<R: RectLike>(r: &R, edges: &mut Vec<VerticalEdge>) {
+    let (p0, p1) = (r.p0(), r.p1());
+    let (x0, x1) = (p0.x().min(p1.x()), p0.x().max(p1.x()));
+    let (y0, y1) = (p0.y().min(p1.y()), p0.y().max(p1.y()));
+    if x0 == x1 || y0 == y1 {
+        return;
+    }
+    edges.push(VerticalEdge {
+        x: x0,
+        y0,
+        y1,
+        winding: 1,
+    });
+    edges.push(VerticalEdge {
+        x: x1,
+        y0,
+        y1,
+        winding: -1,
+    });
+}
+
+fn push_shape_edges<S: ShapeLike>(
+    shape: &S,
+    edges: &mut Vec<VerticalEdge>,
+) -> Result<(), RegionErr> {
+    match shape.shape_ref() {
+        ShapeRef::Rect(r) => push_rect_edges(r, edges),
+        ShapeRef::Poly(p) => push_poly_edges(p.points(), edges)?,
+        ShapeRef::Path(p) => {
+            for r in p.segment_rects()?.iter() {
+                push_rect_edges(r, edges);
+            }
+        }
+        ShapeRef::VarWidthPath(p) => {
+            for r in p.segment_rects()?.iter() {
+                push_rect_edges(r, edges);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge two sorted interval boundary lists, keeping those parts for which
+/// `op` holds.
+fn combine_intervals(a: &[i32], b: &[i32], op: BooleanOp) -> Vec<i32> {
+    let mut result = Vec::with_capacity(a.len() + b.len());
+    let (mut ia, mut ib) = (0, 0);
+    let (mut in_a, mut in_b, mut inside) = (false, false, false);
+
+    while ia < a.len() || ib < b.len() {
+        let x = match (a.get(ia), b.get(ib)) {
+            (Some(&xa), Some(&xb)) => xa.min(xb),
+            (Some(&xa), None) => xa,
+            (None, Some(&xb)) => xb,
+            (None, None) => unreachable!(),
+        };
+        while a.get(ia) == Some(&x) {
+            in_a = !in_a;
+            ia += 1;
+        }
+        while b.get(ib) == Some(&x) {
+            in_b = !in_b;
+            ib += 1;
+        }
+        if op.apply(in_a, in_b) != inside {
+            inside = !inside;
+            result.push(x);
+        }
+    }
+
+    result
+}
+
+impl Region {
+    /// An empty region.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_rect(rect: &Rect) -> Self {
+        let mut edges = Vec::with_capacity(2);
+        push_rect_edges(rect, &mut edges);
+        Self::from_edges(edges)
+    }
+
+    /// The region covered by a rectilinear polygon, which may be wound either
+    /// way.
+    pub fn from_poly(poly: &Poly) -> Result<Self, RegionErr> {
+        let mut edges = Vec::with_capacity(poly.points.len() / 2);
+        push_poly_edges(&poly.points, &mut edges)?;
+        Ok(Self::from_edges(edges))
+    }
+
+    /// The union of the regions covered by each of the given shapes, owned or
+    /// archived, ignoring their layers. Fails if any of the shapes is not
+    /// rectilinear, or is a malformed path.
+    pub fn from_shapes<'a, S: ShapeLike + 'a, I: IntoIterator<Item = &'a S>>(
+        shapes: I,
+    ) -> Result<Self, RegionErr> {
+        let mut edges = Vec::new();
+        for shape in shapes {
+            push_shape_edges(shape, &mut edges)?;
+        }
+        Ok(Self::from_edges(edges))
+    }
+
+    /// The union of the regions covered by those of the given shapes which are
+    /// rectilinear, along with the positions in `shapes` of those which are
+    /// not, or are malformed paths, so that callers can handle them some other
+    /// way.
+    pub fn from_rectilinear_shapes<'a, S: ShapeLike + 'a, I: IntoIterator<Item = &'a S>>(
+        shapes: I,
+    ) -> (Self, Vec<usize>) {
+        let mut edges = Vec::new();
+        let mut left_out = Vec::new();
+        for (ix, shape) in shapes.into_iter().enumerate() {
+            let start = edges.len();
+            if push_shape_edges(shape, &mut edges).is_err() {
+                edges.truncate(start);
+                left_out.push(ix);
+            }
+        }
+        (Self::from_edges(edges), left_out)
+    }
+
+    /// The union of the regions covered by those shapes which are on `layer`.
+    pub fn from_layer<S: ShapeLike>(shapes: &[S], layer: u8) -> Result<Self, RegionErr> {
+        Self::from_shapes(shapes.iter().filter(|s| s.layer() == layer))
+    }
+
+    /// The union of a set of (possibly overlapping) rects, ignoring their
+    /// layers.
+    pub fn from_rects<'a, I: IntoIterator<Item = &'a Rect>>(rects: I) -> Self {
+        let mut edges = Vec::new();
+        for rect in rects {
+            push_rect_edges(rect, &mut edges);
+        }
+        Self::from_edges(edges)
+    }
+
+    /// Sweep a scanline upwards over a set of vertical edges, keeping those
+    /// parts of each band with a non-zero winding number.
+    fn from_edges(mut edges: Vec<VerticalEdge>) -> Self {
+        let mut ys: Vec<i32> = edges.iter().flat_map(|e| [e.y0, e.y1]).collect();
+        ys.sort_unstable();
+        ys.dedup();
+
+        edges.sort_unstable_by_key(|e| e.y0);
+
+        let mut region = Region::new();
+        let mut active: Vec<VerticalEdge> = Vec::new();
+        let mut next_edge = 0;
+
+        for band in ys.windows(2) {
+            let (y0, y1) = (band[0], band[1]);
+
+            active.retain(|e| e.y1 > y0);
+            while let Some(&edge) = edges.get(next_edge).filter(|e| e.y0 == y0) {
+                let ix = active.partition_point(|e| e.x <= edge.x);
+                active.insert(ix, edge);
+                next_edge += 1;
+            }
+
+            let mut xs = Vec::new();
+            let mut winding = 0;
+            let mut ix = 0;
+            while ix < active.len() {
+                let x = active[ix].x;
+                let was_inside = winding != 0;
+                while ix < active.len() && active[ix].x == x {
+                    winding += active[ix].winding;
+                    ix += 1;
+                }
+                if was_inside != (winding != 0) {
+                    xs.push(x);
+                }
+            }
+
+            region.push_slab(y0, y1, xs);
+        }
+
+        region
+    }
+
+    /// Push a slab on top of the region, merging it into the slab below if
+    /// they cover the same intervals.
+    fn push_slab(&mut self, y0: i32, y1: i32, xs: Vec<i32>) {
+        if xs.is_empty() {
+            return;
+        }
+        if let Some(last) = self.slabs.last_mut() {
+            if last.y1 == y0 && last.xs == xs {
+                last.y1 = y1;
+                return;
+            }
+        }
+        self.slabs.push(Slab { y0, y1, xs });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slabs.is_empty()
+    }
+
+    /// The area covered, which may not fit in an `i64`.
+    pub fn area(&self) -> i128 {
+        self.slabs
+            .iter()
+            .map(|s| {
+                let width: i128 = s.xs.chunks(2).map(|i| i[1] as i128 - i[0] as i128).sum();
+                width * (s.y1 as i128 - s.y0 as i128)
+            })
+            .sum()
+    }
+
+    /// The total length of the boundary, including the boundaries of holes.
+    pub fn perimeter(&self) -> i128 {
+        self.boundary_edges()
+            .into_iter()
+            .map(|(a, b)| (b.x as i128 - a.x as i128).abs() + (b.y as i128 - a.y as i128).abs())
+            .sum()
+    }
+
+    /// The bounding box of the region, or `None` if it is empty.
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        for s in self.slabs.iter() {
+            bbox.min.x = bbox.min.x.min(s.xs[0]);
+            bbox.max.x = bbox.max.x.max(s.xs[s.xs.len() - 1]);
+            bbox.min.y = bbox.min.y.min(s.y0);
+            bbox.max.y = bbox.max.y.max(s.y1);
+        }
+        bbox.validate()
+    }
+
+    pub fn boolean(&self, other: &Region, op: BooleanOp) -> Region {
+        let mut ys: Vec<i32> = self
+            .slabs
+            .iter()
+            .chain(other.slabs.iter())
+            .flat_map(|s| [s.y0, s.y1])
+            .collect();
+        ys.sort_unstable();
+        ys.dedup();
+
+        let mut region = Region::new();
+        let (mut ia, mut ib) = (0, 0);
+        let empty = Vec::new();
+
+        for band in ys.windows(2) {
+            let (y0, y1) = (band[0], band[1]);
+
+            while self.slabs.get(ia).is_some_and(|s| s.y1 <= y0) {
+                ia += 1;
+            }
+            while other.slabs.get(ib).is_some_and(|s| s.y1 <= y0) {
+                ib += 1;
+            }
+            let xs_a = self
+                .slabs
+                .get(ia)
+                .filter(|s| s.y0 <= y0)
+                .map_or(&empty, |s| &s.xs);
+            let xs_b = other
+                .slabs
+                .get(ib)
+                .filter(|s| s.y0 <= y0)
+                .map_or(&empty, |s| &s.xs);
+
+            region.push_slab(y0, y1, combine_intervals(xs_a, xs_b, op));
+        }
+
+        region
+    }
+
+    pub fn union(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Or)
+    }
+
+    pub fn intersection(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::And)
+    }
+
+    pub fn difference(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Not)
+    }
+
+    pub fn xor(&self, other: &Region) -> Region {
+        self.boolean(other, BooleanOp::Xor)
+    }
+
+    /// Decompose the region into disjoint rectangles on `layer`. Each rect
+    /// spans the full height of one of the region's slabs.
+    pub fn rects(&self, layer: u8) -> Vec<Rect> {
+        self.slabs
+            .iter()
+            .flat_map(|s| {
+                s.xs.chunks(2).map(move |i| Rect {
+                    p0: Point::new(i[0], s.y0),
+                    p1: Point::new(i[1], s.y1),
+                    layer,
+                })
+            })
+            .collect()
+    }
+
+    /// The region mirrored about the line `x = y`. Its slabs hold the vertical
+    /// chords of the original region.
+    pub fn transpose(&self) -> Region {
+        let rects: Vec<Rect> = self
+            .rects(0)
+            .into_iter()
+            .map(|r| Rect {
+                p0: Point::new(r.p0.y, r.p0.x),
+                p1: Point::new(r.p1.y, r.p1.x),
+                layer: r.layer,
+            })
+            .collect();
+        Region::from_rects(rects.iter())
+    }
+
+    /// The length of the shortest horizontal or vertical chord through the
+    /// region, or `None` if it is empty.
+    pub fn min_width(&self) -> Option<i32> {
+        let widths = |r: &Region| {
+            r.slabs
+                .iter()
+                .flat_map(|s| s.xs.chunks(2).map(|i| i[1] - i[0]))
+                .min()
+        };
+        match (widths(self), widths(&self.transpose())) {
+            (Some(w), Some(h)) => Some(w.min(h)),
+            _ => None,
+        }
+    }
+
+    /// The region as shapes on `layer`: a [`Poly`] for each polygon without
+    /// holes, and a set of [`Rect`]s for each polygon with holes (which a
+    /// [`Poly`] cannot represent).
+    pub fn shapes(&self, layer: u8) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+        for poly in self.polys(layer) {
+            if poly.holes.is_empty() {
+                shapes.push(Shape::Poly(poly.outline));
+            } else {
+                shapes.extend(
+                    poly.traced_region()
+                        .rects(layer)
+                        .into_iter()
+                        .map(Shape::Rect),
+                );
+            }
+        }
+        shapes
+    }
+
+    /// Split the region into its connected components. Components which only
+    /// touch at a corner are considered separate.
+    pub fn components(&self) -> Vec<Region> {
+        self.polys(0).iter().map(|p| p.traced_region()).collect()
+    }
+
+    /// The boundary edges of the region, directed so that the region is on
+    /// their left.
+    fn boundary_edges(&self) -> Vec<(Point, Point)> {
+        let mut edges = Vec::new();
+        let empty = Vec::new();
+
+        for (ix, slab) in self.slabs.iter().enumerate() {
+            for i in slab.xs.chunks(2) {
+                edges.push((Point::new(i[0], slab.y1), Point::new(i[0], slab.y0)));
+                edges.push((Point::new(i[1], slab.y0), Point::new(i[1], slab.y1)));
+            }
+
+            // Horizontal edges along the bottom of this slab.
+            let below = match ix.checked_sub(1).map(|b| &self.slabs[b]) {
+                Some(b) if b.y1 == slab.y0 => &b.xs,
+                _ => &empty,
+            };
+            push_horizontal_edges(slab.y0, &slab.xs, below, &mut edges);
+
+            // Horizontal edges along the top of this slab, if nothing sits
+            // directly on top of it. (Otherwise, they are handled as the bottom
+            // edges of the next slab.)
+            if self.slabs.get(ix + 1).is_none_or(|a| a.y0 != slab.y1) {
+                push_horizontal_edges(slab.y1, &empty, &slab.xs, &mut edges);
+            }
+        }
+
+        edges
+    }
+
+    /// Trace the boundary of the region into polygons on `layer`, assigning
+    /// each hole to the polygon that contains it.
+    ///
+    /// Polygons which only touch at a corner are kept apart, and collinear
+    /// points are removed.
+    pub fn polys(&self, layer: u8) -> Vec<PolyWithHoles> {
+        let mut outlines = Vec::new();
+        let mut holes = Vec::new();
+
+        for points in trace_loops(self.boundary_edges()) {
+            let area = signed_area_doubled(&points);
+            let poly = Poly { points, layer };
+            if area > 0 {
+                outlines.push((
+                    area,
+                    PolyWithHoles {
+                        outline: poly,
+                        holes: Vec::new(),
+                    },
+                ));
+            } else {
+                holes.push(poly);
+            }
+        }
+
+        // Sorting by area means that the first outline found to contain a hole
+        // is the innermost one.
+        outlines.sort_by_key(|(area, _)| *area);
+        for hole in holes {
+            let corner = lowest_leftmost(&hole.points);
+            if let Some((_, owner)) = outlines
+                .iter_mut()
+                .find(|(_, p)| contains_doubled(&p.outline.points, corner))
+            {
+                owner.holes.push(hole);
+            }
+        }
+
+        let mut polys: Vec<PolyWithHoles> = outlines.into_iter().map(|(_, p)| p).collect();
+        polys.sort_by_key(|p| {
+            let p = lowest_leftmost(&p.outline.points);
+            (p.y, p.x)
+        });
+        polys
+    }
+}
+
+/// Merge a set of possibly overlapping rects into the outlines of their union,
+/// layer by layer. This is the inverse of decomposing polygons into rects.
+///
+/// Each outline is returned with its holes, without collinear points, and with
+/// the winding described on [`PolyWithHoles`]. Results are sorted by layer.
+pub fn merge_rects(rects: &[Rect]) -> Vec<PolyWithHoles> {
+    let mut layers: BTreeMap<u8, Vec<&Rect>> = BTreeMap::new();
+    for rect in rects {
+        layers.entry(rect.layer).or_default().push(rect);
+    }
+    layers
+        .into_iter()
+        .flat_map(|(layer, rects)| Region::from_rects(rects).polys(layer))
+        .collect()
+}
+
+/// Push the horizontal edges at `y`, between the intervals covered directly
+/// above (`above`) and directly below (`below`).
+fn push_horizontal_edges(y: i32, above: &[i32], below: &[i32], edges: &mut Vec<(Point, Point)>) {
+    let only_above = combine_intervals(above, below, BooleanOp::Not);
+    let only_below = combine_intervals(below, above, BooleanOp::Not);
+
+    for i in only_above.chunks(2) {
+        edges.push((Point::new(i[0], y), Point::new(i[1], y)));
+    }
+    for i in only_below.chunks(2) {
+        edges.push((Point::new(i[1], y), Point::new(i[0], y)));
+    }
+}
+
+fn lowest_leftmost(points: &[Point]) -> Point {
+    *points.iter().min_by_key(|p| (p.y, p.x)).unwrap()
+}
+
+/// Check if a rectilinear polygon contains the point half a unit above and to
+/// the right of `corner`. Such a point can never lie on the polygon boundary.
+fn contains_doubled(points: &[Point], corner: Point) -> bool {
+    let (tx, ty) = (2 * corner.x as i64 + 1, 2 * corner.y as i64 + 1);
+    let n = points.len();
+    let mut inside = false;
+    for ix in 0..n {
+        let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+        let (y0, y1) = (2 * p0.y.min(p1.y) as i64, 2 * p0.y.max(p1.y) as i64);
+        if p0.x == p1.x && y0 < ty && ty < y1 && 2 * p0.x as i64 > tx {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// The direction of travel along an axis-aligned edge, as a unit vector.
+fn unit_direction(p0: Point, p1: Point) -> (i32, i32) {
+    ((p1.x - p0.x).signum(), (p1.y - p0.y).signum())
+}
+
+/// Link directed boundary edges into closed loops of points, dropping
+/// collinear points.
+///
+/// Where two loops touch at a single point, the tightest left turn is taken, so
+/// that the loops are kept apart.
+fn trace_loops(edges: Vec<(Point, Point)>) -> Vec<Vec<Point>> {
+    let mut outgoing: HashMap<Point, Vec<usize>> = HashMap::with_capacity(edges.len());
+    for (ix, (p0, _)) in edges.iter().enumerate() {
+        outgoing.entry(*p0).or_default().push(ix);
+    }
+
+    let next = |ix: usize| -> usize {
+        let (p0, p1) = edges[ix];
+        let (dx, dy) = unit_direction(p0, p1);
+        // Rank candidates by how far they turn left: left, straight, right.
+        *outgoing[&p1]
+            .iter()
+            .min_by_key(|&&jx| {
+                let (ex, ey) = unit_direction(edges[jx].0, edges[jx].1);
+                match dx * ey - dy * ex {
+                    1 => 0,
+                    0 => 1,
+                    _ => 2,
+                }
+            })
+            .unwrap()
+    };
+
+    let mut visited = vec![false; edges.len()];
+    let mut loops = Vec::new();
+
+    for start in 0..edges.len() {
+        if visited[start] {
+            continue;
+        }
+
+        let mut points = Vec::new();
+        let mut ix = start;
+        loop {
+            visited[ix] = true;
+            let jx = next(ix);
+            if unit_direction(edges[ix].0, edges[ix].1) != unit_direction(edges[jx].0, edges[jx].1)
+            {
+                points.push(edges[ix].1);
+            }
+            ix = jx;
+            if ix == start {
+                break;
+            }
+        }
+
+        // Start every loop at its lowest, left-most point, so that equal
+        // regions always produce equal polygons.
+        let first = points
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, p)| (p.y, p.x))
+            .map(|(ix, _)| ix)
+            .unwrap();
+        points.rotate_left(first);
+        loops.push(points);
+    }
+
+    loops
+}
@@ -0,0 +1,245 @@
+use std::{error::Error, fmt::Display};
+
+use crate::boolean::Region;
+use crate::shapes::{
+    extend_point, Path, PathEndCap, PathLike, Point, PointLike, Poly, Rect, Shape, SideOffsets,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CenterlineErr {
+    NotEnoughPoints,
+    NotRectilinear,
+    /// The polygon is not the outline of any constant width path.
+    NotPathLike,
+}
+
+impl Display for CenterlineErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for CenterlineErr {}
+
+/// Remove repeated points and collinear points from a closed outline, and
+/// check that its edges are rectilinear.
+fn normalize_outline(points: &[Point]) -> Result<Vec<Point>, CenterlineErr> {
+    let mut outline: Vec<Point> = Vec::with_capacity(points.len());
+    for &p in points {
+        if outline.last() != Some(&p) {
+            outline.push(p);
+        }
+    }
+    if outline.len() > 1 && outline.first() == outline.last() {
+        outline.pop();
+    }
+    if outline.len() < 4 {
+        return Err(CenterlineErr::NotEnoughPoints);
+    }
+
+    let n = outline.len();
+    if (0..n).any(|ix| {
+        let (p0, p1) = (outline[ix], outline[(ix + 1) % n]);
+        p0.x != p1.x && p0.y != p1.y
+    }) {
+        return Err(CenterlineErr::NotRectilinear);
+    }
+
+    // A point is collinear if the edges on either side of it are both
+    // horizontal or both vertical.
+    let collinear = |outline: &[Point], ix: usize| {
+        let n = outline.len();
+        let (p0, p1, p2) = (
+            outline[(ix + n - 1) % n],
+            outline[ix],
+            outline[(ix + 1) % n],
+        );
+        (p0.x == p1.x && p1.x == p2.x) || (p0.y == p1.y && p1.y == p2.y)
+    };
+    let mut ix = 0;
+    while ix < outline.len() && outline.len() >= 4 {
+        if collinear(&outline, ix) {
+            outline.remove(ix);
+            ix = ix.saturating_sub(1);
+        } else {
+            ix += 1;
+        }
+    }
+    if outline.len() < 4 {
+        return Err(CenterlineErr::NotEnoughPoints);
+    }
+    Ok(outline)
+}
+
+/// The centerline of the path whose flush begin cap would be the edge ending at
+/// `outline[start]`. This has to be checked against the outline, since not
+/// every outline can be split into two sides anywhere.
+fn centerline_from(outline: &[Point], start: usize) -> Path {
+    let n = outline.len();
+    let num_points = n / 2;
+    let cap = (outline[(start + n - 1) % n], outline[start]);
+    let width = (cap.0.x - cap.1.x).abs() + (cap.0.y - cap.1.y).abs();
+    let lo = width / 2;
+
+    // One side runs forwards from the begin cap and the other backwards, and
+    // the nth points of the two sides lie on either side of the nth point of
+    // the centerline.
+    let points = (0..num_points)
+        .map(|ix| {
+            let a = outline[(start + ix) % n];
+            let b = outline[(start + n - 1 - ix) % n];
+            let center = |a: i32, b: i32| if a == b { a } else { a.min(b) + lo };
+            Point::new(center(a.x, b.x), center(a.y, b.y))
+        })
+        .collect();
+    Path {
+        points,
+        width: width as u32,
+        layer: 0,
+        end_cap: PathEndCap::Flush,
+    }
+}
+
+/// The points ending those edges of an outline which could be the end caps of
+/// a path.
+///
+/// Both corners of an end cap turn the way the outline winds. So do those of
+/// the outer edge of a U-turn, but that is more than twice as long as the
+/// path is wide, while the end caps are exactly as long, so the end caps are
+/// the shortest such edges.
+fn end_cap_candidates(outline: &[Point]) -> Vec<usize> {
+    let n = outline.len();
+    let turn = |ix: usize| {
+        let (p0, p1, p2) = (
+            outline[(ix + n - 1) % n],
+            outline[ix],
+            outline[(ix + 1) % n],
+        );
+        (p1.x as i64 - p0.x as i64) * (p2.y as i64 - p1.y as i64)
+            - (p1.y as i64 - p0.y as i64) * (p2.x as i64 - p1.x as i64)
+    };
+    let winding: i64 = (0..n).map(|ix| turn(ix).signum()).sum();
+    let convex = |ix: usize| turn(ix).signum() == winding.signum();
+    let length = |ix: usize| {
+        let (p0, p1) = (outline[(ix + n - 1) % n], outline[ix]);
+        (p0.x as i64 - p1.x as i64).abs() + (p0.y as i64 - p1.y as i64).abs()
+    };
+
+    let caps: Vec<usize> = (0..n)
+        .filter(|&ix| convex((ix + n - 1) % n) && convex(ix))
+        .collect();
+    let Some(shortest) = caps.iter().map(|&ix| length(ix)).min() else {
+        return caps;
+    };
+    caps.into_iter()
+        .filter(|&ix| length(ix) == shortest)
+        .collect()
+}
+
+/// The same path with half width end caps in place of flush ones, if its end
+/// segments are long enough to be shortened by half its width.
+fn with_half_width_caps(path: &Path) -> Option<Path> {
+    let direction = |p0: &Point, p1: &Point| p0.oct_direction_to(p1)?.as_rect_direction();
+    let last = path.points.len() - 1;
+    let first_direction = direction(&path.points[0], &path.points[1])?;
+    let last_direction = direction(&path.points[last - 1], &path.points[last])?;
+    let (begin, end) = PathEndCap::HalfWidth.extensions(
+        SideOffsets::new(path.width),
+        first_direction,
+        last_direction,
+    );
+
+    let mut points = path.points.clone();
+    points[0] = extend_point(points[0], first_direction, begin);
+    points[last] = extend_point(points[last], last_direction, -end);
+    let shortened = |p0: &Point, p1: &Point, d| direction(p0, p1) == Some(d);
+    (shortened(&points[0], &points[1], first_direction)
+        && shortened(&points[last - 1], &points[last], last_direction))
+    .then(|| Path {
+        points,
+        end_cap: PathEndCap::HalfWidth,
+        ..path.clone()
+    })
+}
+
+/// Recover the centerline of a rectilinear polygon which is the outline of a
+/// constant width path.
+///
+/// If several paths have the polygon as their outline, as for a rectangle, the
+/// narrowest is returned. Since the end caps of a path cannot be told apart
+/// from its end segments, paths are given half width end caps where their end
+/// segments are long enough, as wires usually are drawn, and flush ones
+/// otherwise.
+pub fn poly_to_path(poly: &Poly) -> Result<Path, CenterlineErr> {
+    let outline = normalize_outline(&poly.points)?;
+    // A path with n points has n vertices on each side.
+    if outline.len() % 2 != 0 {
+        return Err(CenterlineErr::NotPathLike);
+    }
+
+    let region = Region::from_poly(poly).map_err(|_| CenterlineErr::NotRectilinear)?;
+    let traces_poly = |path: &Path| {
+        path.as_poly()
+            .is_ok_and(|rebuilt| Region::from_poly(&rebuilt).is_ok_and(|r| r == region))
+    };
+
+    // Only a rectangle has more than two candidates for its end caps, since
+    // any of its sides could be one.
+    let caps = end_cap_candidates(&outline);
+    if caps.len() > 2 && outline.len() > 4 {
+        return Err(CenterlineErr::NotPathLike);
+    }
+    let path = caps
+        .into_iter()
+        .map(|start| centerline_from(&outline, start))
+        .filter(|path| path.width > 0 && traces_poly(path))
+        .min_by_key(|path| path.width)
+        .map(|path| Path {
+            layer: poly.layer,
+            ..path
+        })
+        .ok_or(CenterlineErr::NotPathLike)?;
+
+    Ok(match with_half_width_caps(&path) {
+        Some(half_width) if traces_poly(&half_width) => half_width,
+        _ => path,
+    })
+}
+
+impl Poly {
+    /// See [`poly_to_path`].
+    pub fn to_path(&self) -> Result<Path, CenterlineErr> {
+        poly_to_path(self)
+    }
+}
+
+impl Rect {
+    /// The two point path along the longer side of the rect, with end caps
+    /// chosen as for [`poly_to_path`].
+    pub fn to_path(&self) -> Result<Path, CenterlineErr> {
+        poly_to_path(&Poly {
+            points: vec![
+                self.p0,
+                Point::new(self.p1.x, self.p0.y),
+                self.p1,
+                Point::new(self.p0.x, self.p1.y),
+            ],
+            layer: self.layer,
+        })
+    }
+}
+
+/// Replace every polygon that is the outline of a path with that path,
+/// leaving other shapes as they are.
+///
+/// Rects are left alone, since they take less space than the two point paths
+/// they would become, and [`Rect::to_path`] recovers those where needed.
+pub fn recover_paths(shapes: &[Shape]) -> Vec<Shape> {
+    shapes
+        .iter()
+        .map(|shape| match shape {
+            Shape::Poly(p) => p.to_path().map_or_else(|_| shape.clone(), Shape::Path),
+            _ => shape.clone(),
+        })
+        .collect()
+}
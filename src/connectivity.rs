@@ -0,0 +1,355 @@
+use std::{error::Error, fmt::Display};
+
+use crate::shapes::{
+    Path, PathEndCap, PathErr, PathLike, Point, PointLike, Shape, ShapeLike, VarWidthPathLike,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectivityErr {
+    /// The path at this index in the shapes covers no well defined area.
+    MalformedPath(usize, PathErr),
+}
+
+impl Display for ConnectivityErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for ConnectivityErr {}
+
+/// A layer of cuts, such as contacts or vias, which joins shapes on the layers
+/// below and above it wherever they touch a cut.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Copy,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct ViaLayer {
+    pub cut: u8,
+    pub bottom: u8,
+    pub top: u8,
+}
+
+/// A piece of text placed on a layer, naming the net of the shapes under it.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Label {
+    pub text: String,
+    pub position: Point,
+    pub layer: u8,
+}
+
+/// A set of shapes which are electrically connected.
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone)]
+pub struct Net {
+    /// Indices of the shapes in the net, in increasing order.
+    pub shapes: Vec<usize>,
+    /// The distinct texts of the labels on the net, in sorted order. A net
+    /// with more than one is a short between the nets the labels name.
+    pub labels: Vec<String>,
+}
+
+/// Part of the area covered by a shape, as a closed polygon.
+struct Piece {
+    shape: usize,
+    layer: u8,
+    points: Vec<Point>,
+    min: Point,
+    max: Point,
+    /// Whether the piece is an axis-aligned rectangle, which touches another
+    /// such piece whenever their bounding boxes do.
+    is_rect: bool,
+}
+
+impl Piece {
+    fn new(shape: usize, layer: u8, points: Vec<Point>, is_rect: bool) -> Self {
+        let min = Point::new(
+            points.iter().map(|p| p.x).min().unwrap(),
+            points.iter().map(|p| p.y).min().unwrap(),
+        );
+        let max = Point::new(
+            points.iter().map(|p| p.x).max().unwrap(),
+            points.iter().map(|p| p.y).max().unwrap(),
+        );
+        Piece {
+            shape,
+            layer,
+            points,
+            min,
+            max,
+            is_rect,
+        }
+    }
+
+    fn from_rect(shape: usize, layer: u8, p0: Point, p1: Point) -> Self {
+        let points = vec![p0, Point::new(p1.x, p0.y), p1, Point::new(p0.x, p1.y)];
+        Piece::new(shape, layer, points, true)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.points.len();
+        (0..n).map(move |ix| (self.points[ix], self.points[(ix + 1) % n]))
+    }
+
+    /// Whether the piece covers `p`, counting its boundary.
+    fn contains(&self, p: Point) -> bool {
+        if p.x < self.min.x || p.x > self.max.x || p.y < self.min.y || p.y > self.max.y {
+            return false;
+        }
+        if self.is_rect || self.edges().any(|(a, b)| on_segment(a, b, p)) {
+            return true;
+        }
+        // Count the edges crossed by a ray running from `p` towards +x.
+        let crossings = self
+            .edges()
+            .filter(|&(a, b)| {
+                (a.y > p.y) != (b.y > p.y) && {
+                    let side = orientation(a, b, p);
+                    if b.y > a.y {
+                        side > 0
+                    } else {
+                        side < 0
+                    }
+                }
+            })
+            .count();
+        crossings % 2 == 1
+    }
+
+    /// Whether the pieces share any point, even if only a corner.
+    fn touches(&self, other: &Piece) -> bool {
+        if self.max.x < other.min.x
+            || other.max.x < self.min.x
+            || self.max.y < other.min.y
+            || other.max.y < self.min.y
+        {
+            return false;
+        }
+        if self.is_rect && other.is_rect {
+            return true;
+        }
+        // If the boundaries do not meet, one piece is either inside the other
+        // or apart from it.
+        self.edges()
+            .any(|(a, b)| other.edges().any(|(c, d)| segments_meet(a, b, c, d)))
+            || other.contains(self.points[0])
+            || self.contains(other.points[0])
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`: positive if `c` is to
+/// the left of the line from `a` to `b`.
+fn orientation(a: Point, b: Point, c: Point) -> i128 {
+    let (abx, aby) = (b.x as i128 - a.x as i128, b.y as i128 - a.y as i128);
+    let (acx, acy) = (c.x as i128 - a.x as i128, c.y as i128 - a.y as i128);
+    abx * acy - aby * acx
+}
+
+fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    orientation(a, b, p) == 0
+        && p.x >= a.x.min(b.x)
+        && p.x <= a.x.max(b.x)
+        && p.y >= a.y.min(b.y)
+        && p.y <= a.y.max(b.y)
+}
+
+/// Whether the closed segments `a`-`b` and `c`-`d` share a point.
+fn segments_meet(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (o1, o2) = (orientation(a, b, c).signum(), orientation(a, b, d).signum());
+    let (o3, o4) = (orientation(c, d, a).signum(), orientation(c, d, b).signum());
+    (o1 != o2 && o3 != o4)
+        || on_segment(a, b, c)
+        || on_segment(a, b, d)
+        || on_segment(c, d, a)
+        || on_segment(c, d, b)
+}
+
+/// Split a shape into the pieces of area it covers.
+///
+/// Paths which cannot be converted into a single polygon are covered by their
+/// segments instead: rectilinear ones by their segment rects, and octilinear
+/// ones by each segment as a path of its own, extended by half the width at
+/// the joints. Malformed paths of either kind are reported.
+fn shape_pieces(shape_ix: usize, shape: &Shape, pieces: &mut Vec<Piece>) -> Result<(), PathErr> {
+    let layer = shape.layer();
+    match shape {
+        Shape::Rect(r) => pieces.push(Piece::from_rect(shape_ix, layer, r.p0, r.p1)),
+        Shape::Poly(p) if p.points.is_empty() => {}
+        Shape::Poly(p) => pieces.push(Piece::new(shape_ix, layer, p.points.clone(), false)),
+        Shape::Path(p) => {
+            if let Ok(poly) = p.as_poly() {
+                pieces.push(Piece::new(shape_ix, layer, poly.points, false));
+            } else if let Ok(rects) = p.segment_rects() {
+                for r in rects {
+                    pieces.push(Piece::from_rect(shape_ix, layer, r.p0, r.p1));
+                }
+            } else {
+                let joint = p.width.div_ceil(2) as i32;
+                let (begin, end) = match p.end_cap {
+                    PathEndCap::Flush => (0, 0),
+                    PathEndCap::HalfWidth => (joint, joint),
+                    PathEndCap::Custom { begin, end } => (begin, end),
+                };
+                let last = p.points.len().saturating_sub(2);
+                for (ix, w) in p.points.windows(2).enumerate() {
+                    let segment = Path {
+                        points: w.to_vec(),
+                        width: p.width,
+                        layer,
+                        end_cap: PathEndCap::Custom {
+                            begin: if ix == 0 { begin } else { joint },
+                            end: if ix == last { end } else { joint },
+                        },
+                    };
+                    // Repeated points make empty segments, which cover
+                    // nothing.
+                    if w[0] == w[1] {
+                        continue;
+                    }
+                    let poly = segment.as_poly()?;
+                    pieces.push(Piece::new(shape_ix, layer, poly.points, false));
+                }
+            }
+        }
+        Shape::VarWidthPath(p) => {
+            for r in p.segment_rects()? {
+                pieces.push(Piece::from_rect(shape_ix, layer, r.p0, r.p1));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Call `f` on every pair of pieces which touch, by sweeping across them in x.
+fn for_each_touching<'a>(mut pieces: Vec<&'a Piece>, mut f: impl FnMut(&'a Piece, &'a Piece)) {
+    pieces.sort_by_key(|p| p.min.x);
+    let mut active: Vec<&Piece> = Vec::new();
+    for piece in pieces {
+        active.retain(|a| a.max.x >= piece.min.x);
+        for a in active.iter() {
+            if a.touches(piece) {
+                f(a, piece);
+            }
+        }
+        active.push(piece);
+    }
+}
+
+/// Sets of shapes which have been joined together, as a union-find forest.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        DisjointSets {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut ix: usize) -> usize {
+        while self.parents[ix] != ix {
+            self.parents[ix] = self.parents[self.parents[ix]];
+            ix = self.parents[ix];
+        }
+        ix
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// Group shapes into nets.
+///
+/// Shapes on the same layer are connected if they touch or overlap, even if
+/// only at a corner. Shapes on different layers are connected only through the
+/// cut layers in `vias`, where a cut shape joins every shape on its bottom and
+/// top layers that it touches. Every shape is in exactly one net, and nets are
+/// sorted by their first shape.
+///
+/// Each label is attached to the net of the shapes on its layer which cover
+/// its position. Labels over no shape are ignored.
+///
+/// Fails if any path is malformed, since the shapes it should connect would
+/// otherwise be left apart without warning.
+pub fn extract_nets(
+    shapes: &[Shape],
+    vias: &[ViaLayer],
+    labels: &[Label],
+) -> Result<Vec<Net>, ConnectivityErr> {
+    let mut pieces = Vec::new();
+    for (ix, shape) in shapes.iter().enumerate() {
+        shape_pieces(ix, shape, &mut pieces).map_err(|e| ConnectivityErr::MalformedPath(ix, e))?;
+    }
+    let mut sets = DisjointSets::new(shapes.len());
+
+    let mut layers: Vec<u8> = pieces.iter().map(|p| p.layer).collect();
+    layers.sort_unstable();
+    layers.dedup();
+    for layer in layers {
+        let on_layer = pieces.iter().filter(|p| p.layer == layer).collect();
+        for_each_touching(on_layer, |a, b| sets.join(a.shape, b.shape));
+    }
+
+    for via in vias {
+        let joined = |layer| layer == via.cut || layer == via.bottom || layer == via.top;
+        let on_layers = pieces.iter().filter(|p| joined(p.layer)).collect();
+        for_each_touching(on_layers, |a, b| {
+            if (a.layer == via.cut) != (b.layer == via.cut) {
+                sets.join(a.shape, b.shape);
+            }
+        });
+    }
+
+    let mut nets: Vec<Net> = Vec::new();
+    let mut net_ixs = vec![usize::MAX; shapes.len()];
+    for ix in 0..shapes.len() {
+        let root = sets.find(ix);
+        if net_ixs[root] == usize::MAX {
+            net_ixs[root] = nets.len();
+            nets.push(Net::default());
+        }
+        nets[net_ixs[root]].shapes.push(ix);
+    }
+
+    for label in labels {
+        if let Some(piece) = pieces
+            .iter()
+            .find(|p| p.layer == label.layer && p.contains(label.position))
+        {
+            let root = sets.find(piece.shape);
+            nets[net_ixs[root]].labels.push(label.text.clone());
+        }
+    }
+    for net in nets.iter_mut() {
+        net.labels.sort();
+        net.labels.dedup();
+    }
+    Ok(nets)
+}
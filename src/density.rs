@@ -0,0 +1,432 @@
+use std::collections::BTreeSet;
+
+use crate::bbox::{BoundingBox, CalculateBoundingBox};
+use crate::boolean::Region;
+use crate::shapes::{PathLike, Point, PointLike, Rect, Shape, ShapeLike, Shapes, VarWidthPathLike};
+
+/// A grid of windows, which may overlap if the step between them is smaller
+/// than the windows themselves.
+///
+/// The window in column `c` and row `r` has its lower left corner at
+/// `origin + (c * step_x, r * step_y)`.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Grid {
+    pub origin: Point,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub step_x: i32,
+    pub step_y: i32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Grid {
+    /// A grid of `window` by `window` squares, `step` apart, covering `bbox`.
+    /// Windows along the top and right may stick out past it.
+    pub fn covering(bbox: &BoundingBox, window: i32, step: i32) -> Self {
+        assert!(window > 0 && step > 0, "Windows and steps must be positive");
+        let count = |length: i64| {
+            let beyond_first = (length - window as i64).max(0);
+            (beyond_first + step as i64 - 1) / step as i64 + 1
+        };
+        Grid {
+            origin: bbox.min(),
+            window_width: window,
+            window_height: window,
+            step_x: step,
+            step_y: step,
+            columns: count(bbox.width()) as u32,
+            rows: count(bbox.height()) as u32,
+        }
+    }
+
+    /// The window in `column` and `row`. Windows which stick out past the end
+    /// of the `i32` range are cut off there.
+    pub fn window(&self, column: u32, row: u32) -> Rect {
+        let clamp = |v: i64| v.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let x0 = self.origin.x as i64 + column as i64 * self.step_x as i64;
+        let y0 = self.origin.y as i64 + row as i64 * self.step_y as i64;
+        Rect {
+            p0: Point::new(clamp(x0), clamp(y0)),
+            p1: Point::new(
+                clamp(x0 + self.window_width as i64),
+                clamp(y0 + self.window_height as i64),
+            ),
+            layer: 0,
+        }
+    }
+
+    /// The ranges of columns and rows of the windows which overlap `bbox`.
+    fn overlapping_bbox(&self, bbox: &BoundingBox) -> ((u32, u32), (u32, u32)) {
+        (
+            Grid::overlapping(
+                bbox.min().x,
+                bbox.max().x,
+                self.origin.x,
+                self.window_width,
+                self.step_x,
+                self.columns,
+            ),
+            Grid::overlapping(
+                bbox.min().y,
+                bbox.max().y,
+                self.origin.y,
+                self.window_height,
+                self.step_y,
+                self.rows,
+            ),
+        )
+    }
+
+    /// The range of indices of the windows which overlap `[lo, hi)` along one
+    /// axis.
+    fn overlapping(
+        lo: i32,
+        hi: i32,
+        origin: i32,
+        window: i32,
+        step: i32,
+        count: u32,
+    ) -> (u32, u32) {
+        // Window i covers [origin + i * step, origin + i * step + window).
+        let (lo, hi, origin) = (lo as i64, hi as i64, origin as i64);
+        let first = (lo - origin - window as i64).div_euclid(step as i64) + 1;
+        let last = (hi - 1 - origin).div_euclid(step as i64);
+        (
+            first.clamp(0, count as i64) as u32,
+            (last + 1).clamp(0, count as i64) as u32,
+        )
+    }
+}
+
+/// The fraction of each window of a [`Grid`] covered by a layer.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DensityMap {
+    pub grid: Grid,
+    /// The density of each window, indexed by row and then column.
+    pub densities: Vec<Vec<f64>>,
+    /// The indices of the shapes on the layer which could not be measured, and
+    /// are left out of the densities: malformed paths, and shapes with edges
+    /// which are not at a multiple of 45 degrees.
+    pub unmeasured: Vec<usize>,
+}
+
+/// Summary statistics of a [`DensityMap`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DensityStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// The largest difference in density between two windows next to each
+    /// other in a row or column.
+    pub max_gradient: f64,
+}
+
+/// The density of `layer` in each window of `grid`: the fraction of the window
+/// covered by the union of the shapes on the layer, so that overlapping shapes
+/// are only counted once.
+///
+/// Shapes with diagonal edges cannot be merged into regions, so the windows
+/// they overlap are measured by sweeping across the outlines of every shape in
+/// them instead, which is exact for edges at multiples of 45 degrees. Shapes
+/// with other edges are left out, and listed in
+/// [`unmeasured`](DensityMap::unmeasured).
+pub fn density_map(shapes: &[Shape], layer: u8, grid: &Grid) -> DensityMap {
+    let on_layer: Vec<(usize, &Shape)> = shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.layer() == layer)
+        .collect();
+    let (region, left_out) = Region::from_rectilinear_shapes(on_layer.iter().map(|(_, s)| *s));
+
+    let mut unmeasured = Vec::new();
+    let mut outlines = Vec::new();
+    for ix in left_out {
+        let (ix, shape) = on_layer[ix];
+        match (shape.try_bbox(), octilinear_outline(shape)) {
+            (Some(bbox), Some(outline)) => outlines.push((bbox, outline)),
+            _ => unmeasured.push(ix),
+        }
+    }
+
+    let window_area = grid.window_width as f64 * grid.window_height as f64;
+    let mut areas: Vec<Vec<f64>> = covered_areas(&region, grid)
+        .into_iter()
+        .map(|row| row.into_iter().map(|a| a as f64).collect())
+        .collect();
+    if !outlines.is_empty() {
+        let mut windows = BTreeSet::new();
+        for (bbox, _) in outlines.iter() {
+            let (columns, rows) = grid.overlapping_bbox(bbox);
+            for row in rows.0..rows.1 {
+                windows.extend((columns.0..columns.1).map(|column| (row, column)));
+            }
+        }
+        let rects = region.rects(0);
+        for (row, column) in windows {
+            let window = grid.window(column, row);
+            areas[row as usize][column as usize] = octilinear_area(&rects, &outlines, &window);
+        }
+    }
+
+    DensityMap {
+        grid: *grid,
+        densities: areas
+            .into_iter()
+            .map(|row| row.into_iter().map(|a| a / window_area).collect())
+            .collect(),
+        unmeasured,
+    }
+}
+
+/// The area of `window` covered by the union of `rects` and some octilinear
+/// `outlines`, each with its bounding box.
+pub(crate) fn octilinear_area(
+    rects: &[Rect],
+    outlines: &[(BoundingBox, Vec<Point>)],
+    window: &Rect,
+) -> f64 {
+    let mut polygons: Vec<Vec<Edge>> = rects
+        .iter()
+        .filter(|r| overlaps(r, window))
+        .map(|r| {
+            edges(&[
+                r.p0,
+                Point::new(r.p1.x, r.p0.y),
+                r.p1,
+                Point::new(r.p0.x, r.p1.y),
+            ])
+        })
+        .collect();
+    polygons.extend(
+        outlines
+            .iter()
+            .filter(|(bbox, _)| {
+                bbox.min().x < window.p1.x
+                    && window.p0.x < bbox.max().x
+                    && bbox.min().y < window.p1.y
+                    && window.p0.y < bbox.max().y
+            })
+            .map(|(_, outline)| edges(outline)),
+    );
+    union_area(&polygons, window)
+}
+
+/// The outline of a shape which cannot be merged into a region, if all of its
+/// edges are at multiples of 45 degrees.
+pub(crate) fn octilinear_outline(shape: &Shape) -> Option<Vec<Point>> {
+    let points = match shape {
+        Shape::Rect(_) => return None,
+        Shape::Poly(p) => p.points.clone(),
+        Shape::Path(p) => p.as_poly().ok()?.points,
+        Shape::VarWidthPath(p) => p.as_poly().ok()?.points,
+    };
+    let octilinear = (0..points.len()).all(|ix| {
+        let (p0, p1) = (points[ix], points[(ix + 1) % points.len()]);
+        let (dx, dy) = (
+            (p1.x as i64 - p0.x as i64).abs(),
+            (p1.y as i64 - p0.y as i64).abs(),
+        );
+        dx == 0 || dy == 0 || dx == dy
+    });
+    octilinear.then_some(points)
+}
+
+fn overlaps(r: &Rect, window: &Rect) -> bool {
+    r.p0.x < window.p1.x && window.p0.x < r.p1.x && r.p0.y < window.p1.y && window.p0.y < r.p1.y
+}
+
+/// A sloped or vertical edge of an outline, from `(x0, y0)` to `(x1, y1)`, at
+/// four times the scale of the layout.
+///
+/// Octilinear edges with integer corners cross each other at half units, and
+/// the bands between those crossings have their middles at quarter units, so
+/// at this scale every coordinate the sweep needs is an integer.
+#[derive(Clone, Copy)]
+struct Edge {
+    x0: i64,
+    y0: i64,
+    x1: i64,
+    y1: i64,
+}
+
+impl Edge {
+    /// How far x moves for each step in y: -1, 0 or 1.
+    fn slope(&self) -> i64 {
+        (self.x1 - self.x0).signum() * (self.y1 - self.y0).signum()
+    }
+
+    fn x_at(&self, y: i64) -> i64 {
+        self.x0 + (y - self.y0) * self.slope()
+    }
+}
+
+/// The edges of an outline which are not horizontal.
+fn edges(points: &[Point]) -> Vec<Edge> {
+    (0..points.len())
+        .map(|ix| (points[ix], points[(ix + 1) % points.len()]))
+        .filter(|(p0, p1)| p0.y != p1.y)
+        .map(|(p0, p1)| Edge {
+            x0: 4 * p0.x as i64,
+            y0: 4 * p0.y as i64,
+            x1: 4 * p1.x as i64,
+            y1: 4 * p1.y as i64,
+        })
+        .collect()
+}
+
+/// The area of `window` covered by the union of some octilinear polygons,
+/// each counted by the nonzero winding rule.
+///
+/// Between consecutive corners and crossings of edges, each polygon covers a
+/// set of intervals whose ends move linearly, so the area of each band is the
+/// covered length across its middle times its height.
+fn union_area(polygons: &[Vec<Edge>], window: &Rect) -> f64 {
+    let (wx0, wx1) = (4 * window.p0.x as i64, 4 * window.p1.x as i64);
+    let (wy0, wy1) = (4 * window.p0.y as i64, 4 * window.p1.y as i64);
+    let sides = [
+        Edge {
+            x0: wx0,
+            y0: wy0,
+            x1: wx0,
+            y1: wy1,
+        },
+        Edge {
+            x0: wx1,
+            y0: wy0,
+            x1: wx1,
+            y1: wy1,
+        },
+    ];
+    let all_edges = || polygons.iter().flatten().chain(sides.iter());
+
+    let mut ys = vec![wy0, wy1];
+    for e in all_edges() {
+        ys.extend([e.y0, e.y1]);
+        if e.slope() == 0 {
+            continue;
+        }
+        for f in all_edges().filter(|f| f.slope() != e.slope()) {
+            // Where x0 + s (y - y0) is the same on both lines.
+            let numerator = (f.x0 - f.slope() * f.y0) - (e.x0 - e.slope() * e.y0);
+            ys.push(numerator / (e.slope() - f.slope()));
+        }
+    }
+    ys.retain(|&y| wy0 <= y && y <= wy1);
+    ys.sort_unstable();
+    ys.dedup();
+
+    let mut area: i128 = 0;
+    let mut intervals = Vec::new();
+    for band in ys.windows(2) {
+        let y = (band[0] + band[1]) / 2;
+        intervals.clear();
+        for polygon in polygons {
+            let mut crossings: Vec<(i64, i64)> = polygon
+                .iter()
+                .filter(|e| e.y0.min(e.y1) < y && y < e.y0.max(e.y1))
+                .map(|e| (e.x_at(y), (e.y1 - e.y0).signum()))
+                .collect();
+            crossings.sort_unstable();
+            let mut winding = 0;
+            for (x, direction) in crossings {
+                if winding == 0 {
+                    intervals.push((x, x));
+                }
+                winding += direction;
+                if winding == 0 {
+                    intervals.last_mut().unwrap().1 = x;
+                }
+            }
+        }
+        intervals.sort_unstable();
+
+        let mut covered = 0;
+        let mut reached = wx0;
+        for &(x0, x1) in intervals.iter() {
+            let (x0, x1) = (x0.max(reached), x1.min(wx1));
+            if x0 < x1 {
+                covered += x1 - x0;
+                reached = x1;
+            }
+        }
+        area += covered as i128 * (band[1] - band[0]) as i128;
+    }
+    area as f64 / 16.0
+}
+
+/// The area of each window of `grid` covered by `region`, indexed by row and
+/// then column.
+pub(crate) fn covered_areas(region: &Region, grid: &Grid) -> Vec<Vec<i64>> {
+    let mut covered = vec![vec![0i64; grid.columns as usize]; grid.rows as usize];
+    // The rects of a region are disjoint, so the area of each window covered
+    // by the region is the sum of its overlaps with them.
+    for r in region.rects(0) {
+        let columns = Grid::overlapping(
+            r.p0.x,
+            r.p1.x,
+            grid.origin.x,
+            grid.window_width,
+            grid.step_x,
+            grid.columns,
+        );
+        let rows = Grid::overlapping(
+            r.p0.y,
+            r.p1.y,
+            grid.origin.y,
+            grid.window_height,
+            grid.step_y,
+            grid.rows,
+        );
+        for row in rows.0..rows.1 {
+            for column in columns.0..columns.1 {
+                let window = grid.window(column, row);
+                let width = r.p1.x.min(window.p1.x) - r.p0.x.max(window.p0.x);
+                let height = r.p1.y.min(window.p1.y) - r.p0.y.max(window.p0.y);
+                covered[row as usize][column as usize] += width as i64 * height as i64;
+            }
+        }
+    }
+    covered
+}
+
+impl DensityMap {
+    /// Statistics over every window, or `None` if the grid has no windows.
+    pub fn stats(&self) -> Option<DensityStats> {
+        let all = || self.densities.iter().flatten().copied();
+        let count = all().count();
+        if count == 0 {
+            return None;
+        }
+
+        let mut max_gradient: f64 = 0.0;
+        for (r, row) in self.densities.iter().enumerate() {
+            for (c, &density) in row.iter().enumerate() {
+                if let Some(&right) = row.get(c + 1) {
+                    max_gradient = max_gradient.max((right - density).abs());
+                }
+                if let Some(&above) = self.densities.get(r + 1).map(|above| &above[c]) {
+                    max_gradient = max_gradient.max((above - density).abs());
+                }
+            }
+        }
+        Some(DensityStats {
+            min: all().fold(f64::INFINITY, f64::min),
+            max: all().fold(f64::NEG_INFINITY, f64::max),
+            mean: all().sum::<f64>() / count as f64,
+            max_gradient,
+        })
+    }
+}
+
+impl Shapes {
+    /// The density of `layer` over a grid of `window` by `window` squares,
+    /// `step` apart, covering the bounding box of the shapes.
+    pub fn density(&self, layer: u8, window: i32, step: i32) -> DensityMap {
+        density_map(
+            &self.shapes,
+            layer,
+            &Grid::covering(&self.bbox, window, step),
+        )
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use crate::boolean::{BooleanOp, Region, RegionErr};
+use crate::shapes::Shape;
+
+/// An expression producing a derived layer from other layers.
+///
+/// Expressions are built up with the builder methods, e.g. the parts of layer
+/// 1 not covered by layer 2, grown by 10:
+/// ```
+/// # use doug_geometry::derivation::LayerExpr;
+/// let expr = LayerExpr::layer(1).not(LayerExpr::layer(2)).size(10, 10);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum LayerExpr {
+    /// All shapes on a layer. This is either an input layer, or a layer derived
+    /// by an earlier rule in the same deck.
+    Layer(u8),
+    Boolean(BooleanOp, Box<LayerExpr>, Box<LayerExpr>),
+    /// Grow (positive) or shrink (negative) by the given amounts in x and y.
+    Size(Box<LayerExpr>, i32, i32),
+    /// Keep only those polygons which pass the filter.
+    Select(Box<LayerExpr>, Filter),
+}
+
+/// A filter on the polygons of a layer. The bounds are inclusive.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Filter {
+    Area {
+        min: i64,
+        max: i64,
+    },
+    /// See [`Region::min_width`] for how the width of a polygon is measured.
+    Width {
+        min: i32,
+        max: i32,
+    },
+}
+
+impl Filter {
+    fn passes(&self, region: &Region) -> bool {
+        match *self {
+            Filter::Area { min, max } => (min as i128..=max as i128).contains(&region.area()),
+            Filter::Width { min, max } => {
+                region.min_width().is_some_and(|w| (min..=max).contains(&w))
+            }
+        }
+    }
+}
+
+impl LayerExpr {
+    pub fn layer(layer: u8) -> Self {
+        LayerExpr::Layer(layer)
+    }
+
+    pub fn and(self, other: LayerExpr) -> Self {
+        LayerExpr::Boolean(BooleanOp::And, Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: LayerExpr) -> Self {
+        LayerExpr::Boolean(BooleanOp::Or, Box::new(self), Box::new(other))
+    }
+
+    /// The parts of this layer not covered by `other`.
+    pub fn not(self, other: LayerExpr) -> Self {
+        LayerExpr::Boolean(BooleanOp::Not, Box::new(self), Box::new(other))
+    }
+
+    pub fn xor(self, other: LayerExpr) -> Self {
+        LayerExpr::Boolean(BooleanOp::Xor, Box::new(self), Box::new(other))
+    }
+
+    pub fn size(self, dx: i32, dy: i32) -> Self {
+        LayerExpr::Size(Box::new(self), dx, dy)
+    }
+
+    pub fn with_area(self, min: i64, max: i64) -> Self {
+        LayerExpr::Select(Box::new(self), Filter::Area { min, max })
+    }
+
+    pub fn with_width(self, min: i32, max: i32) -> Self {
+        LayerExpr::Select(Box::new(self), Filter::Width { min, max })
+    }
+
+    /// Evaluate the expression, looking up layers in `layers` first, and then
+    /// falling back to the shapes on that layer.
+    fn evaluate(
+        &self,
+        shapes: &[Shape],
+        layers: &mut BTreeMap<u8, Region>,
+    ) -> Result<Region, RegionErr> {
+        Ok(match self {
+            LayerExpr::Layer(layer) => match layers.get(layer) {
+                Some(region) => region.clone(),
+                None => {
+                    let region = Region::from_layer(shapes, *layer)?;
+                    layers.insert(*layer, region.clone());
+                    region
+                }
+            },
+            LayerExpr::Boolean(op, a, b) => {
+                let a = a.evaluate(shapes, layers)?;
+                a.boolean(&b.evaluate(shapes, layers)?, *op)
+            }
+            LayerExpr::Size(e, dx, dy) => e.evaluate(shapes, layers)?.size(*dx, *dy),
+            LayerExpr::Select(e, filter) => {
+                let rects: Vec<_> = e
+                    .evaluate(shapes, layers)?
+                    .components()
+                    .into_iter()
+                    .filter(|r| filter.passes(r))
+                    .flat_map(|r| r.rects(0))
+                    .collect();
+                Region::from_rects(rects.iter())
+            }
+        })
+    }
+}
+
+/// A derived layer, and the expression that produces it.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Rule {
+    pub layer: u8,
+    pub expr: LayerExpr,
+}
+
+/// An ordered list of rules, each of which can use the layers derived by the
+/// rules before it.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct RuleDeck {
+    pub rules: Vec<Rule>,
+}
+
+impl RuleDeck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule deriving `layer` from `expr`. If `layer` is already used by
+    /// the input shapes or an earlier rule, later rules see the derived layer
+    /// in its place.
+    pub fn rule(mut self, layer: u8, expr: LayerExpr) -> Self {
+        self.rules.push(Rule { layer, expr });
+        self
+    }
+
+    /// Evaluate every rule over `shapes`, returning the regions of the derived
+    /// layers. Fails if a rule uses a layer with shapes which are not
+    /// rectilinear.
+    pub fn evaluate(&self, shapes: &[Shape]) -> Result<BTreeMap<u8, Region>, RegionErr> {
+        let mut layers = BTreeMap::new();
+        let mut derived = BTreeMap::new();
+        for rule in self.rules.iter() {
+            let region = rule.expr.evaluate(shapes, &mut layers)?;
+            layers.insert(rule.layer, region.clone());
+            derived.insert(rule.layer, region);
+        }
+        Ok(derived)
+    }
+
+    /// Evaluate every rule over `shapes`, returning the shapes on the derived
+    /// layers.
+    pub fn evaluate_shapes(&self, shapes: &[Shape]) -> Result<Vec<Shape>, RegionErr> {
+        Ok(self
+            .evaluate(shapes)?
+            .into_iter()
+            .flat_map(|(layer, region)| region.shapes(layer))
+            .collect())
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+
+use crate::bbox::{BoundingBox, CalculateBoundingBox, UnvalidatedBoundingBox};
+use crate::boolean::{PolyWithHoles, Region};
+use crate::shapes::{Point, PointLike, Poly, Rect, Shape, ShapeLike};
+
+/// A design rule, checked on the merged shapes of its layers.
+///
+/// Distances are measured along x and y, so two corners are as far apart as
+/// the larger of their x and y offsets.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub enum Check {
+    /// Every rect that the layer decomposes into must stretch across at
+    /// least `min`, in both directions, without leaving the layer.
+    Width { layer: u8, min: i32 },
+    /// Separate polygons on the layer must be at least `min` apart.
+    Spacing { layer: u8, min: i32 },
+    /// Every polygon on the layer must cover at least `min`.
+    Area { layer: u8, min: i64 },
+    /// Every part of `inner` must be covered by `outer`, with at least `min`
+    /// to spare on every side.
+    Enclosure { inner: u8, outer: u8, min: i32 },
+    /// Gaps between two parts of the same polygon must be at least `min`
+    /// wide.
+    Notch { layer: u8, min: i32 },
+    /// Steps in the outline of a polygon, where it turns one way and then the
+    /// other, must be at least `min` long.
+    Jog { layer: u8, min: i32 },
+}
+
+impl Check {
+    /// The layers which the check looks at.
+    pub fn layers(&self) -> Vec<u8> {
+        match *self {
+            Check::Width { layer, .. }
+            | Check::Spacing { layer, .. }
+            | Check::Area { layer, .. }
+            | Check::Notch { layer, .. }
+            | Check::Jog { layer, .. } => vec![layer],
+            Check::Enclosure { inner, outer, .. } => vec![inner, outer],
+        }
+    }
+}
+
+/// A named [`Check`], so that its violations can be traced back to it.
+#[derive(Debug, Eq, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DesignRule {
+    pub name: String,
+    pub check: Check,
+}
+
+/// The design rules to check, as supplied by the user.
+#[derive(Debug, Default, Eq, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RuleTable {
+    pub rules: Vec<DesignRule>,
+}
+
+/// Why a [`Marker`] was raised.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum MarkerKind {
+    /// The shapes break the rule.
+    Violation,
+    /// The shapes could not be checked against the rule, so they may or may
+    /// not break it. These are shapes with diagonal edges or malformed paths,
+    /// which cannot be merged into regions, polygons which could not be
+    /// decomposed into rects for the width check, and polygons too close to
+    /// the ends of the `i32` range for the notch check.
+    Unchecked,
+}
+
+/// A violation of a design rule, or a part of the layout which could not be
+/// checked against it.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Marker {
+    /// The name of the rule which was violated.
+    pub rule: String,
+    pub kind: MarkerKind,
+    /// The area of the violation.
+    pub bbox: BoundingBox,
+    /// The indices of the shapes involved, in increasing order.
+    pub shapes: Vec<usize>,
+}
+
+/// The region of each rectilinear shape on a layer, by shape index.
+type LayerShapes = Vec<(usize, Region)>;
+
+impl RuleTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, name: &str, check: Check) -> Self {
+        self.rules.push(DesignRule {
+            name: name.to_string(),
+            check,
+        });
+        self
+    }
+
+    /// Check every rule against `shapes`, returning the markers rule by rule.
+    ///
+    /// Widths are measured on the rects which `decompose` splits each merged
+    /// polygon into, such as those of
+    /// `odb_poly_decomp::decomposer::Decomposer::decompose_poly`. Holes are
+    /// cut out of the rects of the outline afterwards.
+    ///
+    /// Shapes with diagonal edges, and malformed paths, cannot be merged into
+    /// regions, so they are not checked. Each of them gets an
+    /// [`Unchecked`](MarkerKind::Unchecked) marker for every rule on its layer
+    /// instead, as do the polygons which `decompose` fails on.
+    pub fn check<E>(
+        &self,
+        shapes: &[Shape],
+        mut decompose: impl FnMut(&Poly) -> Result<Vec<Rect>, E>,
+    ) -> Vec<Marker> {
+        let mut layers: BTreeMap<u8, LayerShapes> = BTreeMap::new();
+        let mut unchecked: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+        for (ix, shape) in shapes.iter().enumerate() {
+            match Region::from_shapes([shape]) {
+                Ok(region) => layers.entry(shape.layer()).or_default().push((ix, region)),
+                Err(_) => unchecked.entry(shape.layer()).or_default().push(ix),
+            }
+        }
+        let empty = Vec::new();
+        let layer = |layer: u8| layers.get(&layer).unwrap_or(&empty);
+
+        let mut markers = Vec::new();
+        for rule in self.rules.iter() {
+            let mut mark = |region: &Region, shapes: Vec<usize>, scale: i32| {
+                markers.push(Marker {
+                    rule: rule.name.clone(),
+                    kind: MarkerKind::Violation,
+                    bbox: unscaled_bbox(region, scale),
+                    shapes,
+                })
+            };
+            let mut undecomposed = Vec::new();
+            let mut unscalable = Vec::new();
+            match rule.check {
+                Check::Width { layer: l, min } => {
+                    undecomposed = check_width(layer(l), min, &mut decompose, &mut mark)
+                }
+                Check::Spacing { layer: l, min } => check_spacing(layer(l), min, &mut mark),
+                Check::Area { layer: l, min } => check_area(layer(l), min, &mut mark),
+                Check::Enclosure { inner, outer, min } => {
+                    check_enclosure(layer(inner), layer(outer), min, &mut mark)
+                }
+                Check::Notch { layer: l, min } => {
+                    unscalable = check_notch(layer(l), min, &mut mark)
+                }
+                Check::Jog { layer: l, min } => check_jog(layer(l), min, &mut mark),
+            }
+
+            for (region, involved) in undecomposed.into_iter().chain(unscalable) {
+                markers.push(Marker {
+                    rule: rule.name.clone(),
+                    kind: MarkerKind::Unchecked,
+                    bbox: region.bbox().unwrap(),
+                    shapes: involved,
+                });
+            }
+            let mut layers = rule.check.layers();
+            layers.dedup();
+            for ixs in layers.iter().filter_map(|l| unchecked.get(l)) {
+                for &ix in ixs {
+                    markers.push(Marker {
+                        rule: rule.name.clone(),
+                        kind: MarkerKind::Unchecked,
+                        bbox: marker_bbox(&shapes[ix]),
+                        shapes: vec![ix],
+                    });
+                }
+            }
+        }
+        markers
+    }
+}
+
+/// The bounding box of a shape, or for a shape without any area, its extent
+/// widened to one unit where it is flat. Shapes without any points at all are
+/// marked at the origin.
+fn marker_bbox(shape: &Shape) -> BoundingBox {
+    shape.try_bbox().unwrap_or_else(|| {
+        let mut extent = UnvalidatedBoundingBox::invalid();
+        extent.update(shape);
+        if extent.is_empty() {
+            extent = UnvalidatedBoundingBox {
+                min: Point::default(),
+                max: Point::default(),
+            };
+        }
+        let widen = |min: &mut i32, max: &mut i32| {
+            if min == max {
+                match max.checked_add(1) {
+                    Some(m) => *max = m,
+                    None => *min -= 1,
+                }
+            }
+        };
+        widen(&mut extent.min.x, &mut extent.max.x);
+        widen(&mut extent.min.y, &mut extent.max.y);
+        BoundingBox::new(extent)
+    })
+}
+
+/// The union of the regions of some shapes.
+fn merged(shapes: &LayerShapes) -> Region {
+    let rects: Vec<Rect> = shapes.iter().flat_map(|(_, r)| r.rects(0)).collect();
+    Region::from_rects(rects.iter())
+}
+
+/// A region with its coordinates multiplied by `scale`, or `None` if they no
+/// longer fit in an `i32`.
+///
+/// Checks against odd distances are done at twice the scale, so that they can
+/// size regions by half of the distance.
+fn scaled(region: &Region, scale: i32) -> Option<Region> {
+    let scale_point = |p: Point| Some(Point::new(p.x.checked_mul(scale)?, p.y.checked_mul(scale)?));
+    let rects = region
+        .rects(0)
+        .into_iter()
+        .map(|r| {
+            Some(Rect {
+                p0: scale_point(r.p0)?,
+                p1: scale_point(r.p1)?,
+                layer: 0,
+            })
+        })
+        .collect::<Option<Vec<Rect>>>()?;
+    Some(Region::from_rects(rects.iter()))
+}
+
+/// The bounding box of a region at `scale`, in unscaled coordinates, rounded
+/// outwards.
+fn unscaled_bbox(region: &Region, scale: i32) -> BoundingBox {
+    let bbox = region.bbox().unwrap();
+    BoundingBox::new(UnvalidatedBoundingBox {
+        min: Point::new(
+            bbox.min().x.div_euclid(scale),
+            bbox.min().y.div_euclid(scale),
+        ),
+        max: Point::new(
+            -(-bbox.max().x).div_euclid(scale),
+            -(-bbox.max().y).div_euclid(scale),
+        ),
+    })
+}
+
+/// The shapes which overlap `region`, which is at `scale`.
+///
+/// Shapes which cannot be scaled lie outside the `i32` range at `scale`, so
+/// they cannot overlap `region`.
+fn overlapping(shapes: &LayerShapes, region: &Region, scale: i32) -> Vec<usize> {
+    shapes
+        .iter()
+        .filter(|(_, r)| scaled(r, scale).is_some_and(|r| !r.intersection(region).is_empty()))
+        .map(|&(ix, _)| ix)
+        .collect()
+}
+
+/// The parts of a layer which are narrower than `min`, measured on the rects
+/// which `decompose` splits it into.
+///
+/// The decomposition may slice a wide part of a polygon into thin rects, so a
+/// thin rect is only too narrow where it cannot be stretched across `min`
+/// without leaving the polygon.
+///
+/// Returns the polygons which could not be decomposed, with the shapes which
+/// overlap them.
+fn check_width<E>(
+    shapes: &LayerShapes,
+    min: i32,
+    decompose: &mut impl FnMut(&Poly) -> Result<Vec<Rect>, E>,
+    mark: &mut impl FnMut(&Region, Vec<usize>, i32),
+) -> Vec<(Region, Vec<usize>)> {
+    let mut undecomposed = Vec::new();
+    if min <= 1 {
+        return undecomposed;
+    }
+    let mut narrow = Vec::new();
+    for polygon in merged(shapes).components() {
+        let outline = polygon.polys(0).remove(0).outline;
+        let Ok(rects) = decompose(&outline) else {
+            let involved = overlapping(shapes, &polygon, 1);
+            undecomposed.push((polygon, involved));
+            continue;
+        };
+        for rect in rects {
+            // Holes are cut out of the rects of the outline.
+            for rect in Region::from_rect(&rect).intersection(&polygon).rects(0) {
+                narrow.extend(unstretchable(&polygon, &rect, Axis::X, min));
+                narrow.extend(unstretchable(&polygon, &rect, Axis::Y, min));
+            }
+        }
+    }
+    for narrow in Region::from_rects(narrow.iter()).components() {
+        mark(&narrow, overlapping(shapes, &narrow, 1), 1);
+    }
+    undecomposed
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    fn other(self) -> Axis {
+        match self {
+            Axis::X => Axis::Y,
+            Axis::Y => Axis::X,
+        }
+    }
+
+    /// The extent of `rect` along the axis.
+    fn span(self, rect: &Rect) -> (i32, i32) {
+        match self {
+            Axis::X => (rect.p0.x, rect.p1.x),
+            Axis::Y => (rect.p0.y, rect.p1.y),
+        }
+    }
+
+    /// `rect` with its extent along the axis replaced.
+    fn with_span(self, rect: &Rect, lo: i32, hi: i32) -> Rect {
+        let (p0, p1) = match self {
+            Axis::X => (Point::new(lo, rect.p0.y), Point::new(hi, rect.p1.y)),
+            Axis::Y => (Point::new(rect.p0.x, lo), Point::new(rect.p1.x, hi)),
+        };
+        Rect { p0, p1, layer: 0 }
+    }
+}
+
+/// The parts of `rect`, which lies inside `polygon`, which span less than
+/// `min` along `axis`, and cannot be stretched along it to span `min` without
+/// leaving the polygon.
+fn unstretchable(polygon: &Region, rect: &Rect, axis: Axis, min: i32) -> Vec<Rect> {
+    let (lo, hi) = axis.span(rect);
+    if hi as i64 - lo as i64 >= min as i64 {
+        return Vec::new();
+    }
+    // Only the parts of the polygon within `min` of the rect matter. Each part
+    // of the rect can stretch until it reaches the nearest parts outside the
+    // polygon on either side of it, so the rect is cut wherever those begin or
+    // end.
+    let (strip_lo, strip_hi) = (hi.saturating_sub(min), lo.saturating_add(min));
+    let outside = Region::from_rect(&axis.with_span(rect, strip_lo, strip_hi))
+        .difference(polygon)
+        .rects(0);
+    let across = axis.other();
+    let (a0, a1) = across.span(rect);
+    let mut cuts = vec![a0, a1];
+    for r in outside.iter() {
+        let (r0, r1) = across.span(r);
+        cuts.extend([r0, r1].into_iter().filter(|&c| a0 < c && c < a1));
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut narrow = Vec::new();
+    for cut in cuts.windows(2) {
+        let (mut start, mut end) = (strip_lo, strip_hi);
+        for r in outside.iter() {
+            let (r0, r1) = across.span(r);
+            if r1 <= cut[0] || r0 >= cut[1] {
+                continue;
+            }
+            let (r_lo, r_hi) = axis.span(r);
+            if r_hi <= lo {
+                start = start.max(r_hi);
+            } else {
+                end = end.min(r_lo);
+            }
+        }
+        if (end as i64 - start as i64) < min as i64 {
+            narrow.push(across.with_span(rect, cut[0], cut[1]));
+        }
+    }
+    narrow
+}
+
+/// Pairs of separate polygons closer than `min`.
+///
+/// Two polygons are closer than `min` exactly when one of them, grown by
+/// `min`, overlaps the other. The marker covers the parts of each polygon
+/// which are too close to the other.
+fn check_spacing(shapes: &LayerShapes, min: i32, mark: &mut impl FnMut(&Region, Vec<usize>, i32)) {
+    if min <= 0 {
+        return;
+    }
+    let mut components: Vec<(Region, BoundingBox)> = merged(shapes)
+        .components()
+        .into_iter()
+        .map(|c| {
+            let bbox = c.bbox().unwrap();
+            (c, bbox)
+        })
+        .collect();
+    components.sort_by_key(|(_, bbox)| bbox.min().x);
+
+    for (ix, (a, a_bbox)) in components.iter().enumerate() {
+        let grown_a = a.size(min, min);
+        for (b, b_bbox) in components[ix + 1..].iter() {
+            // Whether `lo` is at least `min` beyond `hi`.
+            let clear = |lo: i32, hi: i32| lo as i64 >= hi as i64 + min as i64;
+            if clear(b_bbox.min().x, a_bbox.max().x) {
+                break;
+            }
+            if clear(b_bbox.min().y, a_bbox.max().y) || clear(a_bbox.min().y, b_bbox.max().y) {
+                continue;
+            }
+            let violation = grown_a
+                .intersection(b)
+                .union(&b.size(min, min).intersection(a));
+            if !violation.is_empty() {
+                mark(&violation, overlapping(shapes, &violation, 1), 1);
+            }
+        }
+    }
+}
+
+fn check_area(shapes: &LayerShapes, min: i64, mark: &mut impl FnMut(&Region, Vec<usize>, i32)) {
+    for component in merged(shapes).components() {
+        if component.area() < min as i128 {
+            mark(&component, overlapping(shapes, &component, 1), 1);
+        }
+    }
+}
+
+/// The parts of `inner`, grown by `min`, which are not covered by `outer`.
+fn check_enclosure(
+    inner: &LayerShapes,
+    outer: &LayerShapes,
+    min: i32,
+    mark: &mut impl FnMut(&Region, Vec<usize>, i32),
+) {
+    let uncovered = merged(inner).size(min, min).difference(&merged(outer));
+    for component in uncovered.components() {
+        // The uncovered parts lie around the inner shapes, and along the
+        // edges of the outer shapes.
+        let around = component.size(min.max(1), min.max(1));
+        let mut involved = overlapping(inner, &around, 1);
+        involved.extend(overlapping(outer, &component.size(1, 1), 1));
+        involved.sort_unstable();
+        involved.dedup();
+        mark(&component, involved, 1);
+    }
+}
+
+/// Gaps narrower than `min` between parts of the same polygon, including
+/// holes.
+///
+/// At twice the scale, growing a polygon by `min - 1` fills exactly those gaps
+/// narrower than `min`, and shrinking back leaves them filled.
+///
+/// Returns the polygons which do not fit in the `i32` range at twice the
+/// scale, with the shapes which overlap them.
+fn check_notch(
+    shapes: &LayerShapes,
+    min: i32,
+    mark: &mut impl FnMut(&Region, Vec<usize>, i32),
+) -> Vec<(Region, Vec<usize>)> {
+    let mut unscalable = Vec::new();
+    if min <= 1 {
+        return unscalable;
+    }
+    for polygon in merged(shapes).components() {
+        let Some(polygon) = scaled(&polygon, 2) else {
+            let involved = overlapping(shapes, &polygon, 1);
+            unscalable.push((polygon, involved));
+            continue;
+        };
+        let closed = polygon.size(min - 1, min - 1).size(1 - min, 1 - min);
+        for notch in closed.difference(&polygon).components() {
+            mark(&notch, overlapping(shapes, &notch.size(2, 2), 2), 2);
+        }
+    }
+    unscalable
+}
+
+/// Edges shorter than `min` at which an outline steps sideways, turning one
+/// way at one end and the other way at the other.
+///
+/// The marker spans the step and the edges on either side of it.
+fn check_jog(shapes: &LayerShapes, min: i32, mark: &mut impl FnMut(&Region, Vec<usize>, i32)) {
+    let region = merged(shapes);
+    let polygons = region.polys(0);
+    let rings = polygons
+        .iter()
+        .flat_map(|p: &PolyWithHoles| std::iter::once(&p.outline).chain(p.holes.iter()));
+    for ring in rings {
+        for (p0, p1, p2, p3) in jogs(ring) {
+            let length = (p2.x as i64 - p1.x as i64).abs() + (p2.y as i64 - p1.y as i64).abs();
+            if length >= min as i64 {
+                continue;
+            }
+            let corners = [p0, p1, p2, p3];
+            let step = Region::from_rect(&Rect {
+                p0: Point::new(
+                    corners.iter().map(|p| p.x).min().unwrap(),
+                    corners.iter().map(|p| p.y).min().unwrap(),
+                ),
+                p1: Point::new(
+                    corners.iter().map(|p| p.x).max().unwrap(),
+                    corners.iter().map(|p| p.y).max().unwrap(),
+                ),
+                layer: 0,
+            });
+            let involved = overlapping(shapes, &step.intersection(&region), 1);
+            mark(&step, involved, 1);
+        }
+    }
+}
+
+/// The edges `p1`-`p2` of a ring at which it steps sideways, with the points
+/// `p0` and `p3` before and after them.
+fn jogs(ring: &Poly) -> impl Iterator<Item = (Point, Point, Point, Point)> + '_ {
+    let points = &ring.points;
+    let n = points.len();
+    let turn = |a: Point, b: Point, c: Point| {
+        ((b.x - a.x) as i64 * (c.y - b.y) as i64 - (b.y - a.y) as i64 * (c.x - b.x) as i64).signum()
+    };
+    (0..n).filter_map(move |ix| {
+        let (p0, p1, p2, p3) = (
+            points[(ix + n - 1) % n],
+            points[ix],
+            points[(ix + 1) % n],
+            points[(ix + 2) % n],
+        );
+        (turn(p0, p1, p2) * turn(p1, p2, p3) < 0).then_some((p0, p1, p2, p3))
+    })
+}
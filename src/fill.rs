@@ -0,0 +1,126 @@
+use crate::bbox::CalculateBoundingBox;
+use crate::boolean::Region;
+use crate::density::{covered_areas, Grid};
+use crate::shapes::{Point, PointLike, Rect, Shape, ShapeLike, Shapes};
+use crate::units::Units;
+
+/// How to fill the free space of a layer.
+#[derive(Debug, PartialEq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct FillRule {
+    pub layer: u8,
+    /// The width and height of each fill square.
+    pub size: i32,
+    /// The space left between fill and the existing shapes on the layer, and
+    /// between one fill square and the next.
+    pub spacing: i32,
+    /// The fraction of each window to cover, counting the existing shapes.
+    pub target_density: f64,
+    /// The width and height of the windows over which density is measured.
+    pub window: i32,
+}
+
+/// Generate fill squares in the free space of each layer with a rule, inside
+/// `region`.
+///
+/// Fill is placed on a grid with a pitch of `size + spacing`, aligned to the
+/// corner of `region`, so that fill squares are `spacing` apart. The region is
+/// tiled with windows, and each window gets only as many fill squares as it
+/// needs to reach the target density, spread evenly over the sites available
+/// in it. Squares which would straddle two windows are not used.
+///
+/// Shapes with diagonal edges, and malformed paths, block their whole bounding
+/// box, and those without any area block nothing. The fill is in the same
+/// `units` as `shapes`. Returns `None` if no fill is needed.
+pub fn generate_fill(
+    shapes: &[Shape],
+    units: Units,
+    region: &Region,
+    rules: &[FillRule],
+) -> Option<Shapes> {
+    let bbox = region.bbox()?;
+    let mut fill = Vec::new();
+
+    for rule in rules {
+        assert!(
+            rule.size > 0 && rule.spacing >= 0 && rule.window > 0,
+            "Fill sizes and windows must be positive"
+        );
+        let on_layer: Vec<&Shape> = shapes.iter().filter(|s| s.layer() == rule.layer).collect();
+        let (rectilinear, left_out) = Region::from_rectilinear_shapes(on_layer.iter().copied());
+        let blocked_bboxes: Vec<Rect> = left_out
+            .into_iter()
+            .filter_map(|ix| on_layer[ix].try_bbox())
+            .map(|bbox| Rect {
+                p0: bbox.min(),
+                p1: bbox.max(),
+                layer: rule.layer,
+            })
+            .collect();
+        let existing = rectilinear.union(&Region::from_rects(blocked_bboxes.iter()));
+        let free = region.difference(&existing.size(rule.spacing, rule.spacing));
+
+        let grid = Grid::covering(&bbox, rule.window, rule.window);
+        let covered = covered_areas(&existing.intersection(region), &grid);
+        let window_area = rule.window as f64 * rule.window as f64;
+        let site_area = rule.size as i64 * rule.size as i64;
+        let pitch = rule.size + rule.spacing;
+
+        for row in 0..grid.rows {
+            for column in 0..grid.columns {
+                let target = (rule.target_density * window_area).ceil() as i64;
+                let needed = target - covered[row as usize][column as usize];
+                if needed <= 0 {
+                    continue;
+                }
+
+                let window = grid.window(column, row);
+                let free = free.intersection(&Region::from_rect(&window));
+                let sites = fill_sites(&free, &window, bbox.min(), rule.size, pitch, rule.layer);
+                let count = sites
+                    .len()
+                    .min(((needed + site_area - 1) / site_area) as usize);
+                // Take every nth site, so that the fill is spread out.
+                fill.extend((0..count).map(|ix| Shape::Rect(sites[ix * sites.len() / count])));
+            }
+        }
+    }
+
+    if fill.is_empty() {
+        return None;
+    }
+    Some(Shapes {
+        bbox: fill.bbox(),
+        shapes: fill,
+        units,
+    })
+}
+
+/// The squares of the fill grid which lie within `free`, which itself lies
+/// within `window`, row by row.
+fn fill_sites(
+    free: &Region,
+    window: &Rect,
+    origin: Point,
+    size: i32,
+    pitch: i32,
+    layer: u8,
+) -> Vec<Rect> {
+    let first = |lo: i32, origin: i32| (lo - origin + pitch - 1).div_euclid(pitch);
+    let last = |hi: i32, origin: i32| (hi - size - origin).div_euclid(pitch);
+
+    let mut sites = Vec::new();
+    for j in first(window.p0.y, origin.y)..=last(window.p1.y, origin.y) {
+        for i in first(window.p0.x, origin.x)..=last(window.p1.x, origin.x) {
+            let p0 = Point::new(origin.x + i * pitch, origin.y + j * pitch);
+            let site = Rect {
+                p0,
+                p1: Point::new(p0.x + size, p0.y + size),
+                layer,
+            };
+            if Region::from_rect(&site).difference(free).is_empty() {
+                sites.push(site);
+            }
+        }
+    }
+    sites
+}
@@ -1,12 +1,24 @@
-mod bbox;
+pub mod bbox;
+pub mod boolean;
+pub mod centerline;
+pub mod connectivity;
+pub mod density;
+pub mod derivation;
+pub mod drc;
+pub mod fill;
+pub mod library;
+pub mod raster;
 pub mod shapes;
+pub mod sizing;
+pub mod transform;
+pub mod units;
 
-use crate::shapes::{Point, PointLike, RectDirection};
+use crate::shapes::{OctDirection, Point, PointLike};
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Polygon {
     pub points: Vec<Point>,
-    pub directions: Vec<RectDirection>,
+    pub directions: Vec<OctDirection>,
     pub horizontal_inversions: usize,
     pub vertical_inversions: usize,
 }
@@ -26,9 +38,8 @@ impl Polygon {
     pub fn from_points(mut points: Vec<Point>) -> Polygon {
         assert!(points.len() > 3);
 
-        let mut directions = Vec::<RectDirection>::with_capacity(points.len() - 1);
+        let mut directions = Vec::<OctDirection>::with_capacity(points.len() - 1);
 
-        use RectDirection::*;
         let mut reverse_points = false;
         let mut last_horizontal_ix: Option<usize> = None;
         let mut last_vertical_ix: Option<usize> = None;
@@ -38,39 +49,34 @@ impl Polygon {
         for point_window in points.as_slice().windows(2) {
             let [p0, p1]: [_; 2] = point_window.try_into().ok().unwrap();
 
-            let direction = {
-                let directions_to = p0.directions_to(&p1);
-                assert_eq!(directions_to.len(), 1);
-                directions_to[0]
-            };
+            // Diagonal steps move both horizontally and vertically, so they
+            // count towards both kinds of inversion.
+            let direction = p0
+                .oct_direction_to(&p1)
+                .expect("polygon edges must be horizontal, vertical or at 45 degrees");
+            let (dx, dy) = direction.step();
 
             if !directions.is_empty() {
-                match direction {
-                    Left | Right => {
-                        if let Some(ix) = last_horizontal_ix {
-                            if directions[ix] != direction {
-                                horizontal_inversions += 1;
-                            }
+                if dx != 0 {
+                    if let Some(ix) = last_horizontal_ix {
+                        if directions[ix].step().0 != dx {
+                            horizontal_inversions += 1;
                         }
-                        directions.push(direction);
-                        last_horizontal_ix.replace(directions.len() - 1);
                     }
-                    Up | Down => {
-                        if let Some(ix) = last_vertical_ix {
-                            if directions[ix] != direction {
-                                vertical_inversions += 1;
-                            }
+                    last_horizontal_ix.replace(directions.len());
+                }
+                if dy != 0 {
+                    if let Some(ix) = last_vertical_ix {
+                        if directions[ix].step().1 != dy {
+                            vertical_inversions += 1;
                         }
-                        directions.push(direction);
-                        last_vertical_ix.replace(directions.len() - 1);
                     }
+                    last_vertical_ix.replace(directions.len());
                 }
+                directions.push(direction);
             } else {
-                match direction {
-                    Left | Down => {
-                        reverse_points = true;
-                    }
-                    _ => {}
+                if dx < 0 || (dx == 0 && dy < 0) {
+                    reverse_points = true;
                 }
                 directions.push(direction)
             }
@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+use std::{error::Error, fmt::Display};
+
+use crate::bbox::{BoundingBox, CalculateBoundingBox};
+use crate::boolean::Region;
+use crate::shapes::{CoordinateErr, Point, PointLike, Rect, Shape, ShapeLike, Shapes};
+use crate::transform::{Transform, Transformable, TransformedShape};
+use crate::units::Units;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LibraryErr {
+    /// An instance refers to a cell which is not in the library.
+    UnknownCell(String),
+    /// The cell contains an instance of itself, directly or further down.
+    RecursiveCell(String),
+    /// Flattening produced no shapes, or only shapes without any area, so
+    /// there is no bounding box.
+    Empty,
+    /// A placement of the named cell has a magnification of zero.
+    ZeroMagnification(String),
+    /// A placement of the named cell lies outside the `i32` range.
+    Overflow(String),
+}
+
+impl Display for LibraryErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for LibraryErr {}
+
+/// A placement of a cell inside another, like a GDS structure reference.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Instance {
+    pub cell: String,
+    pub transform: Transform,
+}
+
+/// A grid of placements of a cell, like a GDS array reference.
+///
+/// The placement in column `c` and row `r` is that of `transform`, moved by
+/// `c * column_pitch + r * row_pitch`. The pitches are in the coordinates of
+/// the parent cell, so they are not themselves transformed.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct ArrayInstance {
+    pub cell: String,
+    pub transform: Transform,
+    pub columns: u32,
+    pub rows: u32,
+    pub column_pitch: Point,
+    pub row_pitch: Point,
+}
+
+impl ArrayInstance {
+    /// The transform of each placement in the array, row by row, or an error
+    /// for those placements whose offset overflows.
+    pub fn transforms(&self) -> impl Iterator<Item = Result<Transform, CoordinateErr>> {
+        array_transforms(
+            self.transform,
+            self.columns,
+            self.rows,
+            self.column_pitch,
+            self.row_pitch,
+        )
+    }
+}
+
+impl ArchivedArrayInstance {
+    /// The transform of each placement in the array, row by row, or an error
+    /// for those placements whose offset overflows.
+    pub fn transforms(&self) -> impl Iterator<Item = Result<Transform, CoordinateErr>> {
+        array_transforms(
+            (&self.transform).into(),
+            self.columns,
+            self.rows,
+            self.column_pitch.into(),
+            self.row_pitch.into(),
+        )
+    }
+}
+
+fn array_transforms(
+    transform: Transform,
+    columns: u32,
+    rows: u32,
+    column_pitch: Point,
+    row_pitch: Point,
+) -> impl Iterator<Item = Result<Transform, CoordinateErr>> {
+    // Two products of a `u32` and an `i32` may not fit in an `i64` together.
+    let along = |offset: i32, c: u32, r: u32, column: i32, row: i32| {
+        i32::try_from(offset as i128 + c as i128 * column as i128 + r as i128 * row as i128)
+            .map_err(|_| CoordinateErr::Overflow)
+    };
+    (0..rows).flat_map(move |r| {
+        (0..columns).map(move |c| {
+            let offset = transform.offset;
+            Ok(Transform {
+                offset: Point::new(
+                    along(offset.x, c, r, column_pitch.x, row_pitch.x)?,
+                    along(offset.y, c, r, column_pitch.y, row_pitch.y)?,
+                ),
+                ..transform
+            })
+        })
+    })
+}
+
+#[derive(
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Cell {
+    pub name: String,
+    /// The shapes drawn in the cell itself, not counting its instances.
+    pub shapes: Vec<Shape>,
+    pub instances: Vec<Instance>,
+    pub arrays: Vec<ArrayInstance>,
+}
+
+impl Cell {
+    /// The names of the cells placed in this one.
+    pub fn children(&self) -> impl Iterator<Item = &str> {
+        self.instances
+            .iter()
+            .map(|i| i.cell.as_str())
+            .chain(self.arrays.iter().map(|a| a.cell.as_str()))
+    }
+}
+
+/// A set of cells which place each other, forming a hierarchy.
+#[derive(
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Library {
+    pub cells: Vec<Cell>,
+    /// The units of the coordinates of every cell.
+    #[serde(default)]
+    pub units: Units,
+}
+
+impl Library {
+    pub fn cell(&self, name: &str) -> Option<&Cell> {
+        self.cells.iter().find(|c| c.name == name)
+    }
+
+    /// The cells which are not placed in any other cell.
+    pub fn top_cells(&self) -> Vec<&Cell> {
+        let placed: std::collections::HashSet<&str> =
+            self.cells.iter().flat_map(|c| c.children()).collect();
+        self.cells
+            .iter()
+            .filter(|c| !placed.contains(c.name.as_str()))
+            .collect()
+    }
+
+    /// Flatten the hierarchy below the cell `top` into one list of shapes, in
+    /// the coordinates of `top`.
+    ///
+    /// If a `window` is given, only the parts of shapes inside it are kept.
+    /// Rectilinear shapes which cross the edge of the window are cut at it,
+    /// into polygons and rects. Shapes with diagonal edges cannot be cut, so
+    /// they are kept whole if they overlap the window at all.
+    pub fn flatten(&self, top: &str, window: Option<&BoundingBox>) -> Result<Shapes, LibraryErr> {
+        let cells: HashMap<&str, &Cell> = self.cells.iter().map(|c| (c.name.as_str(), c)).collect();
+        let mut shapes = Vec::new();
+        let mut stack = Vec::new();
+        flatten_cell(
+            &cells,
+            top,
+            &Transform::identity(),
+            window,
+            &mut stack,
+            &mut shapes,
+        )?;
+
+        Ok(Shapes {
+            bbox: shapes.try_bbox().ok_or(LibraryErr::Empty)?,
+            shapes,
+            units: self.units,
+        })
+    }
+
+    /// Flatten each of the [`top_cells`](Self::top_cells), as with
+    /// [`flatten`](Self::flatten). Top cells without any shapes are left out.
+    pub fn flatten_top_cells(
+        &self,
+        window: Option<&BoundingBox>,
+    ) -> Result<Vec<(String, Shapes)>, LibraryErr> {
+        let mut flattened = Vec::new();
+        for cell in self.top_cells() {
+            match self.flatten(&cell.name, window) {
+                Ok(shapes) => flattened.push((cell.name.clone(), shapes)),
+                Err(LibraryErr::Empty) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(flattened)
+    }
+}
+
+impl ArchivedLibrary {
+    pub fn cell(&self, name: &str) -> Option<&ArchivedCell> {
+        self.cells.iter().find(|c| c.name.as_str() == name)
+    }
+
+    /// Every placement of a cell in the hierarchy below `top`, including `top`
+    /// itself, with the transform into the coordinates of `top`.
+    pub fn placements(&self, top: &str) -> Result<Vec<(&ArchivedCell, Transform)>, LibraryErr> {
+        let cells: HashMap<&str, &ArchivedCell> =
+            self.cells.iter().map(|c| (c.name.as_str(), c)).collect();
+        let mut placements = Vec::new();
+        let mut stack = Vec::new();
+        place_archived_cell(
+            &cells,
+            top,
+            Transform::identity(),
+            &mut stack,
+            &mut placements,
+        )?;
+        Ok(placements)
+    }
+
+    /// The shapes in the hierarchy below `top`, in the coordinates of `top`.
+    ///
+    /// Unlike [`Library::flatten`], this works on the archive in place: only
+    /// the placements of cells are collected up front, and shapes are
+    /// transformed as they are read.
+    pub fn flatten_view(
+        &self,
+        top: &str,
+    ) -> Result<impl Iterator<Item = TransformedShape<'_>>, LibraryErr> {
+        Ok(self
+            .placements(top)?
+            .into_iter()
+            .flat_map(|(cell, transform)| {
+                cell.shapes
+                    .iter()
+                    .map(move |shape| TransformedShape::new(shape, transform))
+            }))
+    }
+}
+
+fn place_archived_cell<'a>(
+    cells: &HashMap<&str, &'a ArchivedCell>,
+    name: &str,
+    transform: Transform,
+    stack: &mut Vec<String>,
+    placements: &mut Vec<(&'a ArchivedCell, Transform)>,
+) -> Result<(), LibraryErr> {
+    let cell = *cells
+        .get(name)
+        .ok_or_else(|| LibraryErr::UnknownCell(name.to_string()))?;
+    if stack.iter().any(|n| n == name) {
+        return Err(LibraryErr::RecursiveCell(name.to_string()));
+    }
+    stack.push(name.to_string());
+    placements.push((cell, transform));
+
+    let placements_here = cell
+        .instances
+        .iter()
+        .map(|i| (i.cell.as_str(), Ok(Transform::from(&i.transform))))
+        .chain(
+            cell.arrays
+                .iter()
+                .flat_map(|a| a.transforms().map(move |t| (a.cell.as_str(), t))),
+        );
+    for (child, placement) in placements_here {
+        place_archived_cell(
+            cells,
+            child,
+            placement_in(child, placement, &transform)?,
+            stack,
+            placements,
+        )?;
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+/// The transform of a placement of the cell `child` into the coordinates of
+/// the top cell, given the transform of its parent.
+fn placement_in(
+    child: &str,
+    placement: Result<Transform, CoordinateErr>,
+    parent: &Transform,
+) -> Result<Transform, LibraryErr> {
+    let placement = placement.map_err(|_| LibraryErr::Overflow(child.to_string()))?;
+    if placement.magnification == 0 {
+        return Err(LibraryErr::ZeroMagnification(child.to_string()));
+    }
+    placement
+        .try_then(parent)
+        .map_err(|_| LibraryErr::Overflow(child.to_string()))
+}
+
+fn flatten_cell(
+    cells: &HashMap<&str, &Cell>,
+    name: &str,
+    transform: &Transform,
+    window: Option<&BoundingBox>,
+    stack: &mut Vec<String>,
+    shapes: &mut Vec<Shape>,
+) -> Result<(), LibraryErr> {
+    let cell = cells
+        .get(name)
+        .ok_or_else(|| LibraryErr::UnknownCell(name.to_string()))?;
+    if stack.iter().any(|n| n == name) {
+        return Err(LibraryErr::RecursiveCell(name.to_string()));
+    }
+    stack.push(name.to_string());
+
+    for shape in cell.shapes.iter() {
+        let shape = shape.transform(transform);
+        match window {
+            Some(window) => clip_shape(shape, window, shapes),
+            None => shapes.push(shape),
+        }
+    }
+
+    let placements = cell
+        .instances
+        .iter()
+        .map(|i| (i.cell.as_str(), Ok(i.transform)))
+        .chain(
+            cell.arrays
+                .iter()
+                .flat_map(|a| a.transforms().map(move |t| (a.cell.as_str(), t))),
+        );
+    for (child, placement) in placements {
+        flatten_cell(
+            cells,
+            child,
+            &placement_in(child, placement, transform)?,
+            window,
+            stack,
+            shapes,
+        )?;
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+/// Push the parts of `shape` inside `window`.
+fn clip_shape(shape: Shape, window: &BoundingBox, shapes: &mut Vec<Shape>) {
+    // A shape without any area has no part inside the window.
+    let Some(bbox) = shape.try_bbox() else {
+        return;
+    };
+    let (min, max) = (bbox.min(), bbox.max());
+    let (wmin, wmax) = (window.min(), window.max());
+
+    if max.x <= wmin.x || min.x >= wmax.x || max.y <= wmin.y || min.y >= wmax.y {
+        return;
+    }
+    let inside = min.x >= wmin.x && max.x <= wmax.x && min.y >= wmin.y && max.y <= wmax.y;
+    if inside {
+        shapes.push(shape);
+        return;
+    }
+
+    if let Shape::Rect(r) = shape {
+        shapes.push(Shape::Rect(Rect {
+            p0: Point::new(min.x.max(wmin.x), min.y.max(wmin.y)),
+            p1: Point::new(max.x.min(wmax.x), max.y.min(wmax.y)),
+            layer: r.layer,
+        }));
+        return;
+    }
+
+    let layer = shape.layer();
+    let window = Region::from_rect(&Rect {
+        p0: wmin,
+        p1: wmax,
+        layer,
+    });
+    match Region::from_shapes([&shape]) {
+        Ok(region) => shapes.extend(region.intersection(&window).shapes(layer)),
+        Err(_) => shapes.push(shape),
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use crate::bbox::{BoundingBox, CalculateBoundingBox};
+use crate::boolean::Region;
+use crate::density::{octilinear_area, octilinear_outline};
+use crate::shapes::{Point, PointLike, Rect, Shape, ShapeLike};
+
+/// The coverage of a layer over a grid of square pixels.
+///
+/// Pixels are stored row by row, as in an image: the first row is along the
+/// top of the window, where y is largest.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Raster {
+    pub window: BoundingBox,
+    /// The width and height of each pixel.
+    pub pixel_size: i32,
+    pub width: u32,
+    pub height: u32,
+    /// The fraction of each pixel covered by the layer, from 0 to 1.
+    pub coverage: Vec<f64>,
+    /// The indices of the shapes on the layer which could not be measured, and
+    /// are left out of the coverage: malformed paths, and shapes with edges
+    /// which are not at a multiple of 45 degrees.
+    pub unmeasured: Vec<usize>,
+}
+
+/// Rasterize the shapes on `layer` inside `window`, with pixels `pixel_size`
+/// wide. Pixels along the bottom and right of the window may stick out past it,
+/// but only the parts inside it are covered.
+///
+/// The coverage is of the union of the shapes, so overlapping shapes are only
+/// counted once. Shapes with diagonal edges cannot be merged into regions, so
+/// the pixels they overlap are measured as in
+/// [`density_map`](crate::density::density_map), which is exact for edges at
+/// multiples of 45 degrees. Shapes with other edges are left out, and listed
+/// in [`unmeasured`](Raster::unmeasured).
+pub fn rasterize(shapes: &[Shape], layer: u8, window: &BoundingBox, pixel_size: i32) -> Raster {
+    assert!(pixel_size > 0, "Pixels must have a positive size");
+    let (min, max) = (window.min(), window.max());
+    let pixels = |length: i32| ((length + pixel_size - 1) / pixel_size) as u32;
+    let mut raster = Raster {
+        window: *window,
+        pixel_size,
+        width: pixels(max.x - min.x),
+        height: pixels(max.y - min.y),
+        coverage: Vec::new(),
+        unmeasured: Vec::new(),
+    };
+    raster.coverage = vec![0.0; raster.width as usize * raster.height as usize];
+
+    let on_layer: Vec<(usize, &Shape)> = shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.layer() == layer)
+        .collect();
+    let (region, left_out) = Region::from_rectilinear_shapes(on_layer.iter().map(|(_, s)| *s));
+    let mut outlines = Vec::new();
+    for ix in left_out {
+        let (ix, shape) = on_layer[ix];
+        match (shape.try_bbox(), octilinear_outline(shape)) {
+            (Some(bbox), Some(outline)) => outlines.push((bbox, outline)),
+            _ => raster.unmeasured.push(ix),
+        }
+    }
+
+    let rects = region.rects(0);
+    for r in rects.iter() {
+        let (x0, x1) = (r.p0.x.max(min.x), r.p1.x.min(max.x));
+        let (y0, y1) = (r.p0.y.max(min.y), r.p1.y.min(max.y));
+        if x0 >= x1 || y0 >= y1 {
+            continue;
+        }
+        raster.add_rect(x0, y0, x1, y1);
+    }
+
+    if !outlines.is_empty() {
+        let mut pixels = BTreeSet::new();
+        for (bbox, _) in outlines.iter() {
+            let (x0, x1) = (bbox.min().x.max(min.x), bbox.max().x.min(max.x));
+            let (y0, y1) = (bbox.min().y.max(min.y), bbox.max().y.min(max.y));
+            if x0 >= x1 || y0 >= y1 {
+                continue;
+            }
+            let (c0, r0, c1, r1) = raster.pixel_range(x0, y0, x1, y1);
+            for row in r0..r1 {
+                pixels.extend((c0..c1).map(|column| (row, column)));
+            }
+        }
+        let pixel_area = pixel_size as f64 * pixel_size as f64;
+        for (row, column) in pixels {
+            let (x0, y0, x1, y1) = raster.pixel_bounds(column, row);
+            let pixel = Rect {
+                p0: Point::new(x0, y0),
+                p1: Point::new(x1, y1),
+                layer: 0,
+            };
+            let ix = raster.index(column, row);
+            raster.coverage[ix] = octilinear_area(&rects, &outlines, &pixel) / pixel_area;
+        }
+    }
+    raster
+}
+
+/// Rasterize every layer used by `shapes`, as with [`rasterize`].
+pub fn rasterize_layers(
+    shapes: &[Shape],
+    window: &BoundingBox,
+    pixel_size: i32,
+) -> BTreeMap<u8, Raster> {
+    let mut layers: Vec<u8> = shapes.iter().map(|s| s.layer()).collect();
+    layers.sort_unstable();
+    layers.dedup();
+    layers
+        .into_iter()
+        .map(|layer| (layer, rasterize(shapes, layer, window, pixel_size)))
+        .collect()
+}
+
+impl Raster {
+    /// The index into `coverage` of the pixel in `column` counting from the
+    /// left and `row` counting from the top.
+    pub fn index(&self, column: u32, row: u32) -> usize {
+        row as usize * self.width as usize + column as usize
+    }
+
+    /// The area of a pixel, clipped to the window.
+    fn pixel_bounds(&self, column: u32, row: u32) -> (i32, i32, i32, i32) {
+        let (min, max) = (self.window.min(), self.window.max());
+        let x0 = min.x + column as i32 * self.pixel_size;
+        let y1 = max.y - row as i32 * self.pixel_size;
+        (
+            x0,
+            (y1 - self.pixel_size).max(min.y),
+            (x0 + self.pixel_size).min(max.x),
+            y1,
+        )
+    }
+
+    /// The columns and rows of the pixels overlapping `[x0, x1)` by
+    /// `[y0, y1)`, which must lie in the window.
+    fn pixel_range(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> (u32, u32, u32, u32) {
+        let (min, max) = (self.window.min(), self.window.max());
+        let size = self.pixel_size;
+        (
+            ((x0 - min.x) / size) as u32,
+            ((max.y - y1) / size) as u32,
+            ((x1 - min.x + size - 1) / size) as u32,
+            ((max.y - y0 + size - 1) / size) as u32,
+        )
+    }
+
+    fn add_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let pixel_area = self.pixel_size as f64 * self.pixel_size as f64;
+        let (c0, r0, c1, r1) = self.pixel_range(x0, y0, x1, y1);
+        for row in r0..r1 {
+            for column in c0..c1 {
+                let (px0, py0, px1, py1) = self.pixel_bounds(column, row);
+                let width = x1.min(px1) - x0.max(px0);
+                let height = y1.min(py1) - y0.max(py0);
+                let ix = self.index(column, row);
+                self.coverage[ix] += (width as i64 * height as i64) as f64 / pixel_area;
+            }
+        }
+    }
+
+    /// Which pixels are covered by at least `threshold` of their area.
+    pub fn bitmap(&self, threshold: f64) -> Vec<bool> {
+        self.coverage.iter().map(|&c| c >= threshold).collect()
+    }
+
+    /// The coverage of each pixel as an 8-bit gray level, with covered pixels
+    /// white.
+    pub fn gray_levels(&self) -> Vec<u8> {
+        self.coverage
+            .iter()
+            .map(|&c| (c * 255.0).round() as u8)
+            .collect()
+    }
+
+    /// Write the raster as a binary PGM image.
+    pub fn write_pgm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.gray_levels())
+    }
+
+    /// Write the raster as an 8-bit grayscale PNG image.
+    ///
+    /// The image data is stored without compression, which keeps the encoder
+    /// small at the cost of larger files.
+    pub fn write_png<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend(self.width.to_be_bytes());
+        header.extend(self.height.to_be_bytes());
+        // Bit depth 8, grayscale, deflate, adaptive filtering, no interlace.
+        header.extend([8, 0, 0, 0, 0]);
+        write_png_chunk(&mut w, b"IHDR", &header)?;
+
+        // Each row starts with its filter type, which is always none.
+        let levels = self.gray_levels();
+        let mut scanlines = Vec::with_capacity(levels.len() + self.height as usize);
+        for row in levels.chunks(self.width.max(1) as usize) {
+            scanlines.push(0);
+            scanlines.extend(row);
+        }
+        write_png_chunk(&mut w, b"IDAT", &zlib_stored(&scanlines))?;
+        write_png_chunk(&mut w, b"IEND", &[])
+    }
+}
+
+fn write_png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    w.write_all(&crc.to_be_bytes())
+}
+
+/// A zlib stream holding `data` in uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+    let mut stream = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    stream.extend([0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        stream.push(last as u8);
+        let length = block.len() as u16;
+        stream.extend(length.to_le_bytes());
+        stream.extend((!length).to_le_bytes());
+        stream.extend(block);
+    }
+
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -1,15 +1,33 @@
 use crate::bbox::{BoundingBox, CalculateBoundingBox, UnvalidatedBoundingBox};
+use crate::units::Units;
 use rkyv::{vec::ArchivedVec, Archive, Deserialize, Serialize};
 
+mod octilinear;
 mod path;
 mod point;
 mod poly;
 mod rect;
+mod var_width_path;
 
+pub use octilinear::*;
 pub use path::*;
 pub use point::*;
 pub use poly::*;
 pub use rect::*;
+pub use var_width_path::*;
+
+/// The version of the archived layout of [`Shapes`] and
+/// [`Library`](crate::library::Library), to store beside archives so that
+/// readers can tell which layout they hold. It goes up whenever either layout
+/// changes:
+///
+/// 1. The original layout of [`Shapes`].
+/// 2. [`Path::end_cap`] added.
+/// 3. [`Shape::VarWidthPath`] added. This is the first layout of
+///    [`Library`](crate::library::Library).
+/// 4. [`Shapes::units`] and [`Library::units`](crate::library::Library::units)
+///    added.
+pub const ARCHIVE_VERSION: u32 = 4;
 
 #[derive(
     Debug, Eq, PartialEq, Archive, Deserialize, Serialize, serde::Serialize, serde::Deserialize,
@@ -19,13 +37,19 @@ pub use rect::*;
 pub struct Shapes {
     pub bbox: BoundingBox,
     pub shapes: Vec<Shape>,
+    /// The units of the coordinates, stored so that archives are
+    /// self-describing. Serialized files without them get the default units.
+    #[serde(default)]
+    pub units: Units,
 }
 
+/// A shape on one of the layers of a layout.
 #[derive(
     Debug,
     Eq,
     PartialEq,
     Hash,
+    Clone,
     Archive,
     Deserialize,
     Serialize,
@@ -38,93 +62,109 @@ pub enum Shape {
     Rect(Rect),
     Poly(Poly),
     Path(Path),
+    VarWidthPath(VarWidthPath),
 }
 
-impl CalculateBoundingBox for ArchivedShape {
-    fn bbox(&self) -> BoundingBox {
-        match self {
-            Self::Rect(r) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
-
-                bbox.min.x = r.p0.x.min(bbox.min.x);
-                bbox.min.y = r.p0.y.min(bbox.min.y);
-                bbox.max.x = r.p0.x.max(bbox.max.x);
-                bbox.max.y = r.p0.y.max(bbox.max.y);
-                bbox.min.x = r.p1.x.min(bbox.min.x);
-                bbox.min.y = r.p1.y.min(bbox.min.y);
-                bbox.max.x = r.p1.x.max(bbox.max.x);
-                bbox.max.y = r.p1.y.max(bbox.max.y);
+/// A borrowed view of one of the variants of a [`ShapeLike`].
+pub enum ShapeRef<'a, S: ShapeLike + ?Sized> {
+    Rect(&'a S::Rect),
+    Poly(&'a S::Poly),
+    Path(&'a S::Path),
+    VarWidthPath(&'a S::VarWidthPath),
+}
 
-                BoundingBox::new(bbox)
-            }
-            Self::Poly(p) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
-                for pt in p.points.iter() {
-                    bbox.min.x = pt.x.min(bbox.min.x);
-                    bbox.min.y = pt.y.min(bbox.min.y);
-                    bbox.max.x = pt.x.max(bbox.max.x);
-                    bbox.max.y = pt.y.max(bbox.max.y);
-                }
-                BoundingBox::new(bbox)
-            }
-            Self::Path(p) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
+/// A shape, whether owned or archived, so that algorithms over shapes can be
+/// written once for both.
+pub trait ShapeLike {
+    type Rect: RectLike;
+    type Poly: PolyLike;
+    type Path: PathLike;
+    type VarWidthPath: VarWidthPathLike;
+
+    fn shape_ref(&self) -> ShapeRef<'_, Self>;
+
+    fn layer(&self) -> u8 {
+        match self.shape_ref() {
+            ShapeRef::Rect(r) => r.layer(),
+            ShapeRef::Poly(p) => p.layer(),
+            ShapeRef::Path(p) => p.layer(),
+            ShapeRef::VarWidthPath(p) => p.layer(),
+        }
+    }
+}
 
-                let p = p.as_poly();
+impl ShapeLike for Shape {
+    type Rect = Rect;
+    type Poly = Poly;
+    type Path = Path;
+    type VarWidthPath = VarWidthPath;
 
-                for pt in p.points.iter() {
-                    bbox.min.x = pt.x.min(bbox.min.x);
-                    bbox.min.y = pt.y.min(bbox.min.y);
-                    bbox.max.x = pt.x.max(bbox.max.x);
-                    bbox.max.y = pt.y.max(bbox.max.y);
-                }
-                BoundingBox::new(bbox)
-            }
+    fn shape_ref(&self) -> ShapeRef<'_, Self> {
+        match self {
+            Shape::Rect(r) => ShapeRef::Rect(r),
+            Shape::Poly(p) => ShapeRef::Poly(p),
+            Shape::Path(p) => ShapeRef::Path(p),
+            Shape::VarWidthPath(p) => ShapeRef::VarWidthPath(p),
         }
     }
 }
 
-impl CalculateBoundingBox for Shape {
-    fn bbox(&self) -> BoundingBox {
-        match self {
-            Self::Rect(r) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
+impl ShapeLike for ArchivedShape {
+    type Rect = ArchivedRect;
+    type Poly = ArchivedPoly;
+    type Path = ArchivedPath;
+    type VarWidthPath = ArchivedVarWidthPath;
 
-                bbox.min.x = r.p0.x.min(bbox.min.x);
-                bbox.min.y = r.p0.y.min(bbox.min.y);
-                bbox.max.x = r.p0.x.max(bbox.max.x);
-                bbox.max.y = r.p0.y.max(bbox.max.y);
-                bbox.min.x = r.p1.x.min(bbox.min.x);
-                bbox.min.y = r.p1.y.min(bbox.min.y);
-                bbox.max.x = r.p1.x.max(bbox.max.x);
-                bbox.max.y = r.p1.y.max(bbox.max.y);
+    fn shape_ref(&self) -> ShapeRef<'_, Self> {
+        match self {
+            ArchivedShape::Rect(r) => ShapeRef::Rect(r),
+            ArchivedShape::Poly(p) => ShapeRef::Poly(p),
+            ArchivedShape::Path(p) => ShapeRef::Path(p),
+            ArchivedShape::VarWidthPath(p) => ShapeRef::VarWidthPath(p),
+        }
+    }
+}
 
-                BoundingBox::new(bbox)
-            }
-            Self::Poly(p) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
-                for pt in p.points.iter() {
-                    bbox.min.x = pt.x.min(bbox.min.x);
-                    bbox.min.y = pt.y.min(bbox.min.y);
-                    bbox.max.x = pt.x.max(bbox.max.x);
-                    bbox.max.y = pt.y.max(bbox.max.y);
-                }
-                BoundingBox::new(bbox)
+impl Shape {
+    /// Whether every edge of the shape is horizontal or vertical, so that it
+    /// can be turned into a [`Region`](crate::boolean::Region).
+    ///
+    /// Paths must also be well formed, with end caps that do not cut back
+    /// past the ends of their segments, and one width per segment.
+    pub fn is_rectilinear(&self) -> bool {
+        let rectilinear = |points: &[Point]| {
+            points
+                .windows(2)
+                .all(|w| w[0].x == w[1].x || w[0].y == w[1].y)
+        };
+        match self {
+            Shape::Rect(_) => true,
+            Shape::Poly(p) => {
+                rectilinear(&p.points)
+                    && p.points
+                        .first()
+                        .zip(p.points.last())
+                        .is_none_or(|(first, last)| rectilinear(&[*last, *first]))
             }
-            Self::Path(p) => {
-                let mut bbox = UnvalidatedBoundingBox::invalid();
+            Shape::Path(p) => p.segment_rects().is_ok(),
+            Shape::VarWidthPath(p) => p.segment_rects().is_ok(),
+        }
+    }
+}
 
-                let p = p.as_poly();
+impl CalculateBoundingBox for ArchivedShape {
+    fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        bbox.update(self);
+        bbox.validate()
+    }
+}
 
-                for pt in p.points.iter() {
-                    bbox.min.x = pt.x.min(bbox.min.x);
-                    bbox.min.y = pt.y.min(bbox.min.y);
-                    bbox.max.x = pt.x.max(bbox.max.x);
-                    bbox.max.y = pt.y.max(bbox.max.y);
-                }
-                BoundingBox::new(bbox)
-            }
-        }
+impl CalculateBoundingBox for Shape {
+    fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        bbox.update(self);
+        bbox.validate()
     }
 }
 
@@ -168,7 +208,8 @@ pub fn bbbox(arc: std::sync::Arc<&ArchivedVec<ArchivedShape>>) -> BoundingBox {
         }
     });
 
-    BoundingBox::new(bbox)
+    bbox.validate()
+        .expect("bounding boxes need something with an area to bound")
 }
 
 // impl CalculateBoundingBox for std::sync::Arc<&ArchivedVec<ArchivedShape>> {
@@ -210,12 +251,11 @@ pub fn bbbox(arc: std::sync::Arc<&ArchivedVec<ArchivedShape>>) -> BoundingBox {
 // }
 
 impl CalculateBoundingBox for Vec<Shape> {
-    fn bbox(&self) -> BoundingBox {
-        let mut shapes = self.iter();
-        let mut bbox = shapes.next().unwrap().bbox();
-        for s in shapes {
-            bbox.union(&s.bbox());
+    fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        for s in self.iter() {
+            bbox.update(s);
         }
-        bbox
+        bbox.validate()
     }
 }
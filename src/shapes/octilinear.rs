@@ -0,0 +1,302 @@
+use crate::shapes::{
+    is_simple_outline, PathEndCap, PathErr, Point, PointLike, Poly, RectDirection,
+};
+
+/// One of the eight directions an octilinear (45 degree) path or polygon edge
+/// can move in.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum OctDirection {
+    Right,
+    UpRight,
+    Up,
+    UpLeft,
+    Left,
+    DownLeft,
+    Down,
+    DownRight,
+}
+
+impl OctDirection {
+    /// The direction of a step of `dx` and `dy`, if it is horizontal, vertical
+    /// or at 45 degrees.
+    pub fn from_step(dx: i64, dy: i64) -> Option<OctDirection> {
+        use OctDirection::*;
+        if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+            return None;
+        }
+        match (dx.signum(), dy.signum()) {
+            (1, 0) => Some(Right),
+            (1, 1) => Some(UpRight),
+            (0, 1) => Some(Up),
+            (-1, 1) => Some(UpLeft),
+            (-1, 0) => Some(Left),
+            (-1, -1) => Some(DownLeft),
+            (0, -1) => Some(Down),
+            (1, -1) => Some(DownRight),
+            _ => None,
+        }
+    }
+
+    /// The unit step in x and y taken by a move in this direction.
+    pub fn step(&self) -> (i32, i32) {
+        match self {
+            OctDirection::Right => (1, 0),
+            OctDirection::UpRight => (1, 1),
+            OctDirection::Up => (0, 1),
+            OctDirection::UpLeft => (-1, 1),
+            OctDirection::Left => (-1, 0),
+            OctDirection::DownLeft => (-1, -1),
+            OctDirection::Down => (0, -1),
+            OctDirection::DownRight => (1, -1),
+        }
+    }
+
+    pub fn opposite(&self) -> OctDirection {
+        let (dx, dy) = self.step();
+        OctDirection::from_step(-dx as i64, -dy as i64).unwrap()
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.as_rect_direction().is_none()
+    }
+
+    /// This direction as a [`RectDirection`], unless it is diagonal.
+    pub fn as_rect_direction(&self) -> Option<RectDirection> {
+        match self {
+            OctDirection::Right => Some(RectDirection::Right),
+            OctDirection::Up => Some(RectDirection::Up),
+            OctDirection::Left => Some(RectDirection::Left),
+            OctDirection::Down => Some(RectDirection::Down),
+            _ => None,
+        }
+    }
+}
+
+impl From<RectDirection> for OctDirection {
+    fn from(direction: RectDirection) -> Self {
+        match direction {
+            RectDirection::Right => OctDirection::Right,
+            RectDirection::Up => OctDirection::Up,
+            RectDirection::Left => OctDirection::Left,
+            RectDirection::Down => OctDirection::Down,
+        }
+    }
+}
+
+/// The line of points `x` with `n . x == c`.
+#[derive(Debug, Clone, Copy)]
+struct Line {
+    n: (f64, f64),
+    c: f64,
+}
+
+impl Line {
+    /// The point where two non-parallel lines cross, rounded to the grid.
+    ///
+    /// If `self` is a diagonal line through grid points, the point is rounded
+    /// to one of those, so that edges along it stay at 45 degrees even where
+    /// the lines cross halfway between grid points.
+    fn intersection(&self, other: &Line) -> Point {
+        let det = self.n.0 * other.n.1 - self.n.1 * other.n.0;
+        let x = (self.c * other.n.1 - other.c * self.n.1) / det;
+        let y = (self.n.0 * other.c - other.n.0 * self.c) / det;
+        if self.n.0.abs() == self.n.1.abs() && self.c.fract() == 0.0 {
+            // Halves are always rounded up, so that both ends of an edge
+            // across the path move the same way.
+            let x = (x + 0.5).floor();
+            let y = (self.c - self.n.0 * x) / self.n.1;
+            return Point::new(x as i32, y as i32);
+        }
+        Point::new(x.round() as i32, y.round() as i32)
+    }
+}
+
+/// The side of a path, looking along it.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) enum Side {
+    Right,
+    Left,
+}
+
+/// The lines bounding a path of a given width and end cap, from which the
+/// vertices of its outline are found.
+///
+/// As for rectilinear paths, the extra unit of an odd width goes to the +x and
+/// +y sides. Diagonal edges lie off the grid in general, so each side of a
+/// diagonal segment is moved to a nearby line that keeps it at 45 degrees,
+/// and each vertex is rounded to a grid point on the sides it joins.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PathLines {
+    half_width: f64,
+    // Odd width paths are laid out as if their centerline were shifted by half
+    // a unit in x and y, which puts the extra unit on the +x and +y sides.
+    shift: f64,
+    end_cap: PathEndCap,
+}
+
+impl PathLines {
+    pub(crate) fn new(width: u32, end_cap: PathEndCap) -> Self {
+        PathLines {
+            half_width: width as f64 / 2.0,
+            shift: if width % 2 == 1 { 0.5 } else { 0.0 },
+            end_cap,
+        }
+    }
+
+    fn centered<P: PointLike>(&self, p: P) -> (f64, f64) {
+        (p.x() as f64 + self.shift, p.y() as f64 + self.shift)
+    }
+
+    /// The side of the segment through `p` running in `direction`.
+    fn side<P: PointLike>(&self, p: P, direction: OctDirection, side: Side) -> Line {
+        let (dx, dy) = direction.step();
+        let n = (dy as f64, -dx as f64);
+        let sign = match side {
+            Side::Right => 1.0,
+            Side::Left => -1.0,
+        };
+        let (x, y) = self.centered(p);
+        let exact = n.0 * x + n.1 * y + sign * self.half_width * n.0.hypot(n.1);
+        if !direction.is_diagonal() {
+            return Line {
+                n,
+                c: exact.round(),
+            };
+        }
+
+        // Where two diagonal segments meet at a right angle, their sides only
+        // cross at a grid point if their constants add up to an even number.
+        // Along a run of diagonal segments, `n . p` always has the same
+        // parity, so rounding the offset from it to a fixed parity keeps every
+        // such joint on the grid, at the cost of moving sides by up to a unit
+        // rather than half of one.
+        let on_centerline = n.0 * p.x() as f64 + n.1 * p.y() as f64;
+        let parity = (self.half_width * std::f64::consts::SQRT_2)
+            .round()
+            .rem_euclid(2.0);
+        let offset = ((exact - on_centerline - parity) / 2.0).round() * 2.0 + parity;
+        Line {
+            n,
+            c: on_centerline + offset,
+        }
+    }
+
+    /// The line across the path at `p`, which it passes in `direction`,
+    /// moved along the path by `extension`.
+    ///
+    /// Half width extensions are measured from the shifted centerline, while
+    /// the other caps are measured from `p` itself, so that flush paths end
+    /// exactly on their end points.
+    fn across<P: PointLike>(&self, p: P, direction: OctDirection, extension: Option<f64>) -> Line {
+        let (dx, dy) = direction.step();
+        let n = (dx as f64, dy as f64);
+        let ((x, y), extension) = match extension {
+            None => (self.centered(p), self.half_width),
+            Some(extension) => ((p.x() as f64, p.y() as f64), extension),
+        };
+        Line {
+            n,
+            c: n.0 * x + n.1 * y + extension * n.0.hypot(n.1),
+        }
+    }
+
+    fn begin<P: PointLike>(&self, p: P, direction: OctDirection) -> Line {
+        match self.end_cap {
+            PathEndCap::Flush => self.across(p, direction, Some(0.0)),
+            PathEndCap::HalfWidth => self.across(p, direction.opposite(), None),
+            PathEndCap::Custom { begin, .. } => self.across(p, direction, Some(-begin as f64)),
+        }
+    }
+
+    fn end<P: PointLike>(&self, p: P, direction: OctDirection) -> Line {
+        match self.end_cap {
+            PathEndCap::Flush => self.across(p, direction, Some(0.0)),
+            PathEndCap::HalfWidth => self.across(p, direction, None),
+            PathEndCap::Custom { end, .. } => self.across(p, direction, Some(end as f64)),
+        }
+    }
+
+    /// The vertex on `side` of the outline at the path point `p`, which the
+    /// path enters in direction `incoming` and leaves in direction `outgoing`.
+    /// These are `None` at the ends of the path, which must not both be.
+    ///
+    /// Where the path doubles back on itself, the joint is squared off as if
+    /// by a half width end cap.
+    pub(crate) fn vertex<P: PointLike>(
+        &self,
+        p: P,
+        incoming: Option<OctDirection>,
+        outgoing: Option<OctDirection>,
+        side: Side,
+    ) -> Point {
+        // The side along the path comes first, so that the vertex is rounded
+        // onto it.
+        let (before, after) = match (incoming, outgoing) {
+            (None, Some(outgoing)) => (self.side(p, outgoing, side), self.begin(p, outgoing)),
+            (Some(incoming), None) => (self.side(p, incoming, side), self.end(p, incoming)),
+            (Some(incoming), Some(outgoing)) if incoming == outgoing.opposite() => {
+                (self.side(p, incoming, side), self.across(p, incoming, None))
+            }
+            (Some(incoming), Some(outgoing)) => {
+                (self.side(p, incoming, side), self.side(p, outgoing, side))
+            }
+            (None, None) => unreachable!("A path has at least one segment"),
+        };
+        before.intersection(&after)
+    }
+}
+
+/// Convert a normalized octilinear path into a polygon with mitered corners,
+/// laid out as described for [`PathLines`].
+pub(crate) fn octilinear_path_to_poly<P: PointLike>(
+    points: &[P],
+    width: u32,
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Result<Poly, PathErr> {
+    let lines = PathLines::new(width, end_cap);
+    let num_points = points.len();
+    let directions: Vec<OctDirection> = points
+        .windows(2)
+        .map(|w| w[0].oct_direction_to(&w[1]).unwrap())
+        .collect();
+
+    let outline = |side: Side| -> Result<Vec<Point>, PathErr> {
+        let vertices: Vec<Point> = (0..num_points)
+            .map(|ix| {
+                let incoming = ix.checked_sub(1).map(|ix| directions[ix]);
+                let outgoing = directions.get(ix).copied();
+                lines.vertex(points[ix], incoming, outgoing, side)
+            })
+            .collect();
+
+        // Each side has to run forwards along its segment. If it runs
+        // backwards, the joints at the ends of the segment are further apart
+        // than the segment is long.
+        for (ix, w) in vertices.windows(2).enumerate() {
+            let (dx, dy) = directions[ix].step();
+            if (w[1].x as i64 - w[0].x as i64) * dx as i64
+                + (w[1].y as i64 - w[0].y as i64) * (dy as i64)
+                < 0
+            {
+                return Err(PathErr::FoldsOver);
+            }
+        }
+        Ok(vertices)
+    };
+
+    let right = outline(Side::Right)?;
+    let left = outline(Side::Left)?;
+
+    let mut points: Vec<Point> = right.into_iter().chain(left.into_iter().rev()).collect();
+    // Rounding can make neighbouring vertices coincide.
+    points.dedup();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if !is_simple_outline(&points) {
+        return Err(PathErr::SelfIntersects);
+    }
+
+    Ok(Poly { points, layer })
+}
@@ -1,12 +1,53 @@
-use crate::shapes::{Point, PointLike, Poly, RectDirection};
+use std::{error::Error, fmt::Display};
+
+use crate::boolean::Region;
+use crate::shapes::{
+    is_simple_outline, octilinear_path_to_poly, ArchivedPoint, OctDirection, PathLines, Point,
+    PointLike, Poly, Rect, RectDirection, Side,
+};
 use rkyv::{Archive, Deserialize, Serialize};
 
-/// A path that can be converted into a rectilinear polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathErr {
+    NotEnoughPoints,
+    /// The path takes a step which is not horizontal, vertical or at 45
+    /// degrees.
+    NotOctilinear,
+    /// The path takes a diagonal step, where only horizontal and vertical
+    /// steps are supported.
+    NotRectilinear,
+    /// A variable width path does not have exactly one width per segment.
+    WrongNumberOfWidths,
+    /// The path doubles back on itself at the point with this index.
+    UTurn(usize),
+    /// The path loops back onto itself and encloses a hole, which a [`Poly`]
+    /// cannot represent.
+    EnclosesHole,
+    /// A segment of an octilinear path is too short for the joints at its
+    /// ends, or a negative end cap cuts back past the other end of its
+    /// segment, so the outline of the path would fold back over itself.
+    FoldsOver,
+    /// The outline of an octilinear path crosses or touches itself. Unlike
+    /// rectilinear paths, these are not traced from the union of their
+    /// segments, since [`Region`] only handles rectilinear geometry.
+    SelfIntersects,
+}
+
+impl Display for PathErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for PathErr {}
+
+/// A path that can be converted into a rectilinear or octilinear polygon.
 #[derive(
     Debug,
     Eq,
     PartialEq,
     Hash,
+    Clone,
     Archive,
     Deserialize,
     Serialize,
@@ -19,111 +60,505 @@ pub struct Path {
     pub points: Vec<Point>,
     pub width: u32,
     pub layer: u8,
+    #[serde(default)]
+    pub end_cap: PathEndCap,
+}
+
+/// How the ends of a path are capped, following the GDS path types.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Copy,
+    Default,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub enum PathEndCap {
+    /// Path type 0: the path ends flush with its end points.
+    #[default]
+    Flush,
+    /// Path type 2: the path extends past its end points by half its width.
+    HalfWidth,
+    /// Path type 4: the path extends past its begin and end points by the given
+    /// amounts, which may be negative.
+    Custom { begin: i32, end: i32 },
+}
+
+impl From<&ArchivedPathEndCap> for PathEndCap {
+    fn from(end_cap: &ArchivedPathEndCap) -> Self {
+        match end_cap {
+            ArchivedPathEndCap::Flush => PathEndCap::Flush,
+            ArchivedPathEndCap::HalfWidth => PathEndCap::HalfWidth,
+            ArchivedPathEndCap::Custom { begin, end } => PathEndCap::Custom {
+                begin: *begin,
+                end: *end,
+            },
+        }
+    }
+}
+
+/// How far the two sides of a path lie from its centerline: `lo` towards
+/// decreasing x (or y), and `hi` towards increasing x (or y).
+///
+/// For odd widths, the extra unit goes to the `hi` side, so that polygons
+/// always have exactly the width of the path.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct SideOffsets {
+    pub lo: i32,
+    pub hi: i32,
+}
+
+impl SideOffsets {
+    pub fn new(width: u32) -> Self {
+        let lo = width / 2;
+        SideOffsets {
+            lo: lo as i32,
+            hi: (width - lo) as i32,
+        }
+    }
+}
+
+impl PathEndCap {
+    /// How far the path extends past its begin and end points, given the
+    /// direction it starts and ends in.
+    ///
+    /// Half width extensions are taken from the [`SideOffsets`], so that a
+    /// path and its reverse produce the same polygon.
+    pub fn extensions(
+        &self,
+        offsets: SideOffsets,
+        start_direction: RectDirection,
+        end_direction: RectDirection,
+    ) -> (i32, i32) {
+        let towards_lo = |direction| matches!(direction, RectDirection::Left | RectDirection::Down);
+        match *self {
+            PathEndCap::Flush => (0, 0),
+            PathEndCap::HalfWidth => (
+                if towards_lo(start_direction) {
+                    offsets.hi
+                } else {
+                    offsets.lo
+                },
+                if towards_lo(end_direction) {
+                    offsets.lo
+                } else {
+                    offsets.hi
+                },
+            ),
+            PathEndCap::Custom { begin, end } => (begin, end),
+        }
+    }
+}
+
+/// Remove repeated points from a path, and merge runs of collinear segments
+/// into one segment.
+///
+/// Paths which double back on themselves are rejected, since their polygon
+/// would have to overlap itself.
+pub fn normalize_path_points<P: PointLike>(points: &[P]) -> Result<Vec<P>, PathErr> {
+    let mut normalized: Vec<P> = Vec::with_capacity(points.len());
+    let mut directions: Vec<OctDirection> = Vec::with_capacity(points.len());
+    // The index of each normalized point in `points`.
+    let mut indices: Vec<usize> = Vec::with_capacity(points.len());
+
+    for (ix, &p) in points.iter().enumerate() {
+        let Some(last) = normalized.last() else {
+            normalized.push(p);
+            indices.push(ix);
+            continue;
+        };
+
+        if last.x() == p.x() && last.y() == p.y() {
+            continue;
+        }
+        let Some(direction) = last.oct_direction_to(&p) else {
+            return Err(PathErr::NotOctilinear);
+        };
+
+        match directions.last() {
+            Some(&last_direction) if last_direction == direction => {
+                normalized.pop();
+                directions.pop();
+                indices.pop();
+            }
+            Some(&last_direction) if last_direction == direction.opposite() => {
+                return Err(PathErr::UTurn(indices[indices.len() - 1]));
+            }
+            _ => {}
+        }
+
+        normalized.push(p);
+        directions.push(direction);
+        indices.push(ix);
+    }
+
+    if normalized.len() < 2 {
+        return Err(PathErr::NotEnoughPoints);
+    }
+    Ok(normalized)
+}
+
+/// The rectangles swept out by each segment of a rectilinear path, with square
+/// joints. Their union is the area covered by the path, even where it doubles
+/// back on itself.
+///
+/// Paths with diagonal steps are rejected, since they cannot be covered by
+/// rectangles, as are paths whose negative end caps cut back past the other
+/// end of their segment.
+pub fn path_segment_rects<P: PointLike>(
+    points: &[P],
+    width: u32,
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Result<Vec<Rect>, PathErr> {
+    let points: Vec<P> = points
+        .iter()
+        .enumerate()
+        .filter(|&(ix, p)| ix == 0 || p.x() != points[ix - 1].x() || p.y() != points[ix - 1].y())
+        .map(|(_, &p)| p)
+        .collect();
+    for w in points.windows(2) {
+        match w[0].oct_direction_to(&w[1]) {
+            None => return Err(PathErr::NotOctilinear),
+            Some(direction) if direction.is_diagonal() => return Err(PathErr::NotRectilinear),
+            Some(_) => {}
+        }
+    }
+    if points.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    let offsets = SideOffsets::new(width);
+    let num_segments = points.len() - 1;
+    let (begin_extension, end_extension) = end_cap.extensions(
+        offsets,
+        points[0].simple_directions_to(&points[1]),
+        points[num_segments - 1].simple_directions_to(&points[num_segments]),
+    );
+    let last = num_segments - 1;
+    let folds_over = if num_segments == 1 {
+        cuts_back(points[0], points[1], begin_extension, end_extension)
+    } else {
+        cuts_back(points[0], points[1], begin_extension, 0)
+            || cuts_back(points[last], points[last + 1], 0, end_extension)
+    };
+    if folds_over {
+        return Err(PathErr::FoldsOver);
+    }
+
+    Ok((0..num_segments)
+        .map(|ix| {
+            let (p0, p1) = (points[ix], points[ix + 1]);
+            let direction = p0.simple_directions_to(&p1);
+            // Joints where the path turns extend by the side offsets, just like
+            // a half width end cap, so that the corner is filled in. Joints
+            // between collinear segments must not extend, or they would poke
+            // out past the ends of the path.
+            let half_width = PathEndCap::HalfWidth.extensions(offsets, direction, direction);
+            let begin = if ix == 0 {
+                begin_extension
+            } else if points[ix - 1].simple_directions_to(&p0) == direction {
+                0
+            } else {
+                half_width.0
+            };
+            let end = if ix == num_segments - 1 {
+                end_extension
+            } else if p1.simple_directions_to(&points[ix + 2]) == direction {
+                0
+            } else {
+                half_width.1
+            };
+            let (p0, p1) = (
+                extend_point(p0, direction, -begin),
+                extend_point(p1, direction, end),
+            );
+
+            let (x0, x1) = (p0.x().min(p1.x()), p0.x().max(p1.x()));
+            let (y0, y1) = (p0.y().min(p1.y()), p0.y().max(p1.y()));
+            let (x0, x1, y0, y1) = match direction {
+                RectDirection::Left | RectDirection::Right => {
+                    (x0, x1, y0 - offsets.lo, y1 + offsets.hi)
+                }
+                RectDirection::Up | RectDirection::Down => {
+                    (x0 - offsets.lo, x1 + offsets.hi, y0, y1)
+                }
+            };
+            Rect {
+                p0: Point::new(x0, y0),
+                p1: Point::new(x1, y1),
+                layer,
+            }
+        })
+        .collect())
+}
+
+/// The vertices of the outline of a path, computed one at a time from the
+/// path's points without allocating: first the right side going forwards, and
+/// then the left side going backwards.
+///
+/// Repeated points and collinear runs are skipped as they are reached, so for
+/// paths which [`path_to_poly`] converts by offsetting their sides, these are
+/// the vertices of that polygon. Otherwise the outline may overlap itself, and
+/// at U-turns the path is squared off as if by a half width end cap, but the
+/// bounding box of the vertices is still that of the area covered by the path.
+#[derive(Debug, Clone)]
+pub struct PathOutline<'a, P> {
+    points: &'a [P],
+    lines: PathLines,
+    /// The next point on the right side, going forwards.
+    forward: Option<usize>,
+    /// The next point on the left side, going backwards.
+    backward: Option<usize>,
+}
+
+impl<'a, P: PointLike> PathOutline<'a, P> {
+    /// The outline of a path, unless it has fewer than two distinct points, or
+    /// takes a step which is not octilinear.
+    pub fn new(points: &'a [P], width: u32, end_cap: PathEndCap) -> Option<Self> {
+        let mut distinct = points
+            .windows(2)
+            .filter(|w| w[0].x() != w[1].x() || w[0].y() != w[1].y())
+            .peekable();
+        distinct.peek()?;
+        if !distinct.all(|w| w[0].oct_direction_to(&w[1]).is_some()) {
+            return None;
+        }
+        Some(PathOutline {
+            points,
+            lines: PathLines::new(width, end_cap),
+            forward: Some(0),
+            backward: Some(points.len() - 1),
+        })
+    }
+
+    fn same(&self, a: usize, b: usize) -> bool {
+        self.points[a].x() == self.points[b].x() && self.points[a].y() == self.points[b].y()
+    }
+
+    /// The index of the next point after `ix` which differs from it.
+    fn next_distinct(&self, ix: usize) -> Option<usize> {
+        (ix + 1..self.points.len()).find(|&next| !self.same(ix, next))
+    }
+
+    /// The index of the last point before `ix` which differs from it.
+    fn previous_distinct(&self, ix: usize) -> Option<usize> {
+        (0..ix).rev().find(|&previous| !self.same(ix, previous))
+    }
+
+    fn direction(&self, from: usize, to: usize) -> OctDirection {
+        self.points[from]
+            .oct_direction_to(&self.points[to])
+            .unwrap()
+    }
+
+    /// The directions in which the path enters and leaves the point at `ix`.
+    fn directions(&self, ix: usize) -> (Option<OctDirection>, Option<OctDirection>) {
+        (
+            self.previous_distinct(ix)
+                .map(|previous| self.direction(previous, ix)),
+            self.next_distinct(ix).map(|next| self.direction(ix, next)),
+        )
+    }
+
+    /// Whether the point at `ix` is a vertex of the path, rather than a
+    /// repeated or collinear point.
+    fn is_corner(&self, ix: usize) -> bool {
+        match self.directions(ix) {
+            (Some(incoming), Some(outgoing)) => incoming != outgoing,
+            _ => true,
+        }
+    }
+
+    fn vertex(&self, ix: usize, side: Side) -> Point {
+        let (incoming, outgoing) = self.directions(ix);
+        self.lines.vertex(self.points[ix], incoming, outgoing, side)
+    }
+}
+
+impl<P: PointLike> Iterator for PathOutline<'_, P> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if let Some(ix) = self.forward {
+            let mut next = self.next_distinct(ix);
+            while let Some(candidate) = next.filter(|&c| !self.is_corner(c)) {
+                next = self.next_distinct(candidate);
+            }
+            self.forward = next;
+            return Some(self.vertex(ix, Side::Right));
+        }
+
+        let ix = self.backward?;
+        let mut previous = self.previous_distinct(ix);
+        while let Some(candidate) = previous.filter(|&c| !self.is_corner(c)) {
+            previous = self.previous_distinct(candidate);
+        }
+        self.backward = previous;
+        Some(self.vertex(ix, Side::Left))
+    }
+}
+
+/// Points whose bounding box is that of the area covered by a path, found
+/// without allocating.
+///
+/// These are the vertices of its [`PathOutline`]. Paths which are not
+/// octilinear are approximated by squares around their points, which are large
+/// enough to cover their end caps.
+pub fn path_extent_points<P: PointLike>(
+    points: &[P],
+    width: u32,
+    end_cap: PathEndCap,
+) -> impl Iterator<Item = Point> + '_ {
+    let outline = PathOutline::new(points, width, end_cap);
+    let approximated = if outline.is_none() { points } else { &[] };
+
+    let margin = width.div_ceil(2) as i32
+        + match end_cap {
+            PathEndCap::Custom { begin, end } => begin.max(end).max(0),
+            _ => 0,
+        };
+    let squares = approximated.iter().flat_map(move |p| {
+        [
+            Point::new(p.x() - margin, p.y() - margin),
+            Point::new(p.x() + margin, p.y() + margin),
+        ]
+    });
+    outline.into_iter().flatten().chain(squares)
+}
+
+/// Whether negative end cap extensions of `begin` at `p0` and `end` at `p1` cut
+/// the segment between them back past its other end.
+pub(crate) fn cuts_back<P: PointLike>(p0: P, p1: P, begin: i32, end: i32) -> bool {
+    let length = (p1.x() as i64 - p0.x() as i64).abs() + (p1.y() as i64 - p0.y() as i64).abs();
+    length + (begin as i64) + (end as i64) < 0
+}
+
+/// Move a point by `distance` in `direction`.
+pub fn extend_point<P: PointLike>(p: P, direction: RectDirection, distance: i32) -> P {
+    match direction {
+        RectDirection::Right => P::new(p.x() + distance, p.y()),
+        RectDirection::Left => P::new(p.x() - distance, p.y()),
+        RectDirection::Up => P::new(p.x(), p.y() + distance),
+        RectDirection::Down => P::new(p.x(), p.y() - distance),
+    }
 }
 
 pub fn shift_pure_right<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
-    forward_poly_points.push(P::new(p0.x(), p0.y() - half_width));
-    backward_poly_points.push(P::new(p0.x(), p0.y() + half_width));
+    forward_poly_points.push(P::new(p0.x(), p0.y() - offsets.lo));
+    backward_poly_points.push(P::new(p0.x(), p0.y() + offsets.hi));
 }
 
 pub fn shift_pure_left<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
-    shift_pure_right(backward_poly_points, forward_poly_points, p0, half_width)
+    shift_pure_right(backward_poly_points, forward_poly_points, p0, offsets)
 }
 
 pub fn shift_pure_up<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
-    forward_poly_points.push(P::new(p0.x() + half_width, p0.y()));
-    backward_poly_points.push(P::new(p0.x() - half_width, p0.y()));
+    forward_poly_points.push(P::new(p0.x() + offsets.hi, p0.y()));
+    backward_poly_points.push(P::new(p0.x() - offsets.lo, p0.y()));
 }
 
 pub fn shift_pure_down<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
-    shift_pure_up(backward_poly_points, forward_poly_points, p0, half_width)
+    shift_pure_up(backward_poly_points, forward_poly_points, p0, offsets)
 }
 
 pub fn shift_right_up<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
     // println!("shift right up");
-    forward_poly_points.push(P::new(p0.x() + half_width, p0.y() - half_width));
-    backward_poly_points.push(P::new(p0.x() - half_width, p0.y() + half_width));
+    forward_poly_points.push(P::new(p0.x() + offsets.hi, p0.y() - offsets.lo));
+    backward_poly_points.push(P::new(p0.x() - offsets.lo, p0.y() + offsets.hi));
 }
 
 pub fn shift_left_down<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
     // println!("shift left down (calling shift right up)");
-    shift_right_up(backward_poly_points, forward_poly_points, p0, half_width);
+    shift_right_up(backward_poly_points, forward_poly_points, p0, offsets);
 }
 
 pub fn shift_right_down<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
     // println!("shift right down");
-    forward_poly_points.push(P::new(p0.x() - half_width, p0.y() - half_width));
-    backward_poly_points.push(P::new(p0.x() + half_width, p0.y() + half_width));
+    forward_poly_points.push(P::new(p0.x() - offsets.lo, p0.y() - offsets.lo));
+    backward_poly_points.push(P::new(p0.x() + offsets.hi, p0.y() + offsets.hi));
 }
 
 pub fn shift_left_up<P: PointLike>(
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
     p0: P,
-    half_width: i32,
+    offsets: SideOffsets,
 ) {
     // println!("shift left up (calling shift right down)");
-    shift_right_down(backward_poly_points, forward_poly_points, p0, half_width);
+    shift_right_down(backward_poly_points, forward_poly_points, p0, offsets);
 }
 
 /// A 2-point path that moves horizontally
-pub fn simple_horizontal_path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
-    let half_width = (width / 2) as i32;
-
+pub fn simple_horizontal_path_to_poly<P: PointLike>(
+    points: &[P],
+    offsets: SideOffsets,
+    layer: u8,
+) -> Poly {
     let points = vec![
-        Point::new(points[0].x(), points[0].y() - half_width),
-        Point::new(points[1].x(), points[1].y() - half_width),
-        Point::new(points[1].x(), points[1].y() + half_width),
-        Point::new(points[0].x(), points[0].y() + half_width),
+        Point::new(points[0].x(), points[0].y() - offsets.lo),
+        Point::new(points[1].x(), points[1].y() - offsets.lo),
+        Point::new(points[1].x(), points[1].y() + offsets.hi),
+        Point::new(points[0].x(), points[0].y() + offsets.hi),
     ];
 
     Poly { points, layer }
 }
 
 /// A 2-point path that moves vertically
-pub fn simple_vertical_path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
-    let half_width = (width / 2) as i32;
-
+pub fn simple_vertical_path_to_poly<P: PointLike>(
+    points: &[P],
+    offsets: SideOffsets,
+    layer: u8,
+) -> Poly {
     let points = vec![
-        Point::new(points[0].x() + half_width, points[0].y()),
-        Point::new(points[1].x() + half_width, points[1].y()),
-        Point::new(points[1].x() - half_width, points[1].y()),
-        Point::new(points[0].x() - half_width, points[0].y()),
+        Point::new(points[0].x() + offsets.hi, points[0].y()),
+        Point::new(points[1].x() + offsets.hi, points[1].y()),
+        Point::new(points[1].x() - offsets.lo, points[1].y()),
+        Point::new(points[0].x() - offsets.lo, points[0].y()),
     ];
 
     Poly { points, layer }
@@ -132,7 +567,7 @@ pub fn simple_vertical_path_to_poly<P: PointLike>(points: &[P], width: u32, laye
 pub fn start_or_end_path_to_poly<P: PointLike>(
     start_or_end_point: P,
     start_or_end_direction: RectDirection,
-    half_width: i32,
+    offsets: SideOffsets,
     forward_poly_points: &mut Vec<P>,
     backward_poly_points: &mut Vec<P>,
 ) {
@@ -141,64 +576,111 @@ pub fn start_or_end_path_to_poly<P: PointLike>(
             forward_poly_points,
             backward_poly_points,
             start_or_end_point,
-            half_width,
+            offsets,
         ),
         RectDirection::Left => shift_pure_left(
             forward_poly_points,
             backward_poly_points,
             start_or_end_point,
-            half_width,
+            offsets,
         ),
         RectDirection::Up => shift_pure_up(
             forward_poly_points,
             backward_poly_points,
             start_or_end_point,
-            half_width,
+            offsets,
         ),
         RectDirection::Down => shift_pure_down(
             forward_poly_points,
             backward_poly_points,
             start_or_end_point,
-            half_width,
+            offsets,
         ),
     }
 }
 
-pub fn path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
+/// Convert a path into a polygon, after normalizing it with
+/// [`normalize_path_points`]. Paths with diagonal segments get mitered corners.
+///
+/// The polygon is always simple (see [`is_simple_outline`]). Where a
+/// rectilinear path crosses or runs alongside itself, its outline is traced
+/// from the union of its segments, and if that encloses a hole the path is
+/// rejected with [`PathErr::EnclosesHole`].
+pub fn path_to_poly<P: PointLike>(
+    points: &[P],
+    width: u32,
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Result<Poly, PathErr> {
+    let points = &normalize_path_points(points)?[..];
+
+    let is_octilinear = points.windows(2).any(|w| {
+        w[0].oct_direction_to(&w[1])
+            .is_some_and(|d| d.is_diagonal())
+    });
+    if is_octilinear {
+        return octilinear_path_to_poly(points, width, end_cap, layer);
+    }
+
+    // Segments shorter than the width of the path make its outline fold back
+    // over itself, as do paths which cross or run alongside themselves. Such
+    // paths are traced from the union of their segments instead.
+    let has_short_segment = points.windows(2).any(|w| {
+        (w[1].x() as i64 - w[0].x() as i64).abs() + (w[1].y() as i64 - w[0].y() as i64).abs()
+            < width as i64
+    });
+    if !has_short_segment {
+        let poly = offset_path_to_poly(points, width, end_cap, layer);
+        if is_simple_outline(&poly.points) {
+            return Ok(poly);
+        }
+    }
+
+    let rects = path_segment_rects(points, width, end_cap, layer)?;
+    let mut polys = Region::from_rects(rects.iter()).polys(layer);
+    match (polys.pop(), polys.is_empty()) {
+        (Some(poly), true) if poly.holes.is_empty() => Ok(poly.outline),
+        _ => Err(PathErr::EnclosesHole),
+    }
+}
+
+/// Convert a normalized rectilinear path into a polygon by offsetting its
+/// sides. The polygon overlaps itself if the path does, or if a segment is
+/// shorter than the width of the path.
+fn offset_path_to_poly<P: PointLike>(
+    points: &[P],
+    width: u32,
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Poly {
     let num_points = points.len();
+    let offsets = SideOffsets::new(width);
 
-    // TODO: remove this assert because all shape verification should happen prior to archiving
-    assert_eq!(
-        width % 2,
-        0,
-        "width must be even for our code's assumptions to hold!"
-    );
+    let start_direction = points[0].simple_directions_to(&points[1]);
+    let end_direction = points[num_points - 2].simple_directions_to(&points[num_points - 1]);
+
+    // The end caps are handled by moving the end points, after which the path
+    // is always flush with them.
+    let (begin_extension, end_extension) =
+        end_cap.extensions(offsets, start_direction, end_direction);
+    let first = extend_point(points[0], start_direction, -begin_extension);
+    let last = extend_point(points[num_points - 1], end_direction, end_extension);
 
     if num_points == 2 {
-        return if points[0].x() == points[1].x() {
-            simple_horizontal_path_to_poly(points, width, layer)
+        return if points[0].y() == points[1].y() {
+            simple_horizontal_path_to_poly(&[first, last], offsets, layer)
         } else {
-            simple_vertical_path_to_poly(points, width, layer)
+            simple_vertical_path_to_poly(&[first, last], offsets, layer)
         };
     }
 
-    let half_width = (width / 2) as i32; // assuming that widths are even!
-
     let mut forward_poly_points = Vec::with_capacity(num_points);
     let mut backward_poly_points = Vec::with_capacity(num_points);
 
-    // TODO: remove this assert because all shape verification should happen prior to archiving
-    assert!(
-        num_points > 1,
-        "Expected number of points in path to be > 1"
-    );
-
-    let start_direction = points[0].simple_directions_to(&points[1]);
-
     start_or_end_path_to_poly(
-        points[0],
+        first,
         start_direction,
-        half_width,
+        offsets,
         &mut forward_poly_points,
         &mut backward_poly_points,
     );
@@ -214,46 +696,46 @@ pub fn path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Left, RectDirection::Left) => shift_pure_left(
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Up, RectDirection::Up) => shift_pure_up(
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Down, RectDirection::Down) => shift_pure_down(
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Right, RectDirection::Down)
             | (RectDirection::Down, RectDirection::Right) => shift_right_down(
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Right, RectDirection::Up)
             | (RectDirection::Up, RectDirection::Right) => shift_right_up(
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
+                offsets,
             ),
             (RectDirection::Left, RectDirection::Up) | (RectDirection::Up, RectDirection::Left) => {
                 shift_left_up(
                     &mut forward_poly_points,
                     &mut backward_poly_points,
                     p0,
-                    half_width,
+                    offsets,
                 )
             }
             (RectDirection::Left, RectDirection::Down)
@@ -261,21 +743,17 @@ pub fn path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
                 &mut forward_poly_points,
                 &mut backward_poly_points,
                 p0,
-                half_width,
-            ),
-            (_, _) => panic!(
-                "Received opposing last/next moves!" // "last: {last_move:?}, next: {next_move:?}"
+                offsets,
             ),
+            (_, _) => unreachable!("Normalized paths do not double back on themselves"),
         }
         last_direction = next_move;
     }
 
-    let end_direction = points[num_points - 2].simple_directions_to(&points[num_points - 1]);
-
     start_or_end_path_to_poly(
-        points[num_points - 1],
+        last,
         end_direction,
-        half_width,
+        offsets,
         &mut forward_poly_points,
         &mut backward_poly_points,
     );
@@ -289,14 +767,79 @@ pub fn path_to_poly<P: PointLike>(points: &[P], width: u32, layer: u8) -> Poly {
     Poly { points, layer }
 }
 
-impl Path {
-    pub fn as_poly(&self) -> Poly {
-        path_to_poly(&self.points, self.width, self.layer)
+/// A path, whether owned or archived.
+pub trait PathLike {
+    type Point: PointLike;
+
+    fn points(&self) -> &[Self::Point];
+    fn width(&self) -> u32;
+    fn end_cap(&self) -> PathEndCap;
+    fn layer(&self) -> u8;
+
+    fn as_poly(&self) -> Result<Poly, PathErr> {
+        path_to_poly(self.points(), self.width(), self.end_cap(), self.layer())
+    }
+
+    fn segment_rects(&self) -> Result<Vec<Rect>, PathErr> {
+        path_segment_rects(self.points(), self.width(), self.end_cap(), self.layer())
+    }
+
+    fn extent_points(&self) -> impl Iterator<Item = Point> + '_ {
+        path_extent_points(self.points(), self.width(), self.end_cap())
+    }
+
+    fn outline(&self) -> Option<PathOutline<'_, Self::Point>> {
+        PathOutline::new(self.points(), self.width(), self.end_cap())
     }
 }
 
-impl ArchivedPath {
-    pub fn as_poly(&self) -> Poly {
-        path_to_poly(&self.points, self.width, self.layer)
+impl PathLike for Path {
+    type Point = Point;
+
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn end_cap(&self) -> PathEndCap {
+        self.end_cap
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl PathLike for ArchivedPath {
+    type Point = ArchivedPoint;
+
+    fn points(&self) -> &[ArchivedPoint] {
+        &self.points
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn end_cap(&self) -> PathEndCap {
+        (&self.end_cap).into()
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl Path {
+    /// A copy of this path with its points normalized by
+    /// [`normalize_path_points`].
+    pub fn normalize(&self) -> Result<Path, PathErr> {
+        Ok(Path {
+            points: normalize_path_points(&self.points)?,
+            ..self.clone()
+        })
     }
 }
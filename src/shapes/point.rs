@@ -1,7 +1,8 @@
 use std::cmp::Ordering;
+use std::ops::{Add, Sub};
+use std::{error::Error, fmt::Display};
 
-use crate::shapes::RectDirection;
-use derive_more::{Add, Sub};
+use crate::shapes::{OctDirection, RectDirection};
 use rkyv::{Archive, Deserialize, Serialize};
 
 #[derive(
@@ -17,21 +18,124 @@ use rkyv::{Archive, Deserialize, Serialize};
     Copy,
     serde::Serialize,
     serde::Deserialize,
-    Add,
-    Sub,
 )]
 #[archive(compare(PartialEq))]
-#[archive_attr(derive(Debug, Copy, Clone, Add, Sub))]
+#[archive_attr(derive(Debug, Copy, Clone))]
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
+/// A coordinate which does not fit in an `i32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordinateErr {
+    Overflow,
+}
+
+impl Display for CoordinateErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for CoordinateErr {}
+
 impl Point {
+    /// Shift by `other`. Like `+` and `-` on points, this panics if either
+    /// coordinate overflows, in release builds as well as debug ones; see
+    /// [`Point::try_shift`] and [`Point::saturating_shift`] for coordinates
+    /// which may be near the edge of the `i32` range.
     pub fn shift(&self, other: Self) -> Self {
+        self.checked_shift(other)
+            .expect("shifted point overflows the i32 range")
+    }
+
+    /// Shift by `other`, or `None` if either coordinate would overflow.
+    pub fn checked_shift(&self, other: Self) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_add(other.x)?,
+            y: self.y.checked_add(other.y)?,
+        })
+    }
+
+    /// Shift by `other`, reporting overflow as an error.
+    pub fn try_shift(&self, other: Self) -> Result<Self, CoordinateErr> {
+        self.checked_shift(other).ok_or(CoordinateErr::Overflow)
+    }
+
+    /// Shift by `other`, clamping each coordinate to the `i32` range.
+    pub fn saturating_shift(&self, other: Self) -> Self {
+        Self {
+            x: self.x.saturating_add(other.x),
+            y: self.y.saturating_add(other.y),
+        }
+    }
+
+    /// The difference `self - other`, or `None` if either coordinate would
+    /// overflow.
+    pub fn checked_sub(&self, other: Self) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_sub(other.x)?,
+            y: self.y.checked_sub(other.y)?,
+        })
+    }
+
+    /// The difference `self - other`, clamping each coordinate to the `i32`
+    /// range.
+    pub fn saturating_sub(&self, other: Self) -> Self {
         Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
+        }
+    }
+
+    /// The point at wide coordinates, if they fit in an `i32`.
+    pub fn try_from_wide(x: i64, y: i64) -> Result<Self, CoordinateErr> {
+        match (i32::try_from(x), i32::try_from(y)) {
+            (Ok(x), Ok(y)) => Ok(Self { x, y }),
+            _ => Err(CoordinateErr::Overflow),
+        }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    /// Panics if either coordinate overflows; see [`Point::checked_shift`].
+    fn add(self, other: Point) -> Point {
+        self.shift(other)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    /// Panics if either coordinate overflows; see [`Point::checked_sub`].
+    fn sub(self, other: Point) -> Point {
+        self.checked_sub(other)
+            .expect("point difference overflows the i32 range")
+    }
+}
+
+impl Add for ArchivedPoint {
+    type Output = ArchivedPoint;
+
+    /// Panics if either coordinate overflows, like `+` on [`Point`].
+    fn add(self, other: ArchivedPoint) -> ArchivedPoint {
+        let sum = Point::from(self) + Point::from(other);
+        ArchivedPoint { x: sum.x, y: sum.y }
+    }
+}
+
+impl Sub for ArchivedPoint {
+    type Output = ArchivedPoint;
+
+    /// Panics if either coordinate overflows, like `-` on [`Point`].
+    fn sub(self, other: ArchivedPoint) -> ArchivedPoint {
+        let difference = Point::from(self) - Point::from(other);
+        ArchivedPoint {
+            x: difference.x,
+            y: difference.y,
         }
     }
 }
@@ -94,6 +198,15 @@ pub trait PointLike: Into<Point> + Copy + Clone {
         result
     }
 
+    /// The direction of the step to `other`, if it is horizontal, vertical
+    /// or at 45 degrees.
+    fn oct_direction_to<Q: PointLike>(&self, other: &Q) -> Option<OctDirection> {
+        OctDirection::from_step(
+            other.x() as i64 - self.x() as i64,
+            other.y() as i64 - self.y() as i64,
+        )
+    }
+
     fn simple_directions_to<Q: PointLike>(&self, other: &Q) -> RectDirection {
         // TODO: remove this assert because all shape verification should happen prior to archiving?
         let moves_to = self.directions_to(other);
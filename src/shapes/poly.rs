@@ -1,4 +1,4 @@
-use crate::shapes::Point;
+use crate::shapes::{ArchivedPoint, Point, PointLike};
 use rkyv::{Archive, Deserialize, Serialize};
 
 #[derive(
@@ -6,6 +6,7 @@ use rkyv::{Archive, Deserialize, Serialize};
     Eq,
     PartialEq,
     Hash,
+    Clone,
     Archive,
     Deserialize,
     Serialize,
@@ -18,3 +19,119 @@ pub struct Poly {
     pub points: Vec<Point>,
     pub layer: u8,
 }
+
+/// A polygon, whether owned or archived.
+pub trait PolyLike {
+    type Point: PointLike;
+
+    fn points(&self) -> &[Self::Point];
+    fn layer(&self) -> u8;
+
+    /// Twice the area enclosed by the outline, which is always a whole number
+    /// even when the area is not. Self-intersecting outlines count the parts
+    /// they wind around in opposite directions against each other.
+    fn area_doubled(&self) -> i128 {
+        crate::boolean::signed_area_doubled(self.points()).abs()
+    }
+
+    /// Whether the outline is simple, as described for
+    /// [`is_simple_outline`].
+    fn is_simple(&self) -> bool {
+        is_simple_outline(self.points())
+    }
+}
+
+/// Whether a closed outline is simple: it has at least three points, no edge
+/// has zero length, and edges only meet where one ends and the next begins.
+/// Outlines which cross, touch or run back along themselves are not simple.
+///
+/// Every pair of edges is compared, which is fine for the outlines of paths
+/// and other hand drawn shapes, but slow for very large polygons.
+pub fn is_simple_outline<P: PointLike>(points: &[P]) -> bool {
+    let n = points.len();
+    if n < 3 {
+        return false;
+    }
+    let edge = |ix: usize| (points[ix], points[(ix + 1) % n]);
+    for ix in 0..n {
+        let (a0, a1) = edge(ix);
+        if a0.x() == a1.x() && a0.y() == a1.y() {
+            return false;
+        }
+        // The next edge may not double back along this one.
+        let (_, b1) = edge((ix + 1) % n);
+        if cross(a0, a1, b1) == 0 && dot(a0, a1, b1) < 0 {
+            return false;
+        }
+        // Edges which are not neighbours may not meet at all. (The first and
+        // last edges are neighbours too.)
+        for jx in ix + 2..n {
+            if ix == 0 && jx == n - 1 {
+                continue;
+            }
+            let (b0, b1) = edge(jx);
+            if segments_meet(a0, a1, b0, b1) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// The cross product of `a - o` and `b - o`, which is positive if `b` is to
+/// the left of the line from `o` to `a`.
+fn cross<P: PointLike>(o: P, a: P, b: P) -> i128 {
+    (a.x() as i128 - o.x() as i128) * (b.y() as i128 - o.y() as i128)
+        - (a.y() as i128 - o.y() as i128) * (b.x() as i128 - o.x() as i128)
+}
+
+/// The dot product of `a - o` and `b - a`, which is negative if the step from
+/// `a` to `b` turns back towards `o`.
+fn dot<P: PointLike>(o: P, a: P, b: P) -> i128 {
+    (a.x() as i128 - o.x() as i128) * (b.x() as i128 - a.x() as i128)
+        + (a.y() as i128 - o.y() as i128) * (b.y() as i128 - a.y() as i128)
+}
+
+/// Whether `p` lies within the bounding box of the segment from `a` to `b`,
+/// which for a point on the line through them means it lies on the segment.
+fn within<P: PointLike>(a: P, b: P, p: P) -> bool {
+    (a.x().min(b.x())..=a.x().max(b.x())).contains(&p.x())
+        && (a.y().min(b.y())..=a.y().max(b.y())).contains(&p.y())
+}
+
+/// Whether the closed segments `a0 a1` and `b0 b1` have any point in common.
+fn segments_meet<P: PointLike>(a0: P, a1: P, b0: P, b1: P) -> bool {
+    let (d0, d1) = (cross(a0, a1, b0), cross(a0, a1, b1));
+    let (d2, d3) = (cross(b0, b1, a0), cross(b0, b1, a1));
+    if d0.signum() * d1.signum() < 0 && d2.signum() * d3.signum() < 0 {
+        return true;
+    }
+    (d0 == 0 && within(a0, a1, b0))
+        || (d1 == 0 && within(a0, a1, b1))
+        || (d2 == 0 && within(b0, b1, a0))
+        || (d3 == 0 && within(b0, b1, a1))
+}
+
+impl PolyLike for Poly {
+    type Point = Point;
+
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl PolyLike for ArchivedPoly {
+    type Point = ArchivedPoint;
+
+    fn points(&self) -> &[ArchivedPoint] {
+        &self.points
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
@@ -1,4 +1,4 @@
-use crate::shapes::Point;
+use crate::shapes::{ArchivedPoint, Point, PointLike};
 use rkyv::{Archive, Deserialize, Serialize};
 
 #[derive(
@@ -22,6 +22,66 @@ pub struct Rect {
     pub layer: u8,
 }
 
+/// A rect, whether owned or archived.
+pub trait RectLike {
+    type Point: PointLike;
+
+    fn p0(&self) -> Self::Point;
+    fn p1(&self) -> Self::Point;
+    fn layer(&self) -> u8;
+
+    /// The width, which may not fit in an `i32`.
+    fn width(&self) -> i64 {
+        (self.p1().x() as i64 - self.p0().x() as i64).abs()
+    }
+
+    /// The height, which may not fit in an `i32`.
+    fn height(&self) -> i64 {
+        (self.p1().y() as i64 - self.p0().y() as i64).abs()
+    }
+
+    /// The area, which may not fit in an `i64`.
+    fn area(&self) -> i128 {
+        self.width() as i128 * self.height() as i128
+    }
+
+    fn perimeter(&self) -> i64 {
+        2 * (self.width() + self.height())
+    }
+}
+
+impl RectLike for Rect {
+    type Point = Point;
+
+    fn p0(&self) -> Point {
+        self.p0
+    }
+
+    fn p1(&self) -> Point {
+        self.p1
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl RectLike for ArchivedRect {
+    type Point = ArchivedPoint;
+
+    fn p0(&self) -> ArchivedPoint {
+        self.p0
+    }
+
+    fn p1(&self) -> ArchivedPoint {
+        self.p1
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum RectDirection {
     Left,
@@ -29,3 +89,14 @@ pub enum RectDirection {
     Up,
     Down,
 }
+
+impl RectDirection {
+    pub fn opposite(&self) -> RectDirection {
+        match self {
+            RectDirection::Left => RectDirection::Right,
+            RectDirection::Right => RectDirection::Left,
+            RectDirection::Up => RectDirection::Down,
+            RectDirection::Down => RectDirection::Up,
+        }
+    }
+}
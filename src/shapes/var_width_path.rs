@@ -0,0 +1,280 @@
+use crate::bbox::{BoundingBox, CalculateBoundingBox, UnvalidatedBoundingBox};
+use crate::boolean::Region;
+use crate::shapes::{
+    cuts_back, extend_point, ArchivedPoint, PathEndCap, PathErr, Point, PointLike, Poly, Rect,
+    RectDirection, SideOffsets,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// A rectilinear path with a width for each of its segments, such as a tapered
+/// route or a power strap.
+///
+/// `widths[i]` is the width of the segment from `points[i]` to `points[i + 1]`.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct VarWidthPath {
+    pub points: Vec<Point>,
+    pub widths: Vec<u32>,
+    pub layer: u8,
+    #[serde(default)]
+    pub end_cap: PathEndCap,
+}
+
+impl VarWidthPath {
+    /// A variable width path, checked to have one width per segment, at least
+    /// one segment of non-zero length, and no diagonal steps.
+    pub fn new(
+        points: Vec<Point>,
+        widths: Vec<u32>,
+        layer: u8,
+        end_cap: PathEndCap,
+    ) -> Result<Self, PathErr> {
+        var_width_path_segment_rects(&points, &widths, end_cap, layer)?;
+        Ok(VarWidthPath {
+            points,
+            widths,
+            layer,
+            end_cap,
+        })
+    }
+}
+
+/// The rectangles swept out by each segment of a variable width path.
+///
+/// Where the path turns, each segment extends past the joint by the side
+/// offset of the segment it turns into, so that the corner is filled in. Where
+/// it carries straight on, the segments meet flush, and a change of width
+/// leaves a step in the outline.
+pub fn var_width_path_segment_rects<P: PointLike>(
+    points: &[P],
+    widths: &[u32],
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Result<Vec<Rect>, PathErr> {
+    if widths.len() + 1 != points.len() {
+        return Err(PathErr::WrongNumberOfWidths);
+    }
+
+    // Repeated points make empty segments, which are dropped along with their
+    // widths.
+    let mut segments: Vec<(P, P, RectDirection, SideOffsets)> = Vec::with_capacity(widths.len());
+    for (w, &width) in points.windows(2).zip(widths) {
+        if w[0].x() == w[1].x() && w[0].y() == w[1].y() {
+            continue;
+        }
+        let direction = match w[0].oct_direction_to(&w[1]) {
+            Some(direction) => direction
+                .as_rect_direction()
+                .ok_or(PathErr::NotRectilinear)?,
+            None => return Err(PathErr::NotRectilinear),
+        };
+        segments.push((w[0], w[1], direction, SideOffsets::new(width)));
+    }
+    if segments.is_empty() {
+        return Err(PathErr::NotEnoughPoints);
+    }
+
+    let last = segments.len() - 1;
+    let (first_direction, first_offsets) = (segments[0].2, segments[0].3);
+    let (last_direction, last_offsets) = (segments[last].2, segments[last].3);
+    let begin_extension = end_cap
+        .extensions(first_offsets, first_direction, first_direction)
+        .0;
+    let end_extension = end_cap
+        .extensions(last_offsets, last_direction, last_direction)
+        .1;
+    let folds_over = if last == 0 {
+        cuts_back(segments[0].0, segments[0].1, begin_extension, end_extension)
+    } else {
+        cuts_back(segments[0].0, segments[0].1, begin_extension, 0)
+            || cuts_back(segments[last].0, segments[last].1, 0, end_extension)
+    };
+    if folds_over {
+        return Err(PathErr::FoldsOver);
+    }
+
+    let rects = segments
+        .iter()
+        .enumerate()
+        .map(|(ix, &(p0, p1, direction, offsets))| {
+            let begin = match ix.checked_sub(1).map(|ix| segments[ix]) {
+                None => begin_extension,
+                Some((_, _, previous, _)) if previous == direction => 0,
+                Some((_, _, _, previous_offsets)) => {
+                    PathEndCap::HalfWidth
+                        .extensions(previous_offsets, direction, direction)
+                        .0
+                }
+            };
+            let end = match segments.get(ix + 1) {
+                None => end_extension,
+                Some(&(_, _, next, _)) if next == direction => 0,
+                Some(&(_, _, _, next_offsets)) => {
+                    PathEndCap::HalfWidth
+                        .extensions(next_offsets, direction, direction)
+                        .1
+                }
+            };
+            let (p0, p1) = (
+                extend_point(p0, direction, -begin),
+                extend_point(p1, direction, end),
+            );
+
+            let (x0, x1) = (p0.x().min(p1.x()), p0.x().max(p1.x()));
+            let (y0, y1) = (p0.y().min(p1.y()), p0.y().max(p1.y()));
+            let (x0, x1, y0, y1) = match direction {
+                RectDirection::Left | RectDirection::Right => {
+                    (x0, x1, y0 - offsets.lo, y1 + offsets.hi)
+                }
+                RectDirection::Up | RectDirection::Down => {
+                    (x0 - offsets.lo, x1 + offsets.hi, y0, y1)
+                }
+            };
+            Rect {
+                p0: Point::new(x0, y0),
+                p1: Point::new(x1, y1),
+                layer,
+            }
+        })
+        .collect();
+    Ok(rects)
+}
+
+/// Convert a variable width path into a polygon, by tracing the outline of the
+/// union of its segment rects.
+pub fn var_width_path_to_poly<P: PointLike>(
+    points: &[P],
+    widths: &[u32],
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Result<Poly, PathErr> {
+    let rects = var_width_path_segment_rects(points, widths, end_cap, layer)?;
+    let mut polys = Region::from_rects(rects.iter()).polys(layer);
+    match (polys.pop(), polys.is_empty()) {
+        (Some(poly), true) if poly.holes.is_empty() => Ok(poly.outline),
+        _ => Err(PathErr::EnclosesHole),
+    }
+}
+
+/// Points whose bounding box is that of the area covered by a variable width
+/// path. Malformed paths are approximated by squares around their points, as
+/// wide as their widest segment.
+pub fn var_width_path_extent_points<P: PointLike>(
+    points: &[P],
+    widths: &[u32],
+    end_cap: PathEndCap,
+    layer: u8,
+) -> Vec<Point> {
+    if let Ok(rects) = var_width_path_segment_rects(points, widths, end_cap, layer) {
+        return rects.into_iter().flat_map(|r| [r.p0, r.p1]).collect();
+    }
+
+    let margin = widths.iter().max().copied().unwrap_or(0).div_ceil(2) as i32
+        + match end_cap {
+            PathEndCap::Custom { begin, end } => begin.max(end).max(0),
+            _ => 0,
+        };
+    points
+        .iter()
+        .flat_map(|p| {
+            [
+                Point::new(p.x() - margin, p.y() - margin),
+                Point::new(p.x() + margin, p.y() + margin),
+            ]
+        })
+        .collect()
+}
+
+/// A variable width path, whether owned or archived.
+pub trait VarWidthPathLike {
+    type Point: PointLike;
+
+    fn points(&self) -> &[Self::Point];
+    fn widths(&self) -> &[u32];
+    fn end_cap(&self) -> PathEndCap;
+    fn layer(&self) -> u8;
+
+    fn as_poly(&self) -> Result<Poly, PathErr> {
+        var_width_path_to_poly(self.points(), self.widths(), self.end_cap(), self.layer())
+    }
+
+    fn segment_rects(&self) -> Result<Vec<Rect>, PathErr> {
+        var_width_path_segment_rects(self.points(), self.widths(), self.end_cap(), self.layer())
+    }
+
+    fn extent_points(&self) -> Vec<Point> {
+        var_width_path_extent_points(self.points(), self.widths(), self.end_cap(), self.layer())
+    }
+}
+
+impl VarWidthPathLike for VarWidthPath {
+    type Point = Point;
+
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    fn widths(&self) -> &[u32] {
+        &self.widths
+    }
+
+    fn end_cap(&self) -> PathEndCap {
+        self.end_cap
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl VarWidthPathLike for ArchivedVarWidthPath {
+    type Point = ArchivedPoint;
+
+    fn points(&self) -> &[ArchivedPoint] {
+        &self.points
+    }
+
+    fn widths(&self) -> &[u32] {
+        &self.widths
+    }
+
+    fn end_cap(&self) -> PathEndCap {
+        (&self.end_cap).into()
+    }
+
+    fn layer(&self) -> u8 {
+        self.layer
+    }
+}
+
+impl CalculateBoundingBox for VarWidthPath {
+    fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        for pt in self.extent_points() {
+            bbox.update_point(pt);
+        }
+        bbox.validate()
+    }
+}
+
+impl CalculateBoundingBox for ArchivedVarWidthPath {
+    fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        for pt in self.extent_points() {
+            bbox.update_point(pt);
+        }
+        bbox.validate()
+    }
+}
@@ -0,0 +1,127 @@
+use crate::boolean::{PolyWithHoles, Region, RegionErr};
+use crate::shapes::{Point, PointLike, Poly, Rect, Shape};
+
+/// `value + delta`, stopping at the ends of the `i32` range.
+fn clamped(value: i32, delta: i64) -> i32 {
+    (value as i64 + delta).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+impl Rect {
+    /// Grow (positive) or shrink (negative) the rect by `dx` on its left and
+    /// right sides, and by `dy` on its top and bottom. Returns `None` if the
+    /// rect is shrunk away entirely.
+    ///
+    /// Rects grown past the ends of the `i32` range stop there.
+    pub fn size(&self, dx: i32, dy: i32) -> Option<Rect> {
+        let (dx, dy) = (dx as i64, dy as i64);
+        let (x0, x1) = (
+            clamped(self.p0.x.min(self.p1.x), -dx),
+            clamped(self.p0.x.max(self.p1.x), dx),
+        );
+        let (y0, y1) = (
+            clamped(self.p0.y.min(self.p1.y), -dy),
+            clamped(self.p0.y.max(self.p1.y), dy),
+        );
+        (x0 < x1 && y0 < y1).then(|| Rect {
+            p0: Point::new(x0, y0),
+            p1: Point::new(x1, y1),
+            layer: self.layer,
+        })
+    }
+}
+
+impl Poly {
+    /// Size a rectilinear polygon, as with [`Region::size`].
+    ///
+    /// Shrinking may split the polygon into several, and growing it may close
+    /// off holes, so the result is a set of polygons.
+    pub fn size(&self, dx: i32, dy: i32) -> Result<Vec<PolyWithHoles>, RegionErr> {
+        Ok(Region::from_poly(self)?.size(dx, dy).polys(self.layer))
+    }
+}
+
+impl PolyWithHoles {
+    /// Size the polygon along with its holes, as with [`Region::size`].
+    pub fn size(&self, dx: i32, dy: i32) -> Result<Vec<PolyWithHoles>, RegionErr> {
+        Ok(self.region()?.size(dx, dy).polys(self.outline.layer))
+    }
+}
+
+/// Size all the shapes on `layer` together, so that shapes which overlap once
+/// they have been grown are merged into one polygon.
+pub fn size_layer(
+    shapes: &[Shape],
+    layer: u8,
+    dx: i32,
+    dy: i32,
+) -> Result<Vec<PolyWithHoles>, RegionErr> {
+    Ok(Region::from_layer(shapes, layer)?.size(dx, dy).polys(layer))
+}
+
+impl Region {
+    /// Grow (positive) or shrink (negative) the region by `dx` on its left and
+    /// right sides, and by `dy` on its top and bottom.
+    ///
+    /// Parts of the region that are narrower than twice the amount it is shrunk
+    /// by disappear. If `dx` and `dy` have different signs, the region is sized
+    /// along x first, and then along y. Regions grown past the ends of the
+    /// `i32` range stop there.
+    pub fn size(&self, dx: i32, dy: i32) -> Region {
+        if (dx >= 0) == (dy >= 0) {
+            self.size_uniform(dx, dy)
+        } else {
+            self.size_uniform(dx, 0).size_uniform(0, dy)
+        }
+    }
+
+    /// Size the region when `dx` and `dy` have the same sign.
+    fn size_uniform(&self, dx: i32, dy: i32) -> Region {
+        if dx >= 0 && dy >= 0 {
+            return self.grow(dx as i64, dy as i64);
+        }
+        // `-(i32::MIN)` does not fit in an `i32`.
+        let (dx, dy) = (-(dx as i64), -(dy as i64));
+
+        let Some(bbox) = self.bbox() else {
+            return Region::new();
+        };
+        let (min, max) = (bbox.min(), bbox.max());
+        // Shrinking a region is the same as growing the space around it, and
+        // then taking that away from the region. Within the bounding box that
+        // space is the rest of the box, and outside it is everything, which
+        // eats a strip from each side of the box. Working inside the box means
+        // nothing has to reach past the ends of the `i32` range.
+        let rest = Region::from_rect(&Rect {
+            p0: min,
+            p1: max,
+            layer: 0,
+        })
+        .difference(self);
+        let strips = [
+            (min.x, min.y, clamped(min.x, dx), max.y),
+            (clamped(max.x, -dx), min.y, max.x, max.y),
+            (min.x, min.y, max.x, clamped(min.y, dy)),
+            (min.x, clamped(max.y, -dy), max.x, max.y),
+        ]
+        .map(|(x0, y0, x1, y1)| Rect {
+            p0: Point::new(x0, y0),
+            p1: Point::new(x1, y1),
+            layer: 0,
+        });
+        self.difference(&rest.grow(dx, dy).union(&Region::from_rects(strips.iter())))
+    }
+
+    /// Grow the region by non-negative `dx` and `dy`.
+    fn grow(&self, dx: i64, dy: i64) -> Region {
+        let rects: Vec<Rect> = self
+            .rects(0)
+            .into_iter()
+            .map(|r| Rect {
+                p0: Point::new(clamped(r.p0.x, -dx), clamped(r.p0.y, -dy)),
+                p1: Point::new(clamped(r.p1.x, dx), clamped(r.p1.y, dy)),
+                layer: r.layer,
+            })
+            .collect();
+        Region::from_rects(rects.iter())
+    }
+}
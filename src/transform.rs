@@ -0,0 +1,653 @@
+use crate::bbox::{BoundingBox, UnvalidatedBoundingBox};
+use crate::shapes::{
+    ArchivedPoint, ArchivedShape, CoordinateErr, OctDirection, Path, PathEndCap, PathLike, Point,
+    PointLike, Poly, Rect, Shape, ShapeLike, ShapeRef, Shapes, VarWidthPath, VarWidthPathLike,
+};
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
+
+/// A counterclockwise rotation by a multiple of 90 degrees.
+#[derive(
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Copy,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+impl Rotation {
+    /// The rotation by `degrees`, if it is a multiple of 90.
+    pub fn from_degrees(degrees: i32) -> Option<Rotation> {
+        if degrees % 90 != 0 {
+            return None;
+        }
+        Some(Rotation::from_quarter_turns(degrees / 90))
+    }
+
+    fn from_quarter_turns(turns: i32) -> Rotation {
+        match turns.rem_euclid(4) {
+            0 => Rotation::R0,
+            1 => Rotation::R90,
+            2 => Rotation::R180,
+            _ => Rotation::R270,
+        }
+    }
+
+    fn quarter_turns(&self) -> i32 {
+        match self {
+            Rotation::R0 => 0,
+            Rotation::R90 => 1,
+            Rotation::R180 => 2,
+            Rotation::R270 => 3,
+        }
+    }
+}
+
+/// A transform with the semantics of a GDS structure reference: points are
+/// mirrored about the x axis (if `mirror_x` is set), then magnified, then
+/// rotated counterclockwise about the origin, and then moved by `offset`.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Copy,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Transform {
+    pub offset: Point,
+    pub rotation: Rotation,
+    pub mirror_x: bool,
+    pub magnification: u32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            offset: Point::default(),
+            rotation: Rotation::R0,
+            mirror_x: false,
+            magnification: 1,
+        }
+    }
+
+    pub fn translate(offset: Point) -> Self {
+        Transform {
+            offset,
+            ..Transform::identity()
+        }
+    }
+
+    pub fn rotate(rotation: Rotation) -> Self {
+        Transform {
+            rotation,
+            ..Transform::identity()
+        }
+    }
+
+    /// Mirror about the x axis, i.e. flip y.
+    pub fn mirror_x() -> Self {
+        Transform {
+            mirror_x: true,
+            ..Transform::identity()
+        }
+    }
+
+    /// Mirror about the y axis, i.e. flip x. This is a mirror about the x axis
+    /// followed by a half turn.
+    pub fn mirror_y() -> Self {
+        Transform {
+            rotation: Rotation::R180,
+            mirror_x: true,
+            ..Transform::identity()
+        }
+    }
+
+    /// Magnify by a positive integer factor.
+    pub fn magnify(magnification: u32) -> Self {
+        assert!(magnification > 0, "Magnifications must be positive");
+        Transform {
+            magnification,
+            ..Transform::identity()
+        }
+    }
+
+    /// Apply only the mirroring and rotation of the transform.
+    fn orient(&self, x: i64, y: i64) -> (i64, i64) {
+        let y = if self.mirror_x { -y } else { y };
+        match self.rotation {
+            Rotation::R0 => (x, y),
+            Rotation::R90 => (-y, x),
+            Rotation::R180 => (-x, -y),
+            Rotation::R270 => (y, -x),
+        }
+    }
+
+    /// Apply the transform. Panics if the result does not fit in an `i32`;
+    /// see [`Transform::try_apply`].
+    pub fn apply<P: PointLike>(&self, p: P) -> P {
+        self.try_apply(p)
+            .expect("transformed point overflows the i32 range")
+    }
+
+    /// Apply the transform, reporting an error instead of overflowing if the
+    /// result does not fit in an `i32`.
+    pub fn try_apply<P: PointLike>(&self, p: P) -> Result<P, CoordinateErr> {
+        // Neither product can overflow, as the magnification is a `u32`.
+        let m = self.magnification as i64;
+        let (x, y) = self.orient(p.x() as i64 * m, p.y() as i64 * m);
+        let p = Point::try_from_wide(x + self.offset.x as i64, y + self.offset.y as i64)?;
+        Ok(P::new(p.x, p.y))
+    }
+
+    /// The transform which applies `self` and then `next`. Panics if the
+    /// combined offset or magnification overflows; see
+    /// [`Transform::try_then`].
+    pub fn then(&self, next: &Transform) -> Transform {
+        self.try_then(next)
+            .expect("combined transform overflows the i32 range")
+    }
+
+    /// The transform which applies `self` and then `next`, reporting an error
+    /// instead of overflowing.
+    pub fn try_then(&self, next: &Transform) -> Result<Transform, CoordinateErr> {
+        // Mirroring before a rotation is the same as rotating the other way
+        // before mirroring.
+        let turns = if next.mirror_x {
+            next.rotation.quarter_turns() - self.rotation.quarter_turns()
+        } else {
+            next.rotation.quarter_turns() + self.rotation.quarter_turns()
+        };
+        Ok(Transform {
+            offset: next.try_apply(self.offset)?,
+            rotation: Rotation::from_quarter_turns(turns),
+            mirror_x: self.mirror_x != next.mirror_x,
+            magnification: self
+                .magnification
+                .checked_mul(next.magnification)
+                .ok_or(CoordinateErr::Overflow)?,
+        })
+    }
+
+    /// The transform undoing this one. Magnified transforms cannot be undone
+    /// on an integer grid, so they have no inverse, and neither do those whose
+    /// inverse offset does not fit in an `i32`.
+    pub fn inverse(&self) -> Option<Transform> {
+        if self.magnification != 1 {
+            return None;
+        }
+        let turns = if self.mirror_x {
+            self.rotation.quarter_turns()
+        } else {
+            -self.rotation.quarter_turns()
+        };
+        let mut inverse = Transform {
+            offset: Point::default(),
+            rotation: Rotation::from_quarter_turns(turns),
+            mirror_x: self.mirror_x,
+            magnification: 1,
+        };
+        let (x, y) = inverse.orient(self.offset.x as i64, self.offset.y as i64);
+        inverse.offset = Point::try_from_wide(-x, -y).ok()?;
+        Some(inverse)
+    }
+
+    /// Magnify a width or length, panicking if it overflows.
+    fn magnify_length<T: TryFrom<i64> + Into<i64>>(&self, length: T) -> T {
+        match T::try_from(length.into() * self.magnification as i64) {
+            Ok(length) => length,
+            Err(_) => panic!("magnified length overflows"),
+        }
+    }
+
+    /// The shift which keeps the extra unit of an odd width path on the +x
+    /// and +y sides. The area of such a path is centered half a unit up and to
+    /// the right of its centerline, and the transform moves that center to
+    /// half of the magnification along each axis, in the direction the axis
+    /// ends up facing. The shifted path is centered on the same point.
+    fn odd_width_shift(&self) -> Point {
+        let m = self.magnification as i64;
+        let (x, y) = self.orient(m, m);
+        // Magnifications are at most `u32::MAX`, so halving them fits.
+        Point::new(x.div_euclid(2) as i32, y.div_euclid(2) as i32)
+    }
+
+    /// The shift for the points of a path with segments of these (unmagnified)
+    /// widths. Only paths whose widths are all odd are shifted.
+    fn path_shift(&self, mut widths: impl Iterator<Item = u32>) -> Point {
+        let odd = |w: u32| w % 2 == 1;
+        match widths.next() {
+            Some(w) if odd(w) && widths.all(odd) => self.odd_width_shift(),
+            _ => Point::default(),
+        }
+    }
+
+    /// Apply the transform to a point of a path, and then the path's shift.
+    fn apply_shifted<P: PointLike>(&self, p: P, shift: Point) -> Point {
+        self.try_apply(Point::new(p.x(), p.y()))
+            .and_then(|p| p.try_shift(shift))
+            .expect("transformed point overflows the i32 range")
+    }
+}
+
+/// The first and last non-empty steps of a path, as directions.
+fn end_steps<P: PointLike>(points: &[P]) -> Option<(OctDirection, OctDirection)> {
+    let mut steps = points
+        .windows(2)
+        .filter_map(|w| w[0].oct_direction_to(&w[1]));
+    let first = steps.next()?;
+    Some((first, steps.last().unwrap_or(first)))
+}
+
+/// The end cap of a path whose transformed points are moved by `shift`.
+///
+/// Half width caps are measured from the shifted centerline, so they stay
+/// where they were, but flush and custom caps are measured from the end
+/// points, so they are lengthened or shortened to make up for the shift.
+fn shifted_end_cap<P: PointLike>(
+    end_cap: PathEndCap,
+    points: &[P],
+    transform: &Transform,
+    shift: Point,
+) -> PathEndCap {
+    let (begin, end) = match end_cap {
+        PathEndCap::HalfWidth => return end_cap,
+        PathEndCap::Flush => (0, 0),
+        PathEndCap::Custom { begin, end } => (begin, end),
+    };
+    let Some((first, last)) = end_steps(points) else {
+        return end_cap;
+    };
+    // The shift along the direction of the transformed path at each end, if
+    // that is horizontal or vertical.
+    let along = |direction: OctDirection| {
+        let (dx, dy) = direction.step();
+        let (dx, dy) = transform.orient(dx as i64, dy as i64);
+        (dx == 0 || dy == 0).then_some(dx as i32 * shift.x + dy as i32 * shift.y)
+    };
+    match (along(first), along(last)) {
+        (Some(0), Some(0)) => end_cap,
+        (Some(first), Some(last)) => PathEndCap::Custom {
+            begin: begin.saturating_add(first),
+            end: end.saturating_sub(last),
+        },
+        _ => end_cap,
+    }
+}
+
+/// Whether transforming a path with [`Transformable::transform`] covers
+/// exactly the transformed area of the path.
+///
+/// This fails where an odd width path must be shifted but has diagonal
+/// segments, whose sides are rounded onto the grid differently once the path
+/// is moved, or where a variable width path has both odd and even widths, so
+/// that only some of its segments would need shifting.
+fn path_moves_exactly<P: PointLike>(points: &[P], widths: &[u32], transform: &Transform) -> bool {
+    let odd = |&w: &u32| w % 2 == 1;
+    if transform.odd_width_shift() == Point::default() || !widths.iter().any(odd) {
+        return true;
+    }
+    widths.iter().all(odd)
+        && points.windows(2).all(|w| {
+            w[0].oct_direction_to(&w[1])
+                .is_none_or(|d| !d.is_diagonal())
+        })
+}
+
+impl From<&ArchivedRotation> for Rotation {
+    fn from(rotation: &ArchivedRotation) -> Self {
+        match rotation {
+            ArchivedRotation::R0 => Rotation::R0,
+            ArchivedRotation::R90 => Rotation::R90,
+            ArchivedRotation::R180 => Rotation::R180,
+            ArchivedRotation::R270 => Rotation::R270,
+        }
+    }
+}
+
+impl From<&ArchivedTransform> for Transform {
+    fn from(transform: &ArchivedTransform) -> Self {
+        Transform {
+            offset: transform.offset.into(),
+            rotation: (&transform.rotation).into(),
+            mirror_x: transform.mirror_x,
+            magnification: transform.magnification,
+        }
+    }
+}
+
+/// An iterator over points moved by a transform, which leaves the points
+/// themselves untouched. This works on archived points in place.
+#[derive(Debug, Clone)]
+pub struct TransformedPoints<'a, P> {
+    points: std::slice::Iter<'a, P>,
+    transform: Transform,
+}
+
+impl<P: PointLike> Iterator for TransformedPoints<'_, P> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        self.points.next().map(|&p| self.transform.apply(p).into())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.points.size_hint()
+    }
+}
+
+impl<P: PointLike> ExactSizeIterator for TransformedPoints<'_, P> {}
+
+impl Transform {
+    /// Lazily apply the transform to each of `points`.
+    pub fn apply_all<'a, P: PointLike>(&self, points: &'a [P]) -> TransformedPoints<'a, P> {
+        TransformedPoints {
+            points: points.iter(),
+            transform: *self,
+        }
+    }
+}
+
+/// An archived shape seen through a transform, without deserializing it.
+#[derive(Debug, Clone, Copy)]
+pub struct TransformedShape<'a> {
+    pub shape: &'a ArchivedShape,
+    pub transform: Transform,
+}
+
+impl<'a> TransformedShape<'a> {
+    pub fn new(shape: &'a ArchivedShape, transform: Transform) -> Self {
+        TransformedShape { shape, transform }
+    }
+
+    pub fn layer(&self) -> u8 {
+        self.shape.layer()
+    }
+
+    /// The transformed points of the shape: the two corners of a rect, the
+    /// vertices of a polygon, or the centerline of a path, shifted as by
+    /// [`Transformable::transform`].
+    pub fn points(&self) -> impl Iterator<Item = Point> + 'a {
+        let (corners, points): (Option<[ArchivedPoint; 2]>, &'a [ArchivedPoint]) = match self.shape
+        {
+            ArchivedShape::Rect(r) => (Some([r.p0, r.p1]), &[]),
+            ArchivedShape::Poly(p) => (None, &p.points),
+            ArchivedShape::Path(p) => (None, &p.points),
+            ArchivedShape::VarWidthPath(p) => (None, &p.points),
+        };
+        let transform = self.transform;
+        let shift = self.shift();
+        corners
+            .into_iter()
+            .flatten()
+            .chain(points.iter().copied())
+            .map(move |p| transform.apply_shifted(p, shift))
+    }
+
+    /// The shift of the points of a path, as for [`Transformable::transform`].
+    fn shift(&self) -> Point {
+        match self.shape {
+            ArchivedShape::Path(p) => self.transform.path_shift(std::iter::once(p.width)),
+            ArchivedShape::VarWidthPath(p) => self.transform.path_shift(p.widths.iter().copied()),
+            _ => Point::default(),
+        }
+    }
+
+    /// The transformed rect, with its minimum corner in `p0`, if the shape is
+    /// a rect.
+    pub fn rect(&self) -> Option<Rect> {
+        let ArchivedShape::Rect(r) = self.shape else {
+            return None;
+        };
+        let (p0, p1): (Point, Point) = (
+            self.transform.apply(r.p0).into(),
+            self.transform.apply(r.p1).into(),
+        );
+        Some(Rect {
+            p0: Point::new(p0.x.min(p1.x), p0.y.min(p1.y)),
+            p1: Point::new(p0.x.max(p1.x), p0.y.max(p1.y)),
+            layer: r.layer,
+        })
+    }
+
+    /// The magnified width of each segment of a path. Rects and polygons have
+    /// no segments.
+    pub fn widths(&self) -> impl Iterator<Item = u32> + 'a {
+        let transform = self.transform;
+        let (width, segments, widths): (Option<u32>, usize, &'a [u32]) = match self.shape {
+            ArchivedShape::Path(p) => (Some(p.width), p.points.len().saturating_sub(1), &[]),
+            ArchivedShape::VarWidthPath(p) => (None, 0, &p.widths),
+            _ => (None, 0, &[]),
+        };
+        std::iter::repeat_n(width.unwrap_or(0), segments)
+            .chain(widths.iter().copied())
+            .map(move |w| transform.magnify_length(w))
+    }
+
+    /// The magnified end cap of a path, adjusted for its shift as by
+    /// [`Transformable::transform`].
+    pub fn end_cap(&self) -> Option<PathEndCap> {
+        let (end_cap, points): (PathEndCap, &[ArchivedPoint]) = match self.shape {
+            ArchivedShape::Path(p) => ((&p.end_cap).into(), &p.points),
+            ArchivedShape::VarWidthPath(p) => ((&p.end_cap).into(), &p.points),
+            _ => return None,
+        };
+        Some(shifted_end_cap(
+            end_cap.transform(&self.transform),
+            points,
+            &self.transform,
+            self.shift(),
+        ))
+    }
+
+    /// The bounding box of the transformed shape. Panics if the shape has no
+    /// points or no area; see [`try_bbox`](Self::try_bbox).
+    pub fn bbox(&self) -> BoundingBox {
+        self.try_bbox()
+            .expect("bounding boxes need something with an area to bound")
+    }
+
+    /// The bounding box of the transformed shape, or `None` if the shape has
+    /// no points or no area.
+    pub fn try_bbox(&self) -> Option<BoundingBox> {
+        let mut bbox = UnvalidatedBoundingBox::invalid();
+        match self.shape.shape_ref() {
+            ShapeRef::Rect(_) | ShapeRef::Poly(_) => {
+                self.points().for_each(|pt| bbox.update_point(pt))
+            }
+            ShapeRef::Path(p) => p
+                .extent_points()
+                .for_each(|pt| bbox.update_point(self.transform.apply(pt))),
+            ShapeRef::VarWidthPath(p) => p
+                .extent_points()
+                .into_iter()
+                .for_each(|pt| bbox.update_point(self.transform.apply(pt))),
+        }
+        bbox.validate()
+    }
+
+    /// Deserialize the shape and apply the transform to it.
+    pub fn to_shape(&self) -> Shape {
+        let shape: Shape = self.shape.deserialize(&mut Infallible).unwrap();
+        shape.transform(&self.transform)
+    }
+}
+
+/// Shapes which can be moved by a [`Transform`].
+///
+/// Widths and end cap extensions are scaled by the magnification. Odd widths
+/// put their extra unit on the +x and +y sides of a path, so a path of odd
+/// width which is mirrored, rotated or magnified is moved to keep it there,
+/// and its flush or custom ends are adjusted to match, so that it covers
+/// exactly the transformed area of the original. Panics if any coordinate or
+/// width overflows.
+pub trait Transformable {
+    fn transform(&self, transform: &Transform) -> Self;
+}
+
+impl Transformable for Point {
+    fn transform(&self, transform: &Transform) -> Self {
+        transform.apply(*self)
+    }
+}
+
+impl Transformable for Rect {
+    /// The transformed rect has its minimum corner in `p0` and its maximum
+    /// corner in `p1`.
+    fn transform(&self, transform: &Transform) -> Self {
+        let (p0, p1) = (transform.apply(self.p0), transform.apply(self.p1));
+        Rect {
+            p0: Point::new(p0.x.min(p1.x), p0.y.min(p1.y)),
+            p1: Point::new(p0.x.max(p1.x), p0.y.max(p1.y)),
+            layer: self.layer,
+        }
+    }
+}
+
+impl Transformable for Poly {
+    fn transform(&self, transform: &Transform) -> Self {
+        Poly {
+            points: self.points.iter().map(|&p| transform.apply(p)).collect(),
+            layer: self.layer,
+        }
+    }
+}
+
+impl Transformable for PathEndCap {
+    fn transform(&self, transform: &Transform) -> Self {
+        match *self {
+            PathEndCap::Custom { begin, end } => PathEndCap::Custom {
+                begin: transform.magnify_length(begin),
+                end: transform.magnify_length(end),
+            },
+            end_cap => end_cap,
+        }
+    }
+}
+
+impl Transformable for Path {
+    /// The sides and ends of diagonal segments of an odd width path are
+    /// rounded onto the grid differently once it is shifted, so those may be
+    /// off by a unit; see [`Shape::transform`](Transformable::transform) for
+    /// an exact result.
+    fn transform(&self, transform: &Transform) -> Self {
+        let shift = transform.path_shift(std::iter::once(self.width));
+        Path {
+            points: self
+                .points
+                .iter()
+                .map(|&p| transform.apply_shifted(p, shift))
+                .collect(),
+            width: transform.magnify_length(self.width),
+            layer: self.layer,
+            end_cap: shifted_end_cap(
+                self.end_cap.transform(transform),
+                &self.points,
+                transform,
+                shift,
+            ),
+        }
+    }
+}
+
+impl Transformable for VarWidthPath {
+    /// A path with both odd and even widths cannot be shifted for its odd
+    /// segments alone, so those may be off by a unit; see
+    /// [`Shape::transform`](Transformable::transform) for an exact result.
+    fn transform(&self, transform: &Transform) -> Self {
+        let shift = transform.path_shift(self.widths.iter().copied());
+        VarWidthPath {
+            points: self
+                .points
+                .iter()
+                .map(|&p| transform.apply_shifted(p, shift))
+                .collect(),
+            widths: self
+                .widths
+                .iter()
+                .map(|&w| transform.magnify_length(w))
+                .collect(),
+            layer: self.layer,
+            end_cap: shifted_end_cap(
+                self.end_cap.transform(transform),
+                &self.points,
+                transform,
+                shift,
+            ),
+        }
+    }
+}
+
+impl Transformable for Shape {
+    /// Paths which cannot be moved exactly as paths are turned into polygons,
+    /// where they have one.
+    fn transform(&self, transform: &Transform) -> Self {
+        match self {
+            Shape::Rect(r) => Shape::Rect(r.transform(transform)),
+            Shape::Poly(p) => Shape::Poly(p.transform(transform)),
+            Shape::Path(p) => {
+                if !path_moves_exactly(&p.points, &[p.width], transform) {
+                    if let Ok(poly) = p.as_poly() {
+                        return Shape::Poly(poly.transform(transform));
+                    }
+                }
+                Shape::Path(p.transform(transform))
+            }
+            Shape::VarWidthPath(p) => {
+                if !path_moves_exactly(&p.points, &p.widths, transform) {
+                    if let Ok(poly) = p.as_poly() {
+                        return Shape::Poly(poly.transform(transform));
+                    }
+                }
+                Shape::VarWidthPath(p.transform(transform))
+            }
+        }
+    }
+}
+
+impl Transformable for BoundingBox {
+    fn transform(&self, transform: &Transform) -> Self {
+        BoundingBox::new(UnvalidatedBoundingBox {
+            min: transform.apply(self.min()),
+            max: transform.apply(self.max()),
+        })
+    }
+}
+
+impl Transformable for Shapes {
+    fn transform(&self, transform: &Transform) -> Self {
+        Shapes {
+            bbox: self.bbox.transform(transform),
+            shapes: self.shapes.iter().map(|s| s.transform(transform)).collect(),
+            units: self.units,
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::num::NonZeroU32;
+use std::{error::Error, fmt::Display};
+
+use crate::bbox::CalculateBoundingBox;
+use crate::shapes::{Path, Point, Poly, PolyLike, Rect, Shape, Shapes, VarWidthPath};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitsErr {
+    /// The value to convert was infinite or not a number.
+    NotFinite,
+    /// The value to convert does not fit in an `i32` of database units.
+    Overflow,
+}
+
+impl Display for UnitsErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for UnitsErr {}
+
+/// The meaning of the integer coordinates of shapes, which are in database
+/// units (DBU).
+///
+/// Every field is non-zero, so archives and serialized files with a zero unit
+/// or grid are rejected rather than dividing by zero later.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+    Copy,
+    Archive,
+    Deserialize,
+    Serialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(Debug))]
+pub struct Units {
+    pub dbu_per_micron: NonZeroU32,
+    /// The database units in one user unit, the unit in which coordinates are
+    /// presented to users.
+    pub dbu_per_user_unit: NonZeroU32,
+    /// The manufacturing grid, in database units. Every vertex should lie on
+    /// a multiple of it.
+    pub grid: NonZeroU32,
+}
+
+/// Nanometer database units, with microns as the user unit and no grid
+/// beyond the database units themselves.
+impl Default for Units {
+    fn default() -> Self {
+        Units::new(1000, 1)
+    }
+}
+
+/// A vertex of a shape which is not on the manufacturing grid.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct OffGridVertex {
+    /// The index of the shape.
+    pub shape: usize,
+    /// The index of the vertex among the points of the shape. The corners of
+    /// a rect are `p0` and then `p1`.
+    pub vertex: usize,
+    pub point: Point,
+}
+
+impl Units {
+    /// Units with microns as the user unit. Panics if either is zero.
+    pub fn new(dbu_per_micron: u32, grid: u32) -> Self {
+        let (Some(dbu_per_micron), Some(grid)) =
+            (NonZeroU32::new(dbu_per_micron), NonZeroU32::new(grid))
+        else {
+            panic!("Units and grids must be positive");
+        };
+        Units {
+            dbu_per_micron,
+            dbu_per_user_unit: dbu_per_micron,
+            grid,
+        }
+    }
+
+    /// Panics if `dbu_per_user_unit` is zero.
+    pub fn with_user_unit(self, dbu_per_user_unit: u32) -> Self {
+        Units {
+            dbu_per_user_unit: NonZeroU32::new(dbu_per_user_unit).expect("Units must be positive"),
+            ..self
+        }
+    }
+
+    /// The nearest whole number of database units to `microns`.
+    pub fn microns_to_dbu(&self, microns: f64) -> Result<i32, UnitsErr> {
+        to_dbu(microns * self.dbu_per_micron.get() as f64)
+    }
+
+    pub fn dbu_to_microns(&self, dbu: i32) -> f64 {
+        dbu as f64 / self.dbu_per_micron.get() as f64
+    }
+
+    /// The nearest whole number of database units to `user` user units.
+    pub fn user_to_dbu(&self, user: f64) -> Result<i32, UnitsErr> {
+        to_dbu(user * self.dbu_per_user_unit.get() as f64)
+    }
+
+    pub fn dbu_to_user(&self, dbu: i32) -> f64 {
+        dbu as f64 / self.dbu_per_user_unit.get() as f64
+    }
+
+    pub fn is_on_grid(&self, p: Point) -> bool {
+        let grid = self.grid.get() as i64;
+        (p.x as i64).rem_euclid(grid) == 0 && (p.y as i64).rem_euclid(grid) == 0
+    }
+
+    /// The nearest multiple of the grid to `v`, rounding halfway cases up.
+    /// Multiples past the end of the `i32` range are avoided by rounding the
+    /// other way.
+    pub fn snap(&self, v: i32) -> i32 {
+        let grid = self.grid.get() as i64;
+        let snapped = (v as i64 + grid / 2).div_euclid(grid) * grid;
+        if snapped > i32::MAX as i64 {
+            (snapped - grid) as i32
+        } else if snapped < i32::MIN as i64 {
+            (snapped + grid) as i32
+        } else {
+            snapped as i32
+        }
+    }
+
+    pub fn snap_point(&self, p: Point) -> Point {
+        Point {
+            x: self.snap(p.x),
+            y: self.snap(p.y),
+        }
+    }
+
+    /// Move every vertex of a shape to the nearest point on the grid, or
+    /// `None` if the shape collapses.
+    ///
+    /// Vertices which snap onto the one before them are removed, along with
+    /// the widths of the empty segments they leave in variable width paths.
+    /// Rects and polygons collapse if they are left without area, and paths
+    /// if they are left without a segment.
+    ///
+    /// Only vertices are snapped: path widths are left alone, so the edges of
+    /// a path are only on the grid if its width is a multiple of twice the
+    /// grid.
+    pub fn snap_shape(&self, shape: &Shape) -> Option<Shape> {
+        match shape {
+            Shape::Rect(r) => {
+                let (p0, p1) = (self.snap_point(r.p0), self.snap_point(r.p1));
+                (p0.x != p1.x && p0.y != p1.y).then_some(Shape::Rect(Rect { p0, p1, ..*r }))
+            }
+            Shape::Poly(p) => {
+                let (mut points, _) = self.snap_points(&p.points);
+                while points.len() > 1 && points.first() == points.last() {
+                    points.pop();
+                }
+                let poly = Poly {
+                    points,
+                    layer: p.layer,
+                };
+                (poly.points.len() >= 3 && poly.area_doubled() != 0).then_some(Shape::Poly(poly))
+            }
+            Shape::Path(p) => {
+                let (points, _) = self.snap_points(&p.points);
+                (points.len() >= 2).then_some(Shape::Path(Path {
+                    points,
+                    width: p.width,
+                    layer: p.layer,
+                    end_cap: p.end_cap,
+                }))
+            }
+            Shape::VarWidthPath(p) => {
+                let (points, kept) = self.snap_points(&p.points);
+                // Each segment left keeps the width of the segment ending at
+                // its far end; the ones before it were empty.
+                let widths = kept
+                    .iter()
+                    .skip(1)
+                    .filter_map(|&ix| p.widths.get(ix - 1).copied())
+                    .collect();
+                (points.len() >= 2).then_some(Shape::VarWidthPath(VarWidthPath {
+                    points,
+                    widths,
+                    layer: p.layer,
+                    end_cap: p.end_cap,
+                }))
+            }
+        }
+    }
+
+    /// Snap `points` to the grid, leaving out those which snap onto the point
+    /// before them. Also returns the indices of the points kept.
+    fn snap_points(&self, points: &[Point]) -> (Vec<Point>, Vec<usize>) {
+        let mut snapped: Vec<Point> = Vec::with_capacity(points.len());
+        let mut kept = Vec::with_capacity(points.len());
+        for (ix, &p) in points.iter().enumerate() {
+            let p = self.snap_point(p);
+            if snapped.last() != Some(&p) {
+                snapped.push(p);
+                kept.push(ix);
+            }
+        }
+        (snapped, kept)
+    }
+
+    /// The vertices of `shapes` which are not on the grid, shape by shape.
+    pub fn off_grid_vertices(&self, shapes: &[Shape]) -> Vec<OffGridVertex> {
+        let mut off_grid = Vec::new();
+        for (shape_ix, shape) in shapes.iter().enumerate() {
+            let points: &[Point] = match shape {
+                Shape::Rect(r) => &[r.p0, r.p1],
+                Shape::Poly(p) => &p.points,
+                Shape::Path(p) => &p.points,
+                Shape::VarWidthPath(p) => &p.points,
+            };
+            off_grid.extend(
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &p)| !self.is_on_grid(p))
+                    .map(|(vertex, &point)| OffGridVertex {
+                        shape: shape_ix,
+                        vertex,
+                        point,
+                    }),
+            );
+        }
+        off_grid
+    }
+}
+
+fn to_dbu(dbu: f64) -> Result<i32, UnitsErr> {
+    if !dbu.is_finite() {
+        return Err(UnitsErr::NotFinite);
+    }
+    let dbu = dbu.round();
+    if dbu < i32::MIN as f64 || dbu > i32::MAX as f64 {
+        return Err(UnitsErr::Overflow);
+    }
+    Ok(dbu as i32)
+}
+
+impl Shapes {
+    /// The vertices which are not on the manufacturing grid of
+    /// [`units`](Shapes::units).
+    pub fn off_grid_vertices(&self) -> Vec<OffGridVertex> {
+        self.units.off_grid_vertices(&self.shapes)
+    }
+
+    /// Snap every vertex to the manufacturing grid, as with
+    /// [`Units::snap_shape`], and update the bounding box to match.
+    ///
+    /// Shapes which collapse are removed, and their indices from before
+    /// snapping are returned. If every shape collapses, the bounding box is
+    /// left as it was.
+    pub fn snap_to_grid(&mut self) -> Vec<usize> {
+        let mut collapsed = Vec::new();
+        let mut snapped = Vec::with_capacity(self.shapes.len());
+        for (ix, shape) in self.shapes.iter().enumerate() {
+            match self.units.snap_shape(shape) {
+                Some(shape) => snapped.push(shape),
+                None => collapsed.push(ix),
+            }
+        }
+        self.shapes = snapped;
+        if let Some(bbox) = self.shapes.try_bbox() {
+            self.bbox = bbox;
+        }
+        collapsed
+    }
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use common::{bbox, rect};
+use doug_geometry::bbox::{CalculateBoundingBox, UnvalidatedBoundingBox};
+use doug_geometry::boolean::Region;
+use doug_geometry::shapes::{
+    ArchivedPoint, CoordinateErr, Point, PointLike, Poly, PolyLike, RectLike, Shape,
+};
+
+const MIN: i32 = i32::MIN;
+const MAX: i32 = i32::MAX;
+
+#[test]
+fn points_shift_within_the_i32_range() {
+    let p = Point::new(MAX - 1, MIN + 1);
+    assert_eq!(
+        p.checked_shift(Point::new(1, -1)),
+        Some(Point::new(MAX, MIN))
+    );
+    assert_eq!(p.checked_shift(Point::new(2, 0)), None);
+    assert_eq!(p.checked_shift(Point::new(0, -2)), None);
+    assert_eq!(p.try_shift(Point::new(2, 0)), Err(CoordinateErr::Overflow));
+    assert_eq!(
+        p.try_shift(Point::new(-5, 5)),
+        Ok(Point::new(MAX - 6, MIN + 6))
+    );
+    assert_eq!(
+        p.saturating_shift(Point::new(10, -10)),
+        Point::new(MAX, MIN)
+    );
+
+    assert_eq!(p.checked_sub(Point::new(-1, 1)), Some(Point::new(MAX, MIN)));
+    assert_eq!(p.checked_sub(Point::new(-2, 0)), None);
+    assert_eq!(p.saturating_sub(Point::new(-10, 10)), Point::new(MAX, MIN));
+
+    assert_eq!(p + Point::new(1, 0), Point::new(MAX, MIN + 1));
+    assert_eq!(p - Point::new(0, 1), Point::new(MAX - 1, MIN));
+    assert_eq!(p.shift(Point::new(-1, -1)), Point::new(MAX - 2, MIN));
+}
+
+#[test]
+fn wide_coordinates_convert_if_they_fit() {
+    assert_eq!(
+        Point::try_from_wide(MAX as i64, MIN as i64),
+        Ok(Point::new(MAX, MIN))
+    );
+    assert_eq!(
+        Point::try_from_wide(MAX as i64 + 1, 0),
+        Err(CoordinateErr::Overflow)
+    );
+    assert_eq!(
+        Point::try_from_wide(0, MIN as i64 - 1),
+        Err(CoordinateErr::Overflow)
+    );
+}
+
+// Point arithmetic panics on overflow in release builds too, rather than
+// wrapping around.
+#[test]
+#[should_panic(expected = "overflows the i32 range")]
+fn adding_past_the_i32_range_panics() {
+    let _ = Point::new(MAX, 0) + Point::new(1, 0);
+}
+
+#[test]
+#[should_panic(expected = "overflows the i32 range")]
+fn subtracting_past_the_i32_range_panics() {
+    let _ = Point::new(0, MIN) - Point::new(0, 1);
+}
+
+#[test]
+fn archived_points_add_and_subtract_like_points() {
+    let (a, b) = (
+        ArchivedPoint::new(MAX - 1, MIN + 5),
+        ArchivedPoint::new(1, 5),
+    );
+    assert_eq!(Point::from(a + b), Point::new(MAX, MIN + 10));
+    assert_eq!(Point::from(a - b), Point::new(MAX - 2, MIN));
+}
+
+#[test]
+#[should_panic(expected = "overflows the i32 range")]
+fn adding_archived_points_past_the_i32_range_panics() {
+    let _ = ArchivedPoint::new(MAX, 0) + ArchivedPoint::new(1, 0);
+}
+
+#[test]
+#[should_panic(expected = "overflows the i32 range")]
+fn subtracting_archived_points_past_the_i32_range_panics() {
+    let _ = ArchivedPoint::new(0, MIN) - ArchivedPoint::new(0, 1);
+}
+
+#[test]
+#[should_panic(expected = "overflows the i32 range")]
+fn shifting_past_the_i32_range_panics() {
+    Point::new(MIN, 0).shift(Point::new(-1, 0));
+}
+
+#[test]
+fn bounding_boxes_shift_within_the_i32_range() {
+    let b = bbox(0, 0, 10, 10);
+    assert_eq!(
+        b.checked_shift(Point::new(MAX - 10, 0)),
+        Some(bbox(MAX - 10, 0, MAX, 10))
+    );
+    assert_eq!(b.checked_shift(Point::new(MAX - 9, 0)), None);
+    assert_eq!(
+        b.try_shift(Point::new(0, MIN)),
+        Ok(bbox(0, MIN, 10, MIN + 10))
+    );
+    assert_eq!(
+        b.try_shift(Point::new(0, MAX)),
+        Err(CoordinateErr::Overflow)
+    );
+}
+
+#[test]
+fn measures_of_the_whole_plane_do_not_overflow() {
+    let span = MAX as i64 - MIN as i64;
+    let plane = bbox(MIN, MIN, MAX, MAX);
+    assert_eq!(plane.width(), span);
+    assert_eq!(plane.height(), span);
+    assert_eq!(plane.area(), span as i128 * span as i128);
+    assert!(plane.area() > i64::MAX as i128);
+    assert_eq!(plane.perimeter(), 4 * span);
+
+    // Rects measure the same either way round.
+    let rect = rect(MAX, MAX, MIN, MIN, 0);
+    assert_eq!(rect.width(), span);
+    assert_eq!(rect.height(), span);
+    assert_eq!(rect.area(), plane.area());
+    assert_eq!(rect.perimeter(), plane.perimeter());
+
+    let region = Region::from_rect(&rect);
+    assert_eq!(region.area(), plane.area());
+    assert_eq!(region.perimeter(), 4 * span as i128);
+
+    let triangle = Poly {
+        points: vec![
+            Point::new(MIN, MIN),
+            Point::new(MAX, MIN),
+            Point::new(MIN, MAX),
+        ],
+        layer: 0,
+    };
+    assert_eq!(triangle.area_doubled(), plane.area());
+}
+
+#[test]
+fn empty_bounding_boxes_are_not_validated() {
+    let empty = UnvalidatedBoundingBox::invalid();
+    assert!(empty.is_empty());
+    assert_eq!(empty.validate(), None);
+
+    // A single point, even at the sentinel values, is not empty but has no
+    // area.
+    let mut point = UnvalidatedBoundingBox::invalid();
+    point.update_point(Point::new(MAX, MAX));
+    assert!(!point.is_empty());
+    assert_eq!(point.validate(), None);
+
+    let mut corners = UnvalidatedBoundingBox::invalid();
+    corners.update_point(Point::new(MAX, MIN));
+    corners.update_point(Point::new(MIN, MAX));
+    assert_eq!(corners.validate(), Some(bbox(MIN, MIN, MAX, MAX)));
+}
+
+#[test]
+fn shapes_without_points_have_no_bounding_box() {
+    let empty = Shape::Poly(Poly {
+        points: vec![],
+        layer: 0,
+    });
+    assert_eq!(empty.try_bbox(), None);
+    assert_eq!(Vec::<Shape>::new().try_bbox(), None);
+    assert_eq!(Region::new().bbox(), None);
+
+    // Shapes which are flat on their own can still be bounded together.
+    let flat = |x0, y0, x1, y1| Shape::Rect(rect(x0, y0, x1, y1, 0));
+    assert_eq!(flat(0, 0, 10, 0).try_bbox(), None);
+    let shapes = vec![flat(0, 0, 10, 0), flat(5, 5, 5, 20), empty];
+    assert_eq!(shapes.try_bbox(), Some(bbox(0, 0, 10, 20)));
+}
+
+#[test]
+#[should_panic(expected = "bounding boxes need something with an area to bound")]
+fn bounding_nothing_panics() {
+    Vec::<Shape>::new().bbox();
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use common::{poly, rect};
+use doug_geometry::boolean::{BooleanOp, PolyWithHoles, Region};
+
+fn region(x0: i32, y0: i32, x1: i32, y1: i32) -> Region {
+    Region::from_rect(&rect(x0, y0, x1, y1, 0))
+}
+
+#[test]
+fn overlapping_rects() {
+    let (a, b) = (region(0, 0, 10, 10), region(5, 5, 15, 15));
+
+    let union = a.union(&b);
+    assert_eq!(union.area(), 175);
+    assert_eq!(
+        union.polys(0),
+        vec![PolyWithHoles {
+            outline: poly(
+                &[
+                    (0, 0),
+                    (10, 0),
+                    (10, 5),
+                    (15, 5),
+                    (15, 15),
+                    (5, 15),
+                    (5, 10),
+                    (0, 10)
+                ],
+                0
+            ),
+            holes: vec![],
+        }]
+    );
+
+    assert_eq!(a.intersection(&b), region(5, 5, 10, 10));
+    assert_eq!(a.difference(&b).area(), 75);
+    assert_eq!(a.difference(&b).polys(0).len(), 1);
+    assert_eq!(
+        a.xor(&b),
+        union.difference(&region(5, 5, 10, 10)),
+        "xor is the union less the overlap"
+    );
+    assert_eq!(
+        a.xor(&b).polys(0).len(),
+        2,
+        "the overlap pinches at corners"
+    );
+}
+
+#[test]
+fn boolean_matches_named_ops() {
+    let (a, b) = (region(0, 0, 10, 10), region(5, -5, 20, 5));
+    assert_eq!(a.boolean(&b, BooleanOp::Or), a.union(&b));
+    assert_eq!(a.boolean(&b, BooleanOp::And), a.intersection(&b));
+    assert_eq!(a.boolean(&b, BooleanOp::Not), a.difference(&b));
+    assert_eq!(a.boolean(&b, BooleanOp::Xor), a.xor(&b));
+}
+
+#[test]
+fn edge_touching_rects_merge() {
+    let (a, b) = (region(0, 0, 10, 10), region(10, 0, 20, 10));
+    assert_eq!(a.union(&b), region(0, 0, 20, 10));
+    assert!(a.intersection(&b).is_empty());
+    assert_eq!(a.difference(&b), a);
+    assert_eq!(a.xor(&b), a.union(&b));
+
+    // Stacked vertically, the slabs merge too.
+    let c = region(0, 10, 10, 20);
+    assert_eq!(a.union(&c), region(0, 0, 10, 20));
+    assert_eq!(a.union(&c).rects(0), vec![rect(0, 0, 10, 20, 0)]);
+}
+
+#[test]
+fn corner_touching_rects_stay_apart() {
+    let union = region(0, 0, 10, 10).union(&region(10, 10, 20, 20));
+    assert_eq!(union.area(), 200);
+    assert_eq!(union.components().len(), 2);
+
+    let polys = union.polys(0);
+    assert_eq!(polys.len(), 2);
+    assert_eq!(
+        polys[0].outline,
+        poly(&[(0, 0), (10, 0), (10, 10), (0, 10)], 0)
+    );
+    assert_eq!(
+        polys[1].outline,
+        poly(&[(10, 10), (20, 10), (20, 20), (10, 20)], 0)
+    );
+}
+
+#[test]
+fn difference_cuts_a_hole() {
+    let ring = region(0, 0, 30, 30).difference(&region(10, 10, 20, 20));
+    assert_eq!(ring.area(), 800);
+    assert_eq!(ring.perimeter(), 160);
+
+    let polys = ring.polys(3);
+    assert_eq!(polys.len(), 1);
+    // Outlines are counter-clockwise and holes clockwise, both starting from
+    // their lowest, left-most point.
+    assert_eq!(
+        polys[0].outline.points,
+        poly(&[(0, 0), (30, 0), (30, 30), (0, 30)], 0).points
+    );
+    assert_eq!(
+        polys[0].holes[0].points,
+        poly(&[(10, 10), (10, 20), (20, 20), (20, 10)], 0).points
+    );
+    assert_eq!(polys[0].outline.layer, 3);
+    assert_eq!(polys[0].region().unwrap(), ring);
+}
+
+#[test]
+fn holed_inputs() {
+    let ring = region(0, 0, 30, 30).difference(&region(10, 10, 20, 20));
+    let plug = region(5, 5, 25, 25);
+
+    assert_eq!(ring.union(&plug), region(0, 0, 30, 30));
+    assert_eq!(ring.intersection(&plug).area(), 300);
+    assert_eq!(ring.intersection(&plug).polys(0)[0].holes.len(), 1);
+    assert_eq!(
+        ring.xor(&plug),
+        region(0, 0, 30, 30)
+            .difference(&region(5, 5, 25, 25))
+            .union(&region(10, 10, 20, 20))
+    );
+
+    // A filled hole inside a hole is a separate polygon.
+    let island = ring.union(&region(12, 12, 18, 18));
+    let polys = island.polys(0);
+    assert_eq!(polys.len(), 2);
+    assert_eq!(polys[0].holes.len(), 1);
+    assert!(polys[1].holes.is_empty());
+    assert_eq!(island.components().len(), 2);
+}
+
+#[test]
+fn from_poly_ignores_winding() {
+    let l_shape = [(0, 0), (20, 0), (20, 10), (10, 10), (10, 20), (0, 20)];
+    let mut reversed = l_shape;
+    reversed.reverse();
+
+    let a = Region::from_poly(&poly(&l_shape, 0)).unwrap();
+    assert_eq!(a, Region::from_poly(&poly(&reversed, 0)).unwrap());
+    assert_eq!(a.area(), 300);
+    assert_eq!(a.polys(0)[0].outline, poly(&l_shape, 0));
+}
+
+#[test]
+fn traced_loops_drop_collinear_points() {
+    let region = Region::from_rects(
+        [
+            rect(0, 0, 10, 10, 0),
+            rect(10, 0, 20, 10, 0),
+            rect(0, 10, 10, 20, 0),
+        ]
+        .iter(),
+    );
+    let polys = region.polys(0);
+    assert_eq!(polys.len(), 1);
+    assert_eq!(
+        polys[0].outline,
+        poly(&[(0, 0), (20, 0), (20, 10), (10, 10), (10, 20), (0, 20)], 0)
+    );
+}
+
+#[test]
+fn traced_loops_split_at_pinch_points() {
+    // Outlines meeting at a corner are traced as separate loops.
+    let outlines = region(0, 0, 10, 10).union(&region(10, 10, 20, 20));
+    assert_eq!(outlines.polys(0).len(), 2);
+
+    // The covered area connects around holes meeting at a corner, so they are
+    // traced as one loop which passes through the corner twice.
+    let holes = region(0, 0, 30, 30)
+        .difference(&region(5, 5, 15, 15))
+        .difference(&region(15, 15, 25, 25));
+    let polys = holes.polys(0);
+    assert_eq!(polys.len(), 1);
+    assert_eq!(
+        polys[0].holes,
+        vec![poly(
+            &[
+                (5, 5),
+                (5, 15),
+                (15, 15),
+                (15, 25),
+                (25, 25),
+                (25, 15),
+                (15, 15),
+                (15, 5)
+            ],
+            0
+        )]
+    );
+    assert_eq!(polys[0].region().unwrap(), holes);
+}
+
+#[test]
+fn empty_regions() {
+    let empty = Region::new();
+    let a = region(0, 0, 10, 10);
+    assert!(empty.is_empty());
+    assert_eq!(empty.area(), 0);
+    assert_eq!(empty.bbox(), None);
+    assert!(empty.polys(0).is_empty());
+    assert_eq!(a.union(&empty), a);
+    assert!(a.intersection(&empty).is_empty());
+    assert!(a.difference(&a).is_empty());
+    assert!(a.xor(&a).is_empty());
+    // Degenerate rects cover nothing.
+    assert!(region(0, 0, 0, 10).is_empty());
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::{path, poly, rect};
+use doug_geometry::boolean::Region;
+use doug_geometry::centerline::{poly_to_path, recover_paths, CenterlineErr};
+use doug_geometry::shapes::{Path, PathEndCap, PathLike, PolyLike, Shape};
+
+fn region(path: &Path) -> Region {
+    Region::from_poly(&path.as_poly().unwrap()).unwrap()
+}
+
+#[test]
+fn wires_with_half_width_caps_are_recovered() {
+    for width in [10, 11] {
+        let wire = path(
+            &[(0, 0), (100, 0), (100, 100), (300, 100)],
+            width,
+            4,
+            PathEndCap::HalfWidth,
+        );
+        let recovered = poly_to_path(&wire.as_poly().unwrap()).unwrap();
+        // The path may come back reversed.
+        let mut reversed = wire.clone();
+        reversed.points.reverse();
+        assert!(recovered == wire || recovered == reversed, "{recovered:?}");
+    }
+}
+
+#[test]
+fn rects_become_paths_along_their_longer_side() {
+    assert_eq!(
+        rect(0, 0, 100, 20, 4).to_path(),
+        Ok(path(&[(10, 10), (90, 10)], 20, 4, PathEndCap::HalfWidth))
+    );
+    assert_eq!(
+        rect(0, 0, 20, 100, 4).to_path(),
+        Ok(path(&[(10, 10), (10, 90)], 20, 4, PathEndCap::HalfWidth))
+    );
+
+    // A square has no room for half width caps.
+    let square = rect(0, 0, 20, 20, 4).to_path().unwrap();
+    assert_eq!(square.end_cap, PathEndCap::Flush);
+    assert_eq!(square.width, 20);
+    assert_eq!(square.layer, 4);
+    assert_eq!(region(&square), Region::from_rect(&rect(0, 0, 20, 20, 4)));
+}
+
+#[test]
+fn long_meanders_are_recovered() {
+    let mut p = vec![(0, 0)];
+    for ix in 0..200 {
+        let (x, y) = (ix * 30, if ix % 2 == 0 { 500 } else { 0 });
+        p.extend([(x, y), (x + 30, y)]);
+    }
+    let wire = path(&p, 10, 4, PathEndCap::Flush);
+    let recovered = poly_to_path(&wire.as_poly().unwrap()).unwrap();
+    assert_eq!(recovered.width, 10);
+    assert_eq!(recovered.points.len(), wire.points.len());
+    assert_eq!(region(&recovered), region(&wire));
+}
+
+#[test]
+fn random_paths_are_recovered() {
+    // A small linear congruential generator, so that failures are reproducible.
+    let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+    let mut range = |n: i32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n as u64) as i32
+    };
+    let mut recovered = 0;
+
+    for _ in 0..500 {
+        let mut p = (0, 0);
+        let mut p_points = vec![p];
+        let mut horizontal = range(2) == 0;
+        for _ in 0..range(5) + 1 {
+            let length = (range(60) + 20) * if range(2) == 0 { 1 } else { -1 };
+            p = if horizontal {
+                (p.0 + length, p.1)
+            } else {
+                (p.0, p.1 + length)
+            };
+            p_points.push(p);
+            horizontal = !horizontal;
+        }
+        let end_cap = [PathEndCap::Flush, PathEndCap::HalfWidth][range(2) as usize];
+        let wire = path(&p_points, range(15) as u32 + 1, 4, end_cap);
+        let Ok(poly) = wire.as_poly() else {
+            continue;
+        };
+        if !poly.is_simple() {
+            continue;
+        }
+
+        let path = poly_to_path(&poly).unwrap_or_else(|e| panic!("{e} {wire:?}"));
+        assert!(path.width <= wire.width, "{wire:?} {path:?}");
+        assert_eq!(region(&path), region(&wire), "{wire:?} {path:?}");
+        recovered += 1;
+    }
+    assert!(recovered > 200);
+}
+
+#[test]
+fn shapes_which_are_not_paths_are_reported() {
+    let tee = poly(
+        &[
+            (0, 0),
+            (30, 0),
+            (30, 10),
+            (20, 10),
+            (20, 40),
+            (10, 40),
+            (10, 10),
+            (0, 10),
+        ],
+        4,
+    );
+    assert_eq!(tee.to_path(), Err(CenterlineErr::NotPathLike));
+
+    // An L whose arms have different widths.
+    let uneven = poly(
+        &[(0, 0), (100, 0), (100, 10), (20, 10), (20, 100), (0, 100)],
+        4,
+    );
+    assert_eq!(uneven.to_path(), Err(CenterlineErr::NotPathLike));
+
+    let slanted = poly(&[(0, 0), (10, 0), (10, 10), (0, 10), (5, 5)], 4);
+    assert_eq!(slanted.to_path(), Err(CenterlineErr::NotRectilinear));
+
+    let degenerate = poly(&[(0, 0), (10, 0), (10, 0), (20, 0)], 4);
+    assert_eq!(degenerate.to_path(), Err(CenterlineErr::NotEnoughPoints));
+}
+
+#[test]
+fn only_polygons_are_replaced_by_paths() {
+    let wire = path(
+        &[(0, 0), (100, 0), (100, 100)],
+        10,
+        4,
+        PathEndCap::HalfWidth,
+    );
+    let tee = Shape::Poly(poly(
+        &[
+            (0, 0),
+            (30, 0),
+            (30, 10),
+            (20, 10),
+            (20, 40),
+            (10, 40),
+            (10, 10),
+            (0, 10),
+        ],
+        4,
+    ));
+    let shapes = vec![
+        Shape::Rect(rect(0, 0, 100, 20, 4)),
+        Shape::Poly(wire.as_poly().unwrap()),
+        tee.clone(),
+    ];
+    let recovered = recover_paths(&shapes);
+    assert_eq!(recovered[0], shapes[0]);
+    let Shape::Path(path) = &recovered[1] else {
+        panic!("{:?}", recovered[1]);
+    };
+    assert_eq!(region(path), region(&wire));
+    assert_eq!(recovered[2], tee);
+}
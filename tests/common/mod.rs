@@ -0,0 +1,41 @@
+//! Fixtures shared by the integration tests. Each test binary uses only some
+//! of them.
+#![allow(dead_code)]
+
+use doug_geometry::bbox::{BoundingBox, UnvalidatedBoundingBox};
+use doug_geometry::shapes::{Path, PathEndCap, Point, PointLike, Poly, Rect};
+
+pub fn points(points: &[(i32, i32)]) -> Vec<Point> {
+    points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+}
+
+pub fn rect(x0: i32, y0: i32, x1: i32, y1: i32, layer: u8) -> Rect {
+    Rect {
+        p0: Point::new(x0, y0),
+        p1: Point::new(x1, y1),
+        layer,
+    }
+}
+
+pub fn poly(p: &[(i32, i32)], layer: u8) -> Poly {
+    Poly {
+        points: points(p),
+        layer,
+    }
+}
+
+pub fn path(p: &[(i32, i32)], width: u32, layer: u8, end_cap: PathEndCap) -> Path {
+    Path {
+        points: points(p),
+        width,
+        layer,
+        end_cap,
+    }
+}
+
+pub fn bbox(x0: i32, y0: i32, x1: i32, y1: i32) -> BoundingBox {
+    BoundingBox::new(UnvalidatedBoundingBox {
+        min: Point::new(x0, y0),
+        max: Point::new(x1, y1),
+    })
+}
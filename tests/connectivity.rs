@@ -0,0 +1,162 @@
+mod common;
+
+use common::{path, poly, rect};
+use doug_geometry::connectivity::{extract_nets, ConnectivityErr, Label, Net, ViaLayer};
+use doug_geometry::shapes::{PathEndCap, PathErr, Point, PointLike, Shape, VarWidthPath};
+
+fn label(text: &str, x: i32, y: i32, layer: u8) -> Label {
+    Label {
+        text: text.to_string(),
+        position: Point::new(x, y),
+        layer,
+    }
+}
+
+/// The shapes of each net, without labels.
+fn groups(nets: &[Net]) -> Vec<Vec<usize>> {
+    nets.iter().map(|n| n.shapes.clone()).collect()
+}
+
+#[test]
+fn shapes_on_a_layer_join_where_they_touch() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(20, 0, 30, 10, 1)),
+        // Overlaps the first.
+        Shape::Rect(rect(5, 5, 15, 15, 1)),
+        // Touches the second at a corner.
+        Shape::Rect(rect(30, 10, 40, 20, 1)),
+        // Over the first, but on another layer.
+        Shape::Rect(rect(0, 0, 10, 10, 2)),
+    ];
+    let nets = extract_nets(&shapes, &[], &[]).unwrap();
+    assert_eq!(groups(&nets), vec![vec![0, 2], vec![1, 3], vec![4]]);
+}
+
+#[test]
+fn polygons_join_only_where_they_meet() {
+    let l_shape = Shape::Poly(poly(
+        &[(0, 0), (30, 0), (30, 10), (10, 10), (10, 30), (0, 30)],
+        1,
+    ));
+    let shapes = [
+        l_shape,
+        // Inside the bounding box of the L, but clear of it.
+        Shape::Rect(rect(15, 15, 25, 25, 1)),
+        // Inside the L, without touching its boundary.
+        Shape::Rect(rect(2, 2, 4, 4, 1)),
+        // A diagonal path leading away from the end of one arm of the L.
+        Shape::Path(path(&[(25, 5), (45, 25)], 2, 1, PathEndCap::Flush)),
+    ];
+    let nets = extract_nets(&shapes, &[], &[]).unwrap();
+    assert_eq!(groups(&nets), vec![vec![0, 2, 3], vec![1]]);
+
+    let without_path = extract_nets(&shapes[..3], &[], &[]).unwrap();
+    assert_eq!(groups(&without_path), vec![vec![0, 2], vec![1]]);
+}
+
+#[test]
+fn vias_join_the_layers_they_touch() {
+    let via = ViaLayer {
+        cut: 5,
+        bottom: 4,
+        top: 6,
+    };
+    let shapes = [
+        Shape::Rect(rect(0, 0, 100, 10, 4)),
+        Shape::Rect(rect(90, 0, 100, 100, 6)),
+        Shape::Rect(rect(92, 2, 98, 8, 5)),
+        // A cut over the bottom layer only.
+        Shape::Rect(rect(2, 2, 8, 8, 5)),
+        Shape::Rect(rect(0, 50, 10, 60, 6)),
+        // Shapes on the bottom and top layers which overlap without a cut.
+        Shape::Rect(rect(0, 50, 10, 60, 4)),
+    ];
+    let nets = extract_nets(&shapes, &[via], &[]).unwrap();
+    assert_eq!(groups(&nets), vec![vec![0, 1, 2, 3], vec![4], vec![5]]);
+
+    // Without the via layer, cuts are shapes like any other.
+    let nets = extract_nets(&shapes, &[], &[]).unwrap();
+    assert_eq!(nets.len(), 6);
+}
+
+#[test]
+fn labels_name_the_nets_under_them() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(10, 0, 20, 10, 1)),
+        Shape::Rect(rect(50, 0, 60, 10, 1)),
+    ];
+    let labels = [
+        label("vdd", 5, 5, 1),
+        // On the edge of the second rect, which counts as covering it.
+        label("vdd", 20, 5, 1),
+        label("out", 55, 10, 1),
+        label("gnd", 55, 5, 1),
+        // Over nothing on its layer.
+        label("nc", 5, 5, 2),
+        label("nc", 30, 5, 1),
+    ];
+    let nets = extract_nets(&shapes, &[], &labels).unwrap();
+    assert_eq!(nets[0].labels, vec!["vdd".to_string()]);
+    // Two names on one net is a short.
+    assert_eq!(nets[1].labels, vec!["gnd".to_string(), "out".to_string()]);
+}
+
+#[test]
+fn self_crossing_paths_are_covered_by_their_segments() {
+    // The path crosses itself, so it has no outline, but each of its
+    // segments still connects whatever it touches.
+    let crossing = Shape::Path(path(
+        &[(0, 0), (100, 100), (100, 0), (0, 100)],
+        10,
+        1,
+        PathEndCap::Flush,
+    ));
+    let shapes = [
+        crossing,
+        Shape::Rect(rect(95, 40, 105, 60, 1)),
+        Shape::Rect(rect(-5, 95, 5, 105, 1)),
+    ];
+    let nets = extract_nets(&shapes, &[], &[]).unwrap();
+    assert_eq!(groups(&nets), vec![vec![0, 1, 2]]);
+
+    // A rectilinear loop encloses a hole, so it is covered by its rects.
+    let looped = Shape::Path(path(
+        &[(0, 0), (100, 0), (100, 100), (0, 100), (0, 0)],
+        10,
+        1,
+        PathEndCap::Flush,
+    ));
+    let shapes = [
+        looped,
+        Shape::Rect(rect(45, 45, 55, 55, 1)),
+        Shape::Rect(rect(0, 45, 10, 55, 1)),
+    ];
+    let nets = extract_nets(&shapes, &[], &[]).unwrap();
+    assert_eq!(groups(&nets), vec![vec![0, 2], vec![1]]);
+}
+
+#[test]
+fn malformed_paths_are_reported() {
+    let steep = Shape::Path(path(&[(0, 0), (10, 30)], 4, 1, PathEndCap::Flush));
+    let shapes = [Shape::Rect(rect(0, 0, 10, 10, 1)), steep];
+    assert_eq!(
+        extract_nets(&shapes, &[], &[]),
+        Err(ConnectivityErr::MalformedPath(1, PathErr::NotOctilinear))
+    );
+
+    let missing_width = Shape::VarWidthPath(VarWidthPath {
+        points: vec![Point::new(0, 0), Point::new(10, 0)],
+        widths: vec![],
+        layer: 1,
+        end_cap: PathEndCap::Flush,
+    });
+    assert_eq!(
+        extract_nets(&[missing_width], &[], &[]),
+        Err(ConnectivityErr::MalformedPath(
+            0,
+            PathErr::WrongNumberOfWidths
+        ))
+    );
+}
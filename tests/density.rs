@@ -0,0 +1,151 @@
+mod common;
+
+use common::{bbox, poly, rect};
+use doug_geometry::density::{density_map, DensityStats, Grid};
+use doug_geometry::shapes::{
+    Path, PathEndCap, PathLike, Point, PointLike, PolyLike, Shape, Shapes,
+};
+
+/// A row of `columns` windows, `size` wide and high, from the origin.
+fn row(columns: u32, size: i32) -> Grid {
+    Grid {
+        origin: Point::new(0, 0),
+        window_width: size,
+        window_height: size,
+        step_x: size,
+        step_y: size,
+        columns,
+        rows: 1,
+    }
+}
+
+#[test]
+fn grids_cover_the_bounding_box() {
+    let grid = Grid::covering(&bbox(-10, 0, 95, 40), 50, 25);
+    // The last column starts at 65 and sticks out past 95.
+    assert_eq!((grid.columns, grid.rows), (4, 1));
+    assert_eq!(grid.window(3, 0), rect(65, 0, 115, 50, 0));
+
+    let (min, max) = (i32::MIN, i32::MAX);
+    let everywhere = rect(min, min, max, max, 1);
+    let grid = Grid::covering(&bbox(min, min, max, max), 1 << 30, 1 << 30);
+    assert_eq!((grid.columns, grid.rows), (4, 4));
+    // The last windows are cut off at the end of the coordinate range.
+    assert_eq!(
+        grid.window(3, 0),
+        rect(1 << 30, min, max, min + (1 << 30), 0)
+    );
+    let map = density_map(&[Shape::Rect(everywhere)], 1, &grid);
+    assert_eq!(map.densities[0][0], 1.0);
+    let short = (1i64 << 30) - 1;
+    assert_eq!(
+        map.densities[3][3],
+        (short * short) as f64 / (1u64 << 60) as f64
+    );
+}
+
+#[test]
+fn overlapping_shapes_are_counted_once() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(5, 0, 15, 10, 1)),
+        // On another layer.
+        Shape::Rect(rect(10, 0, 20, 20, 2)),
+    ];
+    let map = density_map(&shapes, 1, &row(2, 20));
+    assert_eq!(map.densities, vec![vec![0.375, 0.0]]);
+    assert_eq!(map.unmeasured, Vec::<usize>::new());
+
+    // Overlapping windows each count the shapes within them.
+    let grid = Grid {
+        step_x: 10,
+        ..row(2, 20)
+    };
+    let map = density_map(&shapes, 1, &grid);
+    assert_eq!(map.densities, vec![vec![0.375, 0.125]]);
+}
+
+#[test]
+fn diagonal_shapes_are_measured_exactly() {
+    let triangle = Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 1));
+    let map = density_map(std::slice::from_ref(&triangle), 1, &row(1, 10));
+    assert_eq!(map.densities, vec![vec![0.5]]);
+
+    // A diamond split down the middle by two windows.
+    let diamond = Shape::Poly(poly(&[(10, 0), (15, 5), (10, 10), (5, 5)], 1));
+    let map = density_map(&[diamond], 1, &row(2, 10));
+    assert_eq!(map.densities, vec![vec![0.25, 0.25]]);
+
+    // The parts covered by both rectilinear and diagonal shapes, or by two
+    // diagonal shapes, are counted once.
+    let shapes = [
+        Shape::Rect(rect(0, 0, 5, 10, 1)),
+        triangle.clone(),
+        triangle,
+    ];
+    let map = density_map(&shapes, 1, &row(1, 10));
+    assert_eq!(map.densities, vec![vec![0.625]]);
+
+    // Two diamonds crossing each other, which overlap in a square of area 2
+    // whose corners lie on half units.
+    let shapes = [
+        Shape::Poly(poly(&[(2, 0), (4, 2), (2, 4), (0, 2)], 1)),
+        Shape::Poly(poly(&[(4, 0), (6, 2), (4, 4), (2, 2)], 1)),
+    ];
+    let map = density_map(&shapes, 1, &row(1, 8));
+    assert_eq!(map.densities, vec![vec![(8.0 + 8.0 - 2.0) / 64.0]]);
+}
+
+#[test]
+fn diagonal_paths_cover_their_outlines() {
+    let path = Path {
+        points: vec![Point::new(5, 5), Point::new(45, 45), Point::new(85, 45)],
+        width: 6,
+        layer: 1,
+        end_cap: PathEndCap::HalfWidth,
+    };
+    let outline = path.as_poly().unwrap();
+    let map = density_map(&[Shape::Path(path)], 1, &row(1, 100));
+    assert_eq!(
+        map.densities[0][0] * 100.0 * 100.0,
+        outline.area_doubled() as f64 / 2.0
+    );
+}
+
+#[test]
+fn shapes_with_other_angles_are_reported() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(10, 0, 20, 10, 2)),
+        Shape::Poly(poly(&[(10, 0), (20, 0), (10, 3)], 1)),
+    ];
+    let map = density_map(&shapes, 1, &row(2, 10));
+    assert_eq!(map.densities, vec![vec![1.0, 0.0]]);
+    assert_eq!(map.unmeasured, vec![2]);
+}
+
+#[test]
+fn stats_summarize_the_windows() {
+    let shapes = Shapes {
+        bbox: bbox(0, 0, 30, 10),
+        shapes: vec![
+            Shape::Rect(rect(0, 0, 10, 10, 1)),
+            Shape::Rect(rect(20, 0, 25, 10, 1)),
+        ],
+        units: Default::default(),
+    };
+    let map = shapes.density(1, 10, 10);
+    assert_eq!(map.densities, vec![vec![1.0, 0.0, 0.5]]);
+    assert_eq!(
+        map.stats(),
+        Some(DensityStats {
+            min: 0.0,
+            max: 1.0,
+            mean: 0.5,
+            max_gradient: 1.0,
+        })
+    );
+
+    let empty = density_map(&[], 1, &row(0, 10));
+    assert_eq!(empty.stats(), None);
+}
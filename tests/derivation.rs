@@ -0,0 +1,184 @@
+mod common;
+
+use common::{path, poly, rect};
+use doug_geometry::boolean::{Region, RegionErr};
+use doug_geometry::derivation::{LayerExpr, RuleDeck};
+use doug_geometry::shapes::{PathEndCap, Shape, ShapeLike};
+
+fn region(rects: &[(i32, i32, i32, i32)]) -> Region {
+    let rects: Vec<_> = rects
+        .iter()
+        .map(|&(x0, y0, x1, y1)| rect(x0, y0, x1, y1, 0))
+        .collect();
+    Region::from_rects(rects.iter())
+}
+
+/// A 10 by 10 square on layer 1, and another on layer 2 moved up and right by
+/// 5.
+fn overlapping() -> Vec<Shape> {
+    vec![
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(5, 5, 15, 15, 2)),
+    ]
+}
+
+#[test]
+fn booleans_combine_layers() {
+    let (a, b) = (LayerExpr::layer(1), LayerExpr::layer(2));
+    let deck = RuleDeck::new()
+        .rule(10, a.clone().and(b.clone()))
+        .rule(11, a.clone().or(b.clone()))
+        .rule(12, a.clone().not(b.clone()))
+        .rule(13, a.xor(b));
+    let layers = deck.evaluate(&overlapping()).unwrap();
+
+    assert_eq!(layers.len(), 4);
+    assert_eq!(layers[&10], region(&[(5, 5, 10, 10)]));
+    assert_eq!(layers[&11], region(&[(0, 0, 10, 10), (5, 5, 15, 15)]));
+    assert_eq!(layers[&12], region(&[(0, 0, 10, 5), (0, 5, 5, 10)]));
+    assert_eq!(
+        layers[&13],
+        region(&[
+            (0, 0, 10, 5),
+            (0, 5, 5, 10),
+            (10, 5, 15, 15),
+            (5, 10, 10, 15)
+        ])
+    );
+    assert_eq!(layers[&13].area(), 150);
+}
+
+#[test]
+fn layers_without_shapes_are_empty() {
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).and(LayerExpr::layer(7)))
+        .rule(11, LayerExpr::layer(1).or(LayerExpr::layer(7)));
+    let layers = deck.evaluate(&overlapping()).unwrap();
+    assert!(layers[&10].is_empty());
+    assert_eq!(layers[&11], region(&[(0, 0, 10, 10)]));
+}
+
+#[test]
+fn sizing_grows_and_shrinks_layers() {
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).size(2, 3))
+        .rule(11, LayerExpr::layer(1).size(-2, -3))
+        .rule(12, LayerExpr::layer(1).size(-5, 0))
+        // Grown enough to meet, the two squares merge.
+        .rule(13, LayerExpr::layer(1).or(LayerExpr::layer(2)).size(1, 1));
+    let layers = deck.evaluate(&overlapping()).unwrap();
+
+    assert_eq!(layers[&10], region(&[(-2, -3, 12, 13)]));
+    assert_eq!(layers[&11], region(&[(2, 3, 8, 7)]));
+    assert!(layers[&12].is_empty());
+    assert_eq!(layers[&13].components().len(), 1);
+}
+
+#[test]
+fn area_filters_keep_polygons_within_bounds() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(20, 0, 40, 10, 1)),
+        Shape::Rect(rect(50, 0, 90, 10, 1)),
+        // Touches the first, so they are measured together.
+        Shape::Rect(rect(10, 0, 15, 10, 1)),
+    ];
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).with_area(150, 200))
+        .rule(11, LayerExpr::layer(1).with_area(0, 149))
+        .rule(12, LayerExpr::layer(1).with_area(400, 400));
+    let layers = deck.evaluate(&shapes).unwrap();
+
+    assert_eq!(layers[&10], region(&[(0, 0, 15, 10), (20, 0, 40, 10)]));
+    assert!(layers[&11].is_empty());
+    // The bounds are inclusive.
+    assert_eq!(layers[&12], region(&[(50, 0, 90, 10)]));
+}
+
+#[test]
+fn width_filters_measure_the_narrowest_part() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 100, 4, 1)),
+        Shape::Rect(rect(0, 20, 100, 30, 1)),
+        // An L whose arms are 6 and 20 wide.
+        Shape::Poly(poly(
+            &[
+                (200, 0),
+                (300, 0),
+                (300, 6),
+                (220, 6),
+                (220, 100),
+                (200, 100),
+            ],
+            1,
+        )),
+    ];
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).with_width(0, 5))
+        .rule(11, LayerExpr::layer(1).with_width(6, 10))
+        .rule(12, LayerExpr::layer(1).with_width(11, 1000));
+    let layers = deck.evaluate(&shapes).unwrap();
+
+    assert_eq!(layers[&10], region(&[(0, 0, 100, 4)]));
+    assert_eq!(
+        layers[&11],
+        region(&[(0, 20, 100, 30), (200, 0, 300, 6), (200, 6, 220, 100)])
+    );
+    assert!(layers[&12].is_empty());
+}
+
+#[test]
+fn rules_read_layers_derived_before_them() {
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).and(LayerExpr::layer(2)))
+        .rule(11, LayerExpr::layer(10).size(1, 1))
+        // Replaces the input layer 2 for the rules after it.
+        .rule(2, LayerExpr::layer(2).not(LayerExpr::layer(11)))
+        .rule(12, LayerExpr::layer(2).and(LayerExpr::layer(11)));
+    let layers = deck.evaluate(&overlapping()).unwrap();
+
+    assert_eq!(layers[&11], region(&[(4, 4, 11, 11)]));
+    assert_eq!(layers[&2].area(), 100 - 36);
+    assert!(layers[&12].is_empty());
+}
+
+#[test]
+fn derived_layers_come_back_as_shapes() {
+    let deck = RuleDeck::new()
+        .rule(10, LayerExpr::layer(1).and(LayerExpr::layer(2)))
+        .rule(11, LayerExpr::layer(1).or(LayerExpr::layer(2)));
+    let shapes = deck.evaluate_shapes(&overlapping()).unwrap();
+
+    assert_eq!(shapes.len(), 2);
+    assert_eq!(shapes[0].layer(), 10);
+    assert_eq!(shapes[1].layer(), 11);
+    assert_eq!(
+        Region::from_shapes(&shapes[..1]).unwrap(),
+        region(&[(5, 5, 10, 10)])
+    );
+}
+
+#[test]
+fn layers_which_are_not_rectilinear_are_rejected() {
+    let mut shapes = overlapping();
+    shapes.push(Shape::Path(path(
+        &[(0, 0), (40, 40)],
+        4,
+        3,
+        PathEndCap::Flush,
+    )));
+    shapes.push(Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 4)));
+
+    for layer in [3, 4] {
+        let deck = RuleDeck::new().rule(10, LayerExpr::layer(1).or(LayerExpr::layer(layer)));
+        assert_eq!(deck.evaluate(&shapes), Err(RegionErr::NotRectilinear));
+        assert_eq!(
+            deck.evaluate_shapes(&shapes),
+            Err(RegionErr::NotRectilinear)
+        );
+    }
+
+    // Layers which no rule reads are not looked at.
+    let deck = RuleDeck::new().rule(10, LayerExpr::layer(1).and(LayerExpr::layer(2)));
+    assert!(deck.evaluate(&shapes).is_ok());
+}
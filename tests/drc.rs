@@ -0,0 +1,382 @@
+mod common;
+
+use common::{bbox, poly, rect};
+use doug_geometry::bbox::BoundingBox;
+use doug_geometry::boolean::{Region, RegionErr};
+use doug_geometry::drc::{Check, Marker, MarkerKind, RuleTable};
+use doug_geometry::shapes::{
+    Path, PathEndCap, PathErr, PathLike, Point, PointLike, Poly, Rect, Shape,
+};
+
+fn marker(rule: &str, kind: MarkerKind, bbox: BoundingBox, shapes: Vec<usize>) -> Marker {
+    Marker {
+        rule: rule.to_string(),
+        kind,
+        bbox,
+        shapes,
+    }
+}
+
+/// Decomposes polygons into the rects of their regions.
+fn region_rects(poly: &Poly) -> Result<Vec<Rect>, RegionErr> {
+    Ok(Region::from_poly(poly)?.rects(0))
+}
+
+/// Decomposes polygons into horizontal slices one unit high, the thinnest
+/// rects a decomposition could produce.
+fn unit_slices(poly: &Poly) -> Result<Vec<Rect>, RegionErr> {
+    Ok(region_rects(poly)?
+        .into_iter()
+        .flat_map(|r| {
+            (r.p0.y..r.p1.y).map(move |y| Rect {
+                p0: Point::new(r.p0.x, y),
+                p1: Point::new(r.p1.x, y + 1),
+                layer: r.layer,
+            })
+        })
+        .collect())
+}
+
+#[test]
+fn narrow_rects_break_the_width_rule() {
+    let rules = RuleTable::new().rule("M1.W", Check::Width { layer: 1, min: 10 });
+    let shapes = [
+        // A wide square, with a narrow wire leaving it.
+        Shape::Rect(rect(0, 0, 40, 40, 1)),
+        Shape::Rect(rect(40, 18, 80, 23, 1)),
+        // An L with arms exactly as wide as the rule.
+        Shape::Poly(poly(
+            &[
+                (100, 0),
+                (130, 0),
+                (130, 10),
+                (110, 10),
+                (110, 30),
+                (100, 30),
+            ],
+            1,
+        )),
+        // Narrow, but on another layer.
+        Shape::Rect(rect(0, 100, 100, 102, 2)),
+    ];
+    let expected = vec![marker(
+        "M1.W",
+        MarkerKind::Violation,
+        bbox(40, 18, 80, 23),
+        vec![1],
+    )];
+    assert_eq!(rules.check(&shapes, region_rects), expected);
+    // However thinly the polygons are sliced, only the narrow parts of them
+    // are reported.
+    assert_eq!(rules.check(&shapes, unit_slices), expected);
+}
+
+#[test]
+fn polygons_closer_than_the_spacing_are_reported() {
+    let rules = RuleTable::new().rule("M1.S", Check::Spacing { layer: 1, min: 10 });
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        // 5 to the right of the first.
+        Shape::Rect(rect(15, 0, 25, 10, 1)),
+        // Touches the second, so it is part of the same polygon.
+        Shape::Rect(rect(25, 0, 35, 10, 1)),
+        // Exactly 10 above the first.
+        Shape::Rect(rect(0, 20, 10, 30, 1)),
+    ];
+    assert_eq!(
+        rules.check(&shapes, region_rects),
+        vec![marker(
+            "M1.S",
+            MarkerKind::Violation,
+            bbox(5, 0, 20, 10),
+            vec![0, 1]
+        )]
+    );
+
+    // Diagonal neighbours are as far apart as the larger of their offsets.
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(14, 17, 24, 27, 1)),
+    ];
+    assert_eq!(rules.check(&shapes, region_rects).len(), 1);
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(14, 20, 24, 30, 1)),
+    ];
+    assert_eq!(rules.check(&shapes, region_rects), vec![]);
+}
+
+#[test]
+fn small_polygons_break_the_area_rule() {
+    let rules = RuleTable::new().rule("M1.A", Check::Area { layer: 1, min: 100 });
+    let shapes = [
+        Shape::Rect(rect(0, 0, 5, 10, 1)),
+        Shape::Rect(rect(5, 0, 10, 10, 1)),
+        Shape::Rect(rect(50, 0, 55, 10, 1)),
+    ];
+    assert_eq!(
+        rules.check(&shapes, region_rects),
+        vec![marker(
+            "M1.A",
+            MarkerKind::Violation,
+            bbox(50, 0, 55, 10),
+            vec![2]
+        )]
+    );
+}
+
+#[test]
+fn enclosures_need_room_on_every_side() {
+    let shapes = [
+        Shape::Rect(rect(5, 5, 25, 25, 1)),
+        Shape::Rect(rect(10, 10, 20, 20, 2)),
+    ];
+    let enclosure = |min| {
+        RuleTable::new().rule(
+            "V1.EN",
+            Check::Enclosure {
+                inner: 2,
+                outer: 1,
+                min,
+            },
+        )
+    };
+    assert_eq!(enclosure(5).check(&shapes, region_rects), vec![]);
+    assert_eq!(
+        enclosure(6).check(&shapes, region_rects),
+        vec![marker(
+            "V1.EN",
+            MarkerKind::Violation,
+            bbox(4, 4, 26, 26),
+            vec![0, 1]
+        )]
+    );
+
+    // Inner shapes with nothing around them are not enclosed at all.
+    let shapes = [Shape::Rect(rect(10, 10, 20, 20, 2))];
+    assert_eq!(
+        enclosure(0).check(&shapes, region_rects),
+        vec![marker(
+            "V1.EN",
+            MarkerKind::Violation,
+            bbox(10, 10, 20, 20),
+            vec![0]
+        )]
+    );
+}
+
+#[test]
+fn narrow_gaps_within_a_polygon_are_notches() {
+    let u_shape = |gap: i32| {
+        let (left, right) = (15 - gap / 2, 15 + gap - gap / 2);
+        Shape::Poly(poly(
+            &[
+                (0, 0),
+                (30, 0),
+                (30, 20),
+                (right, 20),
+                (right, 5),
+                (left, 5),
+                (left, 20),
+                (0, 20),
+            ],
+            1,
+        ))
+    };
+    let rules = RuleTable::new().rule("M1.N", Check::Notch { layer: 1, min: 5 });
+    assert_eq!(
+        rules.check(&[u_shape(4)], region_rects),
+        vec![marker(
+            "M1.N",
+            MarkerKind::Violation,
+            bbox(13, 5, 17, 20),
+            vec![0]
+        )]
+    );
+    assert_eq!(rules.check(&[u_shape(5)], region_rects), vec![]);
+
+    // The same gap between two polygons is a matter of spacing.
+    let shapes = [
+        Shape::Rect(rect(0, 0, 13, 20, 1)),
+        Shape::Rect(rect(17, 0, 30, 20, 1)),
+    ];
+    assert_eq!(rules.check(&shapes, region_rects), vec![]);
+}
+
+#[test]
+fn short_steps_in_outlines_are_jogs() {
+    let stepped = Shape::Poly(poly(
+        &[(0, 0), (20, 0), (20, 10), (22, 10), (22, 30), (0, 30)],
+        1,
+    ));
+    let jog = |min| RuleTable::new().rule("M1.J", Check::Jog { layer: 1, min });
+    assert_eq!(
+        jog(5).check(std::slice::from_ref(&stepped), region_rects),
+        vec![marker(
+            "M1.J",
+            MarkerKind::Violation,
+            bbox(20, 0, 22, 30),
+            vec![0]
+        )]
+    );
+    assert_eq!(jog(2).check(&[stepped], region_rects), vec![]);
+
+    // Corners which turn the same way twice are not steps.
+    let l_shape = Shape::Poly(poly(
+        &[(0, 0), (30, 0), (30, 2), (2, 2), (2, 30), (0, 30)],
+        1,
+    ));
+    assert_eq!(jog(5).check(&[l_shape], region_rects), vec![]);
+}
+
+#[test]
+fn shapes_which_cannot_be_checked_are_reported() {
+    let diagonal = Shape::Path(Path {
+        points: vec![Point::new(0, 0), Point::new(40, 40)],
+        width: 4,
+        layer: 1,
+        end_cap: PathEndCap::Flush,
+    });
+    // The end cap cuts back past the start of the path.
+    let folded = Shape::Path(Path {
+        points: vec![Point::new(100, 0), Point::new(110, 0)],
+        width: 4,
+        layer: 1,
+        end_cap: PathEndCap::Custom { begin: -6, end: -6 },
+    });
+    let shapes = [diagonal, Shape::Rect(rect(100, 100, 101, 101, 1)), folded];
+    let rules = RuleTable::new()
+        .rule("M1.A", Check::Area { layer: 1, min: 10 })
+        .rule("M2.A", Check::Area { layer: 2, min: 10 });
+    assert_eq!(
+        rules.check(&shapes, region_rects),
+        vec![
+            marker(
+                "M1.A",
+                MarkerKind::Violation,
+                bbox(100, 100, 101, 101),
+                vec![1]
+            ),
+            marker("M1.A", MarkerKind::Unchecked, bbox(-1, -1, 42, 42), vec![0]),
+            marker(
+                "M1.A",
+                MarkerKind::Unchecked,
+                bbox(104, -2, 106, 2),
+                vec![2]
+            ),
+        ]
+    );
+
+    // Polygons which cannot be decomposed cannot be measured.
+    let fails = |_: &Poly| Err::<Vec<Rect>, _>(());
+    let rules = RuleTable::new().rule("M1.W", Check::Width { layer: 1, min: 10 });
+    assert_eq!(
+        rules.check(
+            &[
+                Shape::Rect(rect(0, 0, 5, 5, 1)),
+                Shape::Rect(rect(5, 0, 10, 5, 1))
+            ],
+            fails
+        ),
+        vec![marker(
+            "M1.W",
+            MarkerKind::Unchecked,
+            bbox(0, 0, 10, 5),
+            vec![0, 1]
+        )]
+    );
+}
+
+#[test]
+fn unchecked_shapes_without_area_are_marked_where_they_lie() {
+    let folded = |width| {
+        Shape::Path(Path {
+            points: vec![Point::new(0, 0), Point::new(10, 0)],
+            width,
+            layer: 1,
+            end_cap: PathEndCap::Custom { begin: -11, end: 0 },
+        })
+    };
+    let rules = RuleTable::new().rule("M1.A", Check::Area { layer: 1, min: 10 });
+    assert_eq!(
+        rules.check(&[folded(4), folded(0)], region_rects),
+        vec![
+            marker("M1.A", MarkerKind::Unchecked, bbox(10, -2, 11, 2), vec![0]),
+            marker("M1.A", MarkerKind::Unchecked, bbox(10, 0, 11, 1), vec![1]),
+        ]
+    );
+}
+
+#[test]
+fn notches_too_close_to_the_ends_of_the_coordinate_range_are_unchecked() {
+    // A U with a gap of 4, starting at `x`.
+    let u_shape = |x: i32| {
+        Shape::Poly(poly(
+            &[
+                (x, 0),
+                (x + 30, 0),
+                (x + 30, 20),
+                (x + 17, 20),
+                (x + 17, 5),
+                (x + 13, 5),
+                (x + 13, 20),
+                (x, 20),
+            ],
+            1,
+        ))
+    };
+    let (min, max) = (i32::MIN, i32::MAX);
+    let shapes = [
+        u_shape(0),
+        u_shape(max - 30),
+        Shape::Rect(rect(min, 0, min + 10, 10, 1)),
+    ];
+    let rules = RuleTable::new().rule("M1.N", Check::Notch { layer: 1, min: 5 });
+    // Only the U at the origin can be checked at twice the scale.
+    assert_eq!(
+        rules.check(&shapes, region_rects),
+        vec![
+            marker("M1.N", MarkerKind::Violation, bbox(13, 5, 17, 20), vec![0]),
+            marker(
+                "M1.N",
+                MarkerKind::Unchecked,
+                bbox(min, 0, min + 10, 10),
+                vec![2]
+            ),
+            marker(
+                "M1.N",
+                MarkerKind::Unchecked,
+                bbox(max - 30, 0, max, 20),
+                vec![1]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn paths_are_rectilinear_only_if_their_rects_are_well_formed() {
+    let path = Path {
+        points: vec![Point::new(0, 0), Point::new(10, 0), Point::new(10, 10)],
+        width: 4,
+        layer: 1,
+        end_cap: PathEndCap::Custom {
+            begin: -10,
+            end: -10,
+        },
+    };
+    assert!(Shape::Path(path.clone()).is_rectilinear());
+
+    let cut_back = Path {
+        end_cap: PathEndCap::Custom { begin: -11, end: 0 },
+        ..path.clone()
+    };
+    assert_eq!(cut_back.segment_rects(), Err(PathErr::FoldsOver));
+    assert!(!Shape::Path(cut_back).is_rectilinear());
+
+    let diagonal = Path {
+        points: vec![Point::new(0, 0), Point::new(10, 10)],
+        ..path
+    };
+    assert!(!Shape::Path(diagonal).is_rectilinear());
+    assert!(Shape::Rect(rect(0, 0, 10, 10, 1)).is_rectilinear());
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::{path, rect};
+use doug_geometry::bbox::CalculateBoundingBox;
+use doug_geometry::boolean::Region;
+use doug_geometry::fill::{generate_fill, FillRule};
+use doug_geometry::shapes::{Path, PathEndCap, Rect, Shape, ShapeLike};
+use doug_geometry::units::Units;
+
+fn rule(layer: u8, target_density: f64, window: i32) -> FillRule {
+    FillRule {
+        layer,
+        size: 10,
+        spacing: 10,
+        target_density,
+        window,
+    }
+}
+
+/// The fill squares on `layer`, checking that they are squares of the rule's
+/// size inside `region`, and at least the rule's spacing from each other and
+/// from the existing shapes.
+fn checked_fill(fill: &[Shape], existing: &[Shape], region: &Region, rule: &FillRule) -> Vec<Rect> {
+    let squares: Vec<Rect> = fill
+        .iter()
+        .filter(|s| s.layer() == rule.layer)
+        .map(|s| match s {
+            Shape::Rect(r) => *r,
+            s => panic!("{s:?}"),
+        })
+        .collect();
+    let existing = Region::from_shapes(existing.iter().filter(|s| s.layer() == rule.layer))
+        .unwrap()
+        .size(rule.spacing, rule.spacing);
+    for (ix, square) in squares.iter().enumerate() {
+        assert_eq!(square.p1.x - square.p0.x, rule.size);
+        assert_eq!(square.p1.y - square.p0.y, rule.size);
+        let square_region = Region::from_rect(square);
+        assert!(square_region.difference(region).is_empty(), "{square:?}");
+        assert!(
+            square_region.intersection(&existing).is_empty(),
+            "{square:?}"
+        );
+        let grown = square_region.size(rule.spacing, rule.spacing);
+        for other in squares[ix + 1..].iter() {
+            assert!(
+                grown.intersection(&Region::from_rect(other)).is_empty(),
+                "{square:?} {other:?}"
+            );
+        }
+    }
+    squares
+}
+
+#[test]
+fn empty_layers_are_filled_up_to_the_target() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let rule = rule(1, 0.2, 100);
+    let fill = generate_fill(&[], Units::default(), &region, &[rule]).unwrap();
+    // 2000 units need 20 squares, spread over the 25 sites on the grid.
+    let squares = checked_fill(&fill.shapes, &[], &region, &rule);
+    assert_eq!(squares.len(), 20);
+    assert_eq!(fill.bbox, fill.shapes.bbox());
+
+    // More than the grid can hold gets every site.
+    let rule = FillRule {
+        target_density: 0.9,
+        ..rule
+    };
+    let fill = generate_fill(&[], Units::default(), &region, &[rule]).unwrap();
+    assert_eq!(checked_fill(&fill.shapes, &[], &region, &rule).len(), 25);
+}
+
+#[test]
+fn fill_keeps_away_from_existing_shapes() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let existing = [
+        Shape::Rect(rect(0, 0, 45, 100, 1)),
+        // On another layer, so it does not block the fill.
+        Shape::Rect(rect(50, 0, 100, 100, 2)),
+    ];
+    let rule = rule(1, 0.9, 100);
+    let fill = generate_fill(&existing, Units::default(), &region, &[rule]).unwrap();
+    let squares = checked_fill(&fill.shapes, &existing, &region, &rule);
+    // Only the columns from x = 60 on are far enough from the existing rect.
+    assert_eq!(squares.len(), 10);
+    assert!(squares.iter().all(|s| s.p0.x >= 60));
+}
+
+#[test]
+fn dense_windows_get_no_fill() {
+    let region = Region::from_rect(&rect(0, 0, 200, 100, 0));
+    let existing = [Shape::Rect(rect(0, 0, 60, 100, 1))];
+    let rule = rule(1, 0.5, 100);
+    let fill = generate_fill(&existing, Units::default(), &region, &[rule]).unwrap();
+    let squares = checked_fill(&fill.shapes, &existing, &region, &rule);
+    // The first window is already 60% covered, and fill squares do not
+    // straddle the windows.
+    assert_eq!(squares.len(), 25);
+    assert!(squares.iter().all(|s| s.p0.x >= 100));
+
+    // With only the first window, no fill is needed at all.
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    assert_eq!(
+        generate_fill(&existing, Units::default(), &region, &[rule]),
+        None
+    );
+}
+
+#[test]
+fn diagonal_shapes_block_their_bounding_boxes() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let diagonal = Shape::Path(path(&[(0, 0), (40, 40)], 2, 1, PathEndCap::Flush));
+    let rule = rule(1, 0.9, 100);
+    let fill = generate_fill(
+        std::slice::from_ref(&diagonal),
+        Units::default(),
+        &region,
+        &[rule],
+    )
+    .unwrap();
+    let blocked = Region::from_rect(&Rect {
+        p0: diagonal.bbox().min(),
+        p1: diagonal.bbox().max(),
+        layer: 1,
+    })
+    .size(rule.spacing, rule.spacing);
+    for shape in fill.shapes.iter() {
+        let Shape::Rect(square) = shape else {
+            panic!("{shape:?}");
+        };
+        assert!(Region::from_rect(square).intersection(&blocked).is_empty());
+    }
+    assert_eq!(fill.shapes.len(), 25 - 9);
+}
+
+#[test]
+fn shapes_without_area_block_nothing() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    // A malformed path, folded back on itself, with no width.
+    let folded = Shape::Path(Path {
+        end_cap: PathEndCap::Custom { begin: -60, end: 0 },
+        ..path(&[(20, 50), (70, 50)], 0, 1, PathEndCap::Flush)
+    });
+    let rule = rule(1, 0.9, 100);
+    let fill = generate_fill(&[folded], Units::default(), &region, &[rule]).unwrap();
+    assert_eq!(fill.shapes.len(), 25);
+}
+
+#[test]
+fn fill_is_in_the_units_of_the_layout() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let units = Units::new(2000, 5);
+    let fill = generate_fill(&[], units, &region, &[rule(1, 0.2, 100)]).unwrap();
+    assert_eq!(fill.units, units);
+}
+
+#[test]
+fn each_rule_fills_its_own_layer() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let existing = [Shape::Rect(rect(0, 0, 100, 50, 2))];
+    let rules = [rule(1, 0.1, 100), rule(2, 0.6, 100)];
+    let fill = generate_fill(&existing, Units::default(), &region, &rules).unwrap();
+    let first = checked_fill(&fill.shapes, &existing, &region, &rules[0]);
+    let second = checked_fill(&fill.shapes, &existing, &region, &rules[1]);
+    assert_eq!(first.len(), 10);
+    // 5000 is already covered, so 1000 more is needed.
+    assert_eq!(second.len(), 10);
+    assert_eq!(fill.shapes.len(), 20);
+}
+
+#[test]
+#[should_panic(expected = "Fill sizes and windows must be positive")]
+fn empty_fill_squares_are_rejected() {
+    let region = Region::from_rect(&rect(0, 0, 100, 100, 0));
+    let rule = FillRule {
+        size: 0,
+        ..rule(1, 0.5, 100)
+    };
+    generate_fill(&[], Units::default(), &region, &[rule]);
+}
@@ -0,0 +1,247 @@
+mod common;
+
+use common::{bbox, path, poly, rect};
+use doug_geometry::boolean::Region;
+use doug_geometry::library::{ArrayInstance, Cell, Instance, Library, LibraryErr};
+use doug_geometry::shapes::{PathEndCap, Point, PointLike, Shape, ShapeLike};
+use doug_geometry::transform::{Rotation, Transform};
+
+fn cell(name: &str, shapes: Vec<Shape>) -> Cell {
+    Cell {
+        name: name.to_string(),
+        shapes,
+        ..Cell::default()
+    }
+}
+
+fn instance(cell: &str, transform: Transform) -> Instance {
+    Instance {
+        cell: cell.to_string(),
+        transform,
+    }
+}
+
+fn array(
+    cell: &str,
+    columns: u32,
+    rows: u32,
+    column_pitch: Point,
+    row_pitch: Point,
+) -> ArrayInstance {
+    ArrayInstance {
+        cell: cell.to_string(),
+        transform: Transform::translate(Point::new(1000, 0)),
+        columns,
+        rows,
+        column_pitch,
+        row_pitch,
+    }
+}
+
+/// A leaf cell with one 10 by 20 rect, placed once rotated in `middle`, which
+/// is placed in `top` both directly and as a 3 by 2 array.
+fn library() -> Library {
+    let mut middle = cell("middle", vec![]);
+    middle.instances.push(instance(
+        "leaf",
+        Transform {
+            offset: Point::new(100, 0),
+            rotation: Rotation::R90,
+            mirror_x: false,
+            magnification: 1,
+        },
+    ));
+    let mut top = cell("top", vec![Shape::Rect(rect(-5, -5, 5, 5, 1))]);
+    top.instances
+        .push(instance("middle", Transform::translate(Point::new(0, 500))));
+    top.arrays.push(array(
+        "middle",
+        3,
+        2,
+        Point::new(200, 0),
+        Point::new(0, 300),
+    ));
+    Library {
+        cells: vec![
+            top,
+            middle,
+            cell("leaf", vec![Shape::Rect(rect(0, 0, 10, 20, 1))]),
+            cell("unused", vec![]),
+        ],
+        ..Library::default()
+    }
+}
+
+#[test]
+fn hierarchies_are_flattened_into_the_top_cell() {
+    let library = library();
+    let flat = library.flatten("top", None).unwrap();
+    assert_eq!(flat.shapes.len(), 1 + 1 + 6);
+    assert_eq!(flat.shapes[0], Shape::Rect(rect(-5, -5, 5, 5, 1)));
+    // The leaf is turned a quarter, then moved by the middle cell and the
+    // instance of it.
+    assert_eq!(flat.shapes[1], Shape::Rect(rect(80, 500, 100, 510, 1)));
+    // The array is laid out row by row.
+    let corners: Vec<Point> = flat.shapes[2..]
+        .iter()
+        .map(|s| match s {
+            Shape::Rect(r) => r.p0,
+            s => panic!("{s:?}"),
+        })
+        .collect();
+    assert_eq!(
+        corners,
+        vec![
+            Point::new(1080, 0),
+            Point::new(1280, 0),
+            Point::new(1480, 0),
+            Point::new(1080, 300),
+            Point::new(1280, 300),
+            Point::new(1480, 300),
+        ]
+    );
+    assert_eq!(flat.bbox.min(), Point::new(-5, -5));
+    assert_eq!(flat.bbox.max(), Point::new(1500, 510));
+}
+
+#[test]
+fn top_cells_are_those_not_placed_anywhere() {
+    let library = library();
+    let names: Vec<&str> = library
+        .top_cells()
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["top", "unused"]);
+
+    // The unused cell is empty, so it is left out.
+    let flattened = library.flatten_top_cells(None).unwrap();
+    assert_eq!(flattened.len(), 1);
+    assert_eq!(flattened[0].0, "top");
+    assert_eq!(library.flatten("unused", None), Err(LibraryErr::Empty));
+}
+
+#[test]
+fn broken_hierarchies_are_reported() {
+    let mut library = library();
+    library.cells[1].instances[0].cell = "missing".to_string();
+    assert_eq!(
+        library.flatten("top", None),
+        Err(LibraryErr::UnknownCell("missing".to_string()))
+    );
+
+    library.cells[1].instances[0].cell = "top".to_string();
+    assert_eq!(
+        library.flatten("top", None),
+        Err(LibraryErr::RecursiveCell("top".to_string()))
+    );
+}
+
+#[test]
+fn arrays_beyond_the_coordinate_range_are_reported() {
+    let mut library = library();
+    library.cells[0].arrays[0].column_pitch = Point::new(i32::MAX / 2, 0);
+    assert_eq!(
+        library.flatten("top", None),
+        Err(LibraryErr::Overflow("middle".to_string()))
+    );
+
+    // Each placement is checked on its own, so the first ones still fit.
+    let transforms: Vec<_> = library.cells[0].arrays[0].transforms().collect();
+    assert_eq!(transforms.len(), 6);
+    assert!(transforms[0].is_ok() && transforms[1].is_ok());
+    assert!(transforms[2].is_err());
+}
+
+#[test]
+fn shapes_are_clipped_to_the_window() {
+    let l_shape = poly(&[(0, 0), (30, 0), (30, 10), (10, 10), (10, 30), (0, 30)], 2);
+    let diagonal = path(&[(0, 0), (40, 40)], 4, 3, PathEndCap::Flush);
+    let library = Library {
+        cells: vec![cell(
+            "top",
+            vec![
+                Shape::Rect(rect(0, 0, 100, 10, 1)),
+                Shape::Rect(rect(200, 200, 300, 300, 1)),
+                Shape::Poly(l_shape.clone()),
+                Shape::Path(diagonal.clone()),
+                Shape::Rect(rect(2, 2, 4, 4, 1)),
+            ],
+        )],
+        ..Library::default()
+    };
+
+    let flat = library.flatten("top", Some(&bbox(0, 0, 20, 20))).unwrap();
+    // Rects are cut at the window, and those outside it are dropped.
+    assert_eq!(flat.shapes[0], Shape::Rect(rect(0, 0, 20, 10, 1)));
+    // The rectilinear polygon is cut into the shapes of what is left of it.
+    let clipped = Region::from_shapes(flat.shapes[1..flat.shapes.len() - 2].iter()).unwrap();
+    let expected = Region::from_poly(&l_shape)
+        .unwrap()
+        .intersection(&Region::from_rect(&rect(0, 0, 20, 20, 1)));
+    assert_eq!(clipped, expected);
+    assert!(flat.shapes[1..flat.shapes.len() - 2]
+        .iter()
+        .all(|s| s.layer() == 2));
+    // The diagonal path cannot be cut, so it is kept whole.
+    assert_eq!(flat.shapes[flat.shapes.len() - 2], Shape::Path(diagonal));
+    assert_eq!(
+        flat.shapes[flat.shapes.len() - 1],
+        Shape::Rect(rect(2, 2, 4, 4, 1))
+    );
+
+    assert_eq!(
+        library.flatten("top", Some(&bbox(500, 500, 600, 600))),
+        Err(LibraryErr::Empty)
+    );
+}
+
+#[test]
+fn shapes_without_area_are_left_out() {
+    let flat_rect = Shape::Rect(rect(0, 5, 10, 5, 1));
+    let empty_poly = Shape::Poly(poly(&[], 2));
+    let library = Library {
+        cells: vec![
+            cell(
+                "top",
+                vec![
+                    flat_rect.clone(),
+                    empty_poly.clone(),
+                    Shape::Rect(rect(0, 0, 10, 10, 1)),
+                ],
+            ),
+            cell("degenerate", vec![flat_rect, empty_poly]),
+        ],
+        ..Library::default()
+    };
+
+    let flat = library.flatten("top", Some(&bbox(0, 0, 5, 20))).unwrap();
+    assert_eq!(flat.shapes, vec![Shape::Rect(rect(0, 0, 5, 10, 1))]);
+
+    // Without a window they are kept, but cannot make up a bounding box alone.
+    assert_eq!(library.flatten("top", None).unwrap().shapes.len(), 3);
+    assert_eq!(library.flatten("degenerate", None), Err(LibraryErr::Empty));
+    assert_eq!(
+        library.flatten("degenerate", Some(&bbox(0, 0, 5, 20))),
+        Err(LibraryErr::Empty)
+    );
+}
+
+#[test]
+fn archived_libraries_place_the_same_cells() {
+    let library = library();
+    let bytes = rkyv::to_bytes::<_, 1024>(&library).unwrap();
+    let archived = unsafe { rkyv::archived_root::<Library>(&bytes[..]) };
+
+    let placements = archived.placements("top").unwrap();
+    let names: Vec<&str> = placements.iter().map(|(c, _)| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "top", "middle", "leaf", "middle", "leaf", "middle", "leaf", "middle", "leaf",
+            "middle", "leaf", "middle", "leaf", "middle", "leaf"
+        ]
+    );
+    assert_eq!(archived.cell("leaf").unwrap().shapes.len(), 1);
+    assert!(archived.cell("missing").is_none());
+}
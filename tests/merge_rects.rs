@@ -0,0 +1,125 @@
+mod common;
+
+use common::{poly, rect};
+use doug_geometry::boolean::{merge_rects, Region};
+use doug_geometry::shapes::{Point, Rect};
+
+/// Twice the signed area, positive for counter-clockwise points.
+fn signed_area_doubled(points: &[Point]) -> i64 {
+    let n = points.len();
+    (0..n)
+        .map(|ix| {
+            let (p0, p1) = (points[ix], points[(ix + 1) % n]);
+            p0.x as i64 * p1.y as i64 - p1.x as i64 * p0.y as i64
+        })
+        .sum()
+}
+
+fn has_collinear_points(points: &[Point]) -> bool {
+    let n = points.len();
+    (0..n).any(|ix| {
+        let (a, b, c) = (points[ix], points[(ix + 1) % n], points[(ix + 2) % n]);
+        (a.x == b.x && b.x == c.x) || (a.y == b.y && b.y == c.y)
+    })
+}
+
+#[test]
+fn overlapping_rects_merge_into_one_outline() {
+    let merged = merge_rects(&[
+        rect(0, 0, 10, 10, 1),
+        rect(5, 0, 20, 10, 1),
+        rect(0, 5, 10, 20, 1),
+    ]);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(
+        merged[0].outline,
+        poly(&[(0, 0), (20, 0), (20, 10), (10, 10), (10, 20), (0, 20)], 1)
+    );
+    assert!(merged[0].holes.is_empty());
+}
+
+#[test]
+fn ring_of_rects_has_a_hole() {
+    let merged = merge_rects(&[
+        rect(0, 0, 30, 10, 0),
+        rect(0, 20, 30, 30, 0),
+        rect(0, 10, 10, 20, 0),
+        rect(20, 10, 30, 20, 0),
+    ]);
+    assert_eq!(merged.len(), 1);
+    let outline = &merged[0].outline.points;
+    let hole = &merged[0].holes[0].points;
+    assert_eq!(outline.len(), 4);
+    assert_eq!(hole.len(), 4);
+    assert!(
+        signed_area_doubled(outline) > 0,
+        "outlines are counter-clockwise"
+    );
+    assert!(signed_area_doubled(hole) < 0, "holes are clockwise");
+    assert_eq!(
+        merged[0].holes[0],
+        poly(&[(10, 10), (10, 20), (20, 20), (20, 10)], 0)
+    );
+}
+
+#[test]
+fn layers_are_merged_separately() {
+    let merged = merge_rects(&[
+        rect(0, 0, 10, 10, 2),
+        rect(0, 0, 10, 10, 1),
+        rect(10, 0, 20, 10, 2),
+    ]);
+    assert_eq!(merged.len(), 2);
+    assert_eq!(
+        merged[0].outline,
+        poly(&[(0, 0), (10, 0), (10, 10), (0, 10)], 1)
+    );
+    assert_eq!(
+        merged[1].outline,
+        poly(&[(0, 0), (20, 0), (20, 10), (0, 10)], 2)
+    );
+}
+
+#[test]
+fn merging_random_rects_preserves_their_union() {
+    // A small linear congruential generator, so that failures are reproducible.
+    let mut state = 0x853c_49e6_748f_ea9b_u64;
+    let mut range = |n: i32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n as u64) as i32
+    };
+
+    for _ in 0..500 {
+        let rects: Vec<Rect> = (0..range(8) + 1)
+            .map(|_| {
+                let (x, y) = (range(20), range(20));
+                rect(x, y, x + range(10) + 1, y + range(10) + 1, 0)
+            })
+            .collect();
+        let union = Region::from_rects(rects.iter());
+
+        let merged = merge_rects(&rects);
+        let mut covered = Region::new();
+        for poly in merged.iter() {
+            assert!(signed_area_doubled(&poly.outline.points) > 0);
+            assert!(!has_collinear_points(&poly.outline.points), "{poly:?}");
+            for hole in poly.holes.iter() {
+                assert!(signed_area_doubled(&hole.points) < 0);
+                assert!(!has_collinear_points(&hole.points), "{poly:?}");
+            }
+            let region = poly.region().unwrap();
+            assert!(covered.intersection(&region).is_empty());
+            covered = covered.union(&region);
+        }
+        assert_eq!(covered, union, "{rects:?}");
+        assert_eq!(merged.len(), union.components().len());
+    }
+}
+
+#[test]
+fn no_rects_merge_to_nothing() {
+    assert!(merge_rects(&[]).is_empty());
+    assert!(merge_rects(&[rect(0, 0, 0, 10, 0)]).is_empty());
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::{path, points, poly};
+use doug_geometry::bbox::CalculateBoundingBox;
+use doug_geometry::boolean::{Region, RegionErr};
+use doug_geometry::shapes::{
+    OctDirection, PathEndCap, PathErr, PathLike, Point, PointLike, PolyLike, Shape,
+};
+use doug_geometry::Polygon;
+
+#[test]
+fn diagonal_turn_is_mitered() {
+    // The sides of the diagonal segment lie on x - y = 100 +- 10 * sqrt(2),
+    // rounded to the nearest whole number.
+    let path = path(&[(0, 0), (100, 0), (200, 100)], 20, 0, PathEndCap::Flush);
+    let poly = path.as_poly().unwrap();
+    assert_eq!(
+        poly.points,
+        points(&[
+            (0, -10),
+            (104, -10),
+            (207, 93),
+            (193, 107),
+            (96, 10),
+            (0, 10)
+        ])
+    );
+    assert!(poly.is_simple());
+}
+
+#[test]
+fn diagonal_ends_are_square_to_the_path() {
+    let flush = path(&[(0, 0), (100, 100)], 20, 0, PathEndCap::Flush)
+        .as_poly()
+        .unwrap();
+    assert_eq!(
+        flush.points,
+        points(&[(7, -7), (107, 93), (93, 107), (-7, 7)])
+    );
+
+    // Half width caps extend each end by 10 along the diagonal, which puts
+    // the corners 10 * sqrt(2) from the end points along each axis.
+    let extended = path(&[(0, 0), (100, 100)], 20, 0, PathEndCap::HalfWidth)
+        .as_poly()
+        .unwrap();
+    assert_eq!(
+        extended.points,
+        points(&[(0, -14), (114, 100), (100, 114), (-14, 0)])
+    );
+}
+
+#[test]
+fn octagonal_loop_of_diagonal_and_straight_segments() {
+    let path = path(
+        &[(0, 0), (100, 0), (150, 50), (150, 150), (100, 200)],
+        10,
+        0,
+        PathEndCap::Flush,
+    );
+    let poly = path.as_poly().unwrap();
+    assert!(poly.is_simple());
+    // Every edge of the outline is horizontal, vertical or diagonal.
+    let n = poly.points.len();
+    for ix in 0..n {
+        assert!(
+            poly.points[ix]
+                .oct_direction_to(&poly.points[(ix + 1) % n])
+                .is_some(),
+            "{poly:?}"
+        );
+    }
+}
+
+#[test]
+fn outlines_of_random_paths_are_octilinear() {
+    // A small linear congruential generator, so that failures are reproducible.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut range = |n: i32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n as u64) as i32
+    };
+    let mut converted = 0;
+
+    for _ in 0..2000 {
+        let mut p = (range(100), range(100));
+        let mut points = vec![p];
+        for _ in 0..range(4) + 1 {
+            let length = range(40) + 20;
+            let (dx, dy) = [
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ][range(8) as usize];
+            p = (p.0 + dx * length, p.1 + dy * length);
+            points.push(p);
+        }
+        let end_cap = [PathEndCap::Flush, PathEndCap::HalfWidth][range(2) as usize];
+        let path = path(&points, range(12) as u32 + 1, 0, end_cap);
+
+        let Ok(poly) = path.as_poly() else {
+            continue;
+        };
+        converted += 1;
+        assert!(poly.is_simple(), "{path:?}");
+        let n = poly.points.len();
+        for ix in 0..n {
+            assert!(
+                poly.points[ix]
+                    .oct_direction_to(&poly.points[(ix + 1) % n])
+                    .is_some(),
+                "{path:?} {poly:?}"
+            );
+        }
+    }
+    assert!(converted > 500);
+}
+
+#[test]
+fn folded_and_crossing_diagonal_paths_are_rejected() {
+    // The diagonal step is shorter than the joints at its ends.
+    let folded = path(
+        &[(0, 0), (100, 0), (102, 2), (102, 100)],
+        20,
+        0,
+        PathEndCap::Flush,
+    );
+    assert_eq!(folded.as_poly(), Err(PathErr::FoldsOver));
+
+    let crossing = path(
+        &[(0, 0), (100, 100), (100, 0), (0, 100)],
+        10,
+        0,
+        PathEndCap::Flush,
+    );
+    assert_eq!(crossing.as_poly(), Err(PathErr::SelfIntersects));
+
+    let steep = path(&[(0, 0), (10, 20)], 10, 0, PathEndCap::Flush);
+    assert_eq!(steep.as_poly(), Err(PathErr::NotOctilinear));
+}
+
+#[test]
+fn bounding_boxes_of_diagonal_paths() {
+    let path = path(&[(0, 0), (100, 0), (200, 100)], 20, 0, PathEndCap::Flush);
+    let bbox = Shape::Path(path).bbox();
+    assert_eq!(bbox.min(), Point::new(0, -10));
+    assert_eq!(bbox.max(), Point::new(207, 107));
+
+    let triangle = Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 0));
+    assert_eq!(triangle.bbox().max(), Point::new(10, 10));
+}
+
+#[test]
+fn regions_reject_diagonal_shapes() {
+    let triangle = poly(&[(0, 0), (10, 0), (0, 10)], 0);
+    assert_eq!(Region::from_poly(&triangle), Err(RegionErr::NotRectilinear));
+
+    let diagonal = path(&[(0, 0), (10, 10)], 2, 0, PathEndCap::Flush);
+    assert_eq!(diagonal.segment_rects(), Err(PathErr::NotRectilinear));
+
+    let square = Shape::Poly(poly(&[(20, 0), (30, 0), (30, 10), (20, 10)], 0));
+    let shapes = [Shape::Poly(triangle), square.clone(), Shape::Path(diagonal)];
+    assert_eq!(
+        Region::from_shapes(shapes.iter()),
+        Err(RegionErr::NotRectilinear)
+    );
+
+    let (region, left_out) = Region::from_rectilinear_shapes(shapes.iter());
+    assert_eq!(region, Region::from_shapes([&square]).unwrap());
+    assert_eq!(left_out, vec![0, 2]);
+}
+
+#[test]
+fn polygon_from_octilinear_points() {
+    let polygon = Polygon::from_points(points(&[(0, 0), (10, 0), (15, 5), (15, 15), (10, 20)]));
+    use OctDirection::*;
+    assert_eq!(polygon.directions, vec![Right, UpRight, Up, UpLeft]);
+    // Diagonal steps count as both horizontal and vertical moves, so the step
+    // up and to the left reverses the horizontal direction of travel.
+    assert_eq!(polygon.horizontal_inversions, 1);
+    assert_eq!(polygon.vertical_inversions, 0);
+    assert_eq!(polygon.points[0], Point::new(0, 0));
+}
+
+#[test]
+fn polygon_from_points_starting_leftwards_is_reversed() {
+    let polygon = Polygon::from_points(points(&[(10, 0), (0, 0), (0, 10), (10, 20)]));
+    assert_eq!(
+        polygon.points,
+        points(&[(10, 20), (0, 10), (0, 0), (10, 0)])
+    );
+    assert_eq!(polygon.directions.len(), 3);
+}
+
+#[test]
+#[should_panic(expected = "horizontal, vertical or at 45 degrees")]
+fn polygon_from_points_rejects_other_angles() {
+    Polygon::from_points(points(&[(0, 0), (10, 0), (20, 5), (0, 5)]));
+}
@@ -0,0 +1,320 @@
+mod common;
+
+use common::{path, points, rect};
+use doug_geometry::boolean::Region;
+use doug_geometry::shapes::{
+    is_simple_outline, Path, PathEndCap, PathErr, PathLike, Point, PointLike, PolyLike, Rect,
+};
+
+/// A small xorshift generator, so that failures are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        lo + (self.next() % (hi - lo) as u64) as i32
+    }
+}
+
+/// A random rectilinear path, which may contain repeated points, collinear
+/// runs and U-turns.
+fn random_path(rng: &mut Rng) -> Path {
+    let mut p = Point::new(rng.range(-20, 20), rng.range(-20, 20));
+    let mut points = vec![p];
+    for _ in 0..rng.range(1, 8) {
+        let length = rng.range(0, 12);
+        p = match rng.range(0, 4) {
+            0 => Point::new(p.x + length, p.y),
+            1 => Point::new(p.x - length, p.y),
+            2 => Point::new(p.x, p.y + length),
+            _ => Point::new(p.x, p.y - length),
+        };
+        points.push(p);
+    }
+    let end_cap = match rng.range(0, 3) {
+        0 => PathEndCap::Flush,
+        1 => PathEndCap::HalfWidth,
+        _ => PathEndCap::Custom {
+            begin: rng.range(0, 5),
+            end: rng.range(0, 5),
+        },
+    };
+    Path {
+        points,
+        width: rng.range(1, 9) as u32,
+        layer: 0,
+        end_cap,
+    }
+}
+
+/// The union of the rectangles swept out by each segment of the path. Where
+/// the path turns, segments are grown by half the width, so that the corner is
+/// filled in.
+fn union_of_segments(path: &Path) -> Region {
+    let (lo, hi) = (
+        (path.width / 2) as i32,
+        (path.width - path.width / 2) as i32,
+    );
+    let segments: Vec<(Point, Point)> = path
+        .points
+        .windows(2)
+        .map(|w| (w[0], w[1]))
+        .filter(|(p0, p1)| p0 != p1)
+        .collect();
+    let direction = |(p0, p1): (Point, Point)| ((p1.x - p0.x).signum(), (p1.y - p0.y).signum());
+    let last = segments.len() - 1;
+
+    let rects: Vec<Rect> = segments
+        .iter()
+        .enumerate()
+        .map(|(ix, &(p0, p1))| {
+            let (mut x0, mut x1) = (p0.x.min(p1.x) - lo, p0.x.max(p1.x) + hi);
+            let (mut y0, mut y1) = (p0.y.min(p1.y) - lo, p0.y.max(p1.y) + hi);
+
+            // How far the segment extends past each of its ends, if not by
+            // half the width.
+            let begin = match (ix, path.end_cap) {
+                (0, PathEndCap::Flush) => Some(0),
+                (0, PathEndCap::HalfWidth) => None,
+                (0, PathEndCap::Custom { begin, .. }) => Some(begin),
+                _ => (direction(segments[ix - 1]) == direction((p0, p1))).then_some(0),
+            };
+            let end = match (ix == last, path.end_cap) {
+                (true, PathEndCap::Flush) => Some(0),
+                (true, PathEndCap::HalfWidth) => None,
+                (true, PathEndCap::Custom { end, .. }) => Some(end),
+                _ => (direction(segments[ix + 1]) == direction((p0, p1))).then_some(0),
+            };
+
+            for (extension, p, other) in [(begin, p0, p1), (end, p1, p0)] {
+                let Some(extension) = extension else {
+                    continue;
+                };
+                match (p0.y == p1.y, p.x < other.x, p.y < other.y) {
+                    (true, true, _) => x0 = p.x - extension,
+                    (true, false, _) => x1 = p.x + extension,
+                    (false, _, true) => y0 = p.y - extension,
+                    (false, _, false) => y1 = p.y + extension,
+                }
+            }
+
+            rect(x0, y0, x1, y1, 0)
+        })
+        .collect();
+
+    Region::from_rects(rects.iter())
+}
+
+fn has_u_turn(path: &Path) -> bool {
+    let directions: Vec<(i32, i32)> = path
+        .points
+        .windows(2)
+        .map(|w| ((w[1].x - w[0].x).signum(), (w[1].y - w[0].y).signum()))
+        .filter(|&d| d != (0, 0))
+        .collect();
+    directions
+        .windows(2)
+        .any(|d| d[0].0 == -d[1].0 && d[0].1 == -d[1].1)
+}
+
+#[test]
+fn as_poly_covers_union_of_segments() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut checked = 0;
+
+    for _ in 0..5000 {
+        let path = random_path(&mut rng);
+        match path.as_poly() {
+            Ok(poly) => {
+                // Comparing regions alone would not catch outlines which
+                // overlap themselves, since regions count any point wound
+                // around as covered.
+                assert!(poly.is_simple(), "{path:?}");
+                let region = Region::from_poly(&poly).unwrap();
+                assert_eq!(poly.area_doubled(), 2 * region.area(), "{path:?}");
+                assert_eq!(region, union_of_segments(&path), "{path:?}");
+                checked += 1;
+            }
+            Err(PathErr::UTurn(_)) => assert!(has_u_turn(&path), "{path:?}"),
+            Err(PathErr::EnclosesHole) => assert!(
+                union_of_segments(&path)
+                    .polys(0)
+                    .iter()
+                    .any(|p| !p.holes.is_empty()),
+                "{path:?}"
+            ),
+            Err(PathErr::NotEnoughPoints) => {
+                assert!(path.points.iter().all(|&p| p == path.points[0]))
+            }
+            Err(e) => panic!("unexpected error {e:?} for {path:?}"),
+        }
+    }
+
+    assert!(checked > 1000);
+}
+
+#[test]
+fn as_poly_of_long_segments_covers_union_of_segments() {
+    let mut rng = Rng(0x5851_f42d_4c95_7f2d);
+    let (mut checked, mut holes) = (0, 0);
+
+    for _ in 0..5000 {
+        // Scaling up the points makes every segment longer than the width, so
+        // these paths are converted by offsetting their sides, unless they
+        // cross or run alongside themselves.
+        let mut path = random_path(&mut rng);
+        for p in path.points.iter_mut() {
+            *p = Point::new(p.x * 10, p.y * 10);
+        }
+        path.width *= 4;
+        match path.as_poly() {
+            Ok(poly) => {
+                assert!(poly.is_simple(), "{path:?}");
+                let region = Region::from_poly(&poly).unwrap();
+                assert_eq!(poly.area_doubled(), 2 * region.area(), "{path:?}");
+                assert_eq!(region, union_of_segments(&path), "{path:?}");
+                checked += 1;
+            }
+            Err(PathErr::EnclosesHole) => {
+                assert!(
+                    union_of_segments(&path)
+                        .polys(0)
+                        .iter()
+                        .any(|p| !p.holes.is_empty()),
+                    "{path:?}"
+                );
+                holes += 1;
+            }
+            Err(PathErr::UTurn(_)) => assert!(has_u_turn(&path), "{path:?}"),
+            Err(PathErr::NotEnoughPoints) => {
+                assert!(path.points.iter().all(|&p| p == path.points[0]))
+            }
+            Err(e) => panic!("unexpected error {e:?} for {path:?}"),
+        }
+    }
+
+    assert!(checked > 1000);
+    assert!(holes > 10);
+}
+
+#[test]
+fn long_loop_encloses_hole() {
+    let path = path(
+        &[(0, 0), (100, 0), (100, 100), (0, 100), (0, -20)],
+        10,
+        0,
+        PathEndCap::Flush,
+    );
+    assert_eq!(path.as_poly(), Err(PathErr::EnclosesHole));
+}
+
+#[test]
+fn path_alongside_itself_is_traced_from_its_union() {
+    // The two long segments run side by side with no gap between them.
+    let path = path(
+        &[(0, 0), (100, 0), (100, 20), (0, 20)],
+        20,
+        0,
+        PathEndCap::Flush,
+    );
+    let poly = path.as_poly().unwrap();
+    assert!(poly.is_simple());
+    assert_eq!(
+        Region::from_poly(&poly).unwrap(),
+        Region::from_rect(&rect(0, -10, 110, 30, 0))
+    );
+    assert_eq!(poly.points.len(), 4);
+}
+
+#[test]
+fn segment_rects_cover_union_of_segments() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..5000 {
+        let path = random_path(&mut rng);
+        if path.points.iter().all(|&p| p == path.points[0]) {
+            continue;
+        }
+        assert_eq!(
+            Region::from_rects(path.segment_rects().unwrap().iter()),
+            union_of_segments(&path),
+            "{path:?}"
+        );
+    }
+}
+
+#[test]
+fn normalize_removes_repeated_and_collinear_points() {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+
+    for _ in 0..5000 {
+        let path = random_path(&mut rng);
+        let Ok(normalized) = path.normalize() else {
+            continue;
+        };
+        assert_eq!(normalized.points.first(), path.points.first());
+        assert_eq!(normalized.points.last(), path.points.last());
+        for w in normalized.points.windows(3) {
+            assert_ne!(w[0], w[1]);
+            assert_ne!(w[0].directions_to(&w[1]), w[1].directions_to(&w[2]));
+        }
+    }
+}
+
+#[test]
+fn two_point_vertical_path() {
+    let path = path(&[(0, 0), (0, 10)], 4, 0, PathEndCap::Flush);
+    assert_eq!(
+        Region::from_poly(&path.as_poly().unwrap()).unwrap(),
+        Region::from_rect(&rect(-2, 0, 2, 10, 0))
+    );
+}
+
+#[test]
+fn simple_outlines() {
+    assert!(is_simple_outline(&points(&[(0, 0), (10, 0), (0, 10)])));
+    assert!(is_simple_outline(&points(&[
+        (0, 0),
+        (10, 0),
+        (10, 10),
+        (0, 10)
+    ])));
+    // A bow tie crosses itself.
+    assert!(!is_simple_outline(&points(&[
+        (0, 0),
+        (10, 10),
+        (10, 0),
+        (0, 10)
+    ])));
+    // Two squares touching at a corner.
+    assert!(!is_simple_outline(&points(&[
+        (0, 0),
+        (10, 0),
+        (10, 10),
+        (20, 10),
+        (20, 20),
+        (10, 20),
+        (10, 10),
+        (0, 10)
+    ])));
+    // Running back along the previous edge.
+    assert!(!is_simple_outline(&points(&[
+        (0, 0),
+        (10, 0),
+        (5, 0),
+        (5, 10)
+    ])));
+    assert!(!is_simple_outline(&points(&[(0, 0), (10, 0)])));
+    assert!(!is_simple_outline(&points(&[
+        (0, 0),
+        (0, 0),
+        (10, 0),
+        (0, 10)
+    ])));
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use common::{bbox, path, points, poly, rect};
+use doug_geometry::raster::{rasterize, rasterize_layers, Raster};
+use doug_geometry::shapes::{PathEndCap, Shape, VarWidthPath};
+
+/// A 2 by 2 raster whose top left pixel is covered, and whose bottom right
+/// pixel is half covered.
+fn corners() -> Raster {
+    let shapes = [
+        Shape::Rect(rect(0, 10, 10, 20, 1)),
+        Shape::Rect(rect(10, 0, 15, 10, 1)),
+    ];
+    rasterize(&shapes, 1, &bbox(0, 0, 20, 20), 10)
+}
+
+#[test]
+fn pixels_are_covered_in_part() {
+    let shapes = [
+        // Half of the bottom left pixel, and all of the bottom right one.
+        Shape::Rect(rect(5, 0, 20, 10, 1)),
+        // Half of the top left pixel, cut along its diagonal.
+        Shape::Poly(poly(&[(0, 10), (10, 10), (0, 20)], 1)),
+        // On another layer.
+        Shape::Rect(rect(10, 10, 20, 20, 2)),
+    ];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 20, 20), 10);
+    assert_eq!((raster.width, raster.height), (2, 2));
+    // The top row comes first.
+    assert_eq!(raster.coverage, vec![0.5, 0.0, 0.5, 1.0]);
+    assert_eq!(raster.index(1, 0), 1);
+    assert_eq!(raster.bitmap(0.5), vec![true, false, true, true]);
+    assert_eq!(raster.bitmap(0.75), vec![false, false, false, true]);
+    assert_eq!(raster.gray_levels(), vec![128, 0, 128, 255]);
+
+    let layers = rasterize_layers(&shapes, &bbox(0, 0, 20, 20), 10);
+    assert_eq!(layers.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(layers[&1], raster);
+    assert_eq!(layers[&2].coverage, vec![0.0, 1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn pixels_past_the_window_are_covered_only_inside_it() {
+    let shapes = [Shape::Rect(rect(-100, -100, 100, 100, 1))];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 15, 10), 10);
+    assert_eq!((raster.width, raster.height), (2, 1));
+    assert_eq!(raster.coverage, vec![1.0, 0.5]);
+
+    // Diagonal shapes are clipped to the window too. Rows start from the top,
+    // so the bottom row sticks out below the window.
+    let shapes = [Shape::Poly(poly(&[(0, 0), (15, 0), (0, 15)], 1))];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 15, 15), 10);
+    assert_eq!(raster.coverage, vec![0.5, 0.0, 0.5, 0.125]);
+}
+
+#[test]
+fn overlapping_diagonal_shapes_are_counted_once() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 1)),
+        Shape::Poly(poly(&[(0, 0), (10, 0), (10, 10)], 1)),
+    ];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 10, 10), 10);
+    assert_eq!(raster.coverage, vec![1.0]);
+
+    // Two triangles over the same half of the pixel, and a rect over part of
+    // it.
+    let shapes = [
+        Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 1)),
+        Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 1)),
+        Shape::Rect(rect(0, 0, 5, 5, 1)),
+    ];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 10, 10), 10);
+    assert_eq!(raster.coverage, vec![0.5]);
+    let shapes = [
+        Shape::Poly(poly(&[(0, 0), (10, 0), (0, 10)], 1)),
+        Shape::Rect(rect(5, 5, 10, 10, 1)),
+    ];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 10, 10), 10);
+    assert_eq!(raster.coverage, vec![0.75]);
+}
+
+#[test]
+fn shapes_which_cannot_be_measured_are_reported() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 2)),
+        Shape::Poly(poly(&[(0, 0), (10, 0), (0, 5)], 1)),
+        // Neither path has an outline: one takes a step at another angle, and
+        // the other has too few widths.
+        Shape::Path(path(&[(0, 0), (10, 5)], 2, 1, PathEndCap::Flush)),
+        Shape::VarWidthPath(VarWidthPath {
+            points: points(&[(0, 5), (10, 5), (10, 10)]),
+            widths: vec![2],
+            layer: 1,
+            end_cap: PathEndCap::Flush,
+        }),
+        Shape::Rect(rect(0, 0, 5, 10, 1)),
+    ];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 10, 10), 10);
+    assert_eq!(raster.unmeasured, vec![1, 2, 3]);
+    assert_eq!(raster.coverage, vec![0.5]);
+}
+
+#[test]
+fn pgm_images_hold_the_gray_levels() {
+    let mut pgm = Vec::new();
+    corners().write_pgm(&mut pgm).unwrap();
+    assert_eq!(pgm, b"P5\n2 2\n255\n\xff\x00\x00\x80");
+}
+
+/// The CRC-32 of PNG chunks, computed with a lookup table rather than bit by
+/// bit, as a check on the encoder.
+fn crc32(bytes: &[u8]) -> u32 {
+    let table: Vec<u32> = (0..256u32)
+        .map(|n| {
+            (0..8).fold(n, |c, _| {
+                if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                }
+            })
+        })
+        .collect();
+    !bytes.iter().fold(0xffff_ffffu32, |c, &b| {
+        table[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+/// The chunks of a PNG image, checking their CRCs.
+fn png_chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>, u32)> {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut chunks = Vec::new();
+    let mut rest = &png[8..];
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+        let data = rest[8..8 + length].to_vec();
+        let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+        assert_eq!(crc, crc32(&rest[4..8 + length]), "{kind:?}");
+        chunks.push((kind, data, crc));
+        rest = &rest[12 + length..];
+    }
+    chunks
+}
+
+/// The data in a zlib stream of stored deflate blocks, checking the blocks and
+/// the Adler-32 checksum, which is returned.
+fn inflate_stored(stream: &[u8]) -> (Vec<u8>, u32) {
+    assert_eq!(&stream[..2], [0x78, 0x01]);
+    assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+    let mut data = Vec::new();
+    let mut rest = &stream[2..];
+    loop {
+        let header = rest[0];
+        assert_eq!(header & 0b110, 0, "blocks are stored");
+        let length = u16::from_le_bytes([rest[1], rest[2]]);
+        assert_eq!(!length, u16::from_le_bytes([rest[3], rest[4]]));
+        data.extend(&rest[5..5 + length as usize]);
+        rest = &rest[5 + length as usize..];
+        if header & 1 == 1 {
+            break;
+        }
+    }
+    assert_eq!(rest.len(), 4);
+    let adler = u32::from_be_bytes(rest.try_into().unwrap());
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data.iter() {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    assert_eq!(adler, (b << 16) | a);
+    (data, adler)
+}
+
+/// The gray levels of a PNG image, row by row, with its width and height.
+fn decode_png(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let chunks = png_chunks(png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _, _)| kind).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+
+    let header = &chunks[0].1;
+    let width = u32::from_be_bytes(header[..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    assert_eq!(&header[8..], [8, 0, 0, 0, 0]);
+
+    let (scanlines, _) = inflate_stored(&chunks[1].1);
+    assert_eq!(scanlines.len(), (width as usize + 1) * height as usize);
+    let mut levels = Vec::new();
+    for row in scanlines.chunks(width as usize + 1) {
+        assert_eq!(row[0], 0, "rows are not filtered");
+        levels.extend(&row[1..]);
+    }
+    (width, height, levels)
+}
+
+#[test]
+fn png_images_decode_to_the_gray_levels() {
+    let mut png = Vec::new();
+    corners().write_png(&mut png).unwrap();
+    assert_eq!(decode_png(&png), (2, 2, vec![255, 0, 0, 128]));
+
+    // Checksums worked out independently of the encoder.
+    let chunks = png_chunks(&png);
+    assert_eq!(chunks[0].2, 0x57dd_52f8);
+    assert_eq!(chunks[2].2, 0xae42_6082);
+    let (scanlines, adler) = inflate_stored(&chunks[1].1);
+    assert_eq!(scanlines, vec![0, 255, 0, 0, 0, 128]);
+    assert_eq!(adler, 0x0581_0180);
+}
+
+#[test]
+fn large_png_images_span_several_blocks() {
+    let shapes = [Shape::Poly(poly(&[(0, 0), (300, 0), (0, 300)], 1))];
+    let raster = rasterize(&shapes, 1, &bbox(0, 0, 300, 300), 1);
+    let mut png = Vec::new();
+    raster.write_png(&mut png).unwrap();
+
+    // 300 rows of 301 bytes are more than fit in one stored block.
+    let chunks = png_chunks(&png);
+    assert!(chunks[1].1.len() > 0xffff);
+    assert_eq!(decode_png(&png), (300, 300, raster.gray_levels()));
+    // The diagonal pixels are half covered.
+    assert_eq!(raster.coverage[raster.index(0, 0)], 0.5);
+    assert_eq!(raster.coverage[raster.index(299, 299)], 0.5);
+    assert_eq!(raster.coverage[raster.index(0, 299)], 1.0);
+    assert_eq!(raster.coverage[raster.index(299, 0)], 0.0);
+}
+
+#[test]
+fn layers_without_shapes_are_black() {
+    let raster = rasterize(&[], 1, &bbox(0, 0, 30, 10), 10);
+    let mut png = Vec::new();
+    raster.write_png(&mut png).unwrap();
+    assert_eq!(decode_png(&png), (3, 1, vec![0, 0, 0]));
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{poly, rect};
+use doug_geometry::boolean::Region;
+use doug_geometry::shapes::Shape;
+use doug_geometry::sizing::size_layer;
+
+fn region(rects: &[(i32, i32, i32, i32)]) -> Region {
+    let rects: Vec<_> = rects
+        .iter()
+        .map(|&(x0, y0, x1, y1)| rect(x0, y0, x1, y1, 0))
+        .collect();
+    Region::from_rects(rects.iter())
+}
+
+#[test]
+fn rects_grow_and_shrink() {
+    let r = rect(0, 0, 10, 20, 3);
+    assert_eq!(r.size(2, 1), Some(rect(-2, -1, 12, 21, 3)));
+    assert_eq!(r.size(-2, -1), Some(rect(2, 1, 8, 19, 3)));
+    assert_eq!(r.size(-5, 0), None);
+    assert_eq!(r.size(3, -10), None);
+}
+
+#[test]
+fn growing_merges_overlapping_shapes() {
+    let shapes = [
+        Shape::Rect(rect(0, 0, 10, 10, 1)),
+        Shape::Rect(rect(14, 0, 24, 10, 1)),
+        Shape::Rect(rect(100, 0, 110, 10, 1)),
+    ];
+    let polys = size_layer(&shapes, 1, 2, 2).unwrap();
+    assert_eq!(polys.len(), 2);
+    assert!(polys.iter().all(|p| p.outline.layer == 1));
+
+    let grown = region(&[(0, 0, 10, 10), (14, 0, 24, 10), (100, 0, 110, 10)]).size(2, 2);
+    assert_eq!(grown, region(&[(-2, -2, 26, 12), (98, -2, 112, 12)]));
+}
+
+#[test]
+fn shrinking_removes_slivers() {
+    // A 20 wide pad with a 3 wide and a 5 wide arm.
+    let pad = region(&[(0, 0, 20, 20), (20, 8, 60, 11), (8, 20, 13, 60)]);
+    // The 3 wide arm is narrower than 2 * 2, the 5 wide one is not.
+    assert_eq!(
+        pad.size(-2, -2).size(2, 2),
+        region(&[(0, 0, 20, 20), (8, 20, 13, 60)])
+    );
+    // Neither is as wide as 2 * 3.
+    assert_eq!(pad.size(-3, -3).size(3, 3), region(&[(0, 0, 20, 20)]));
+    assert!(pad.size(-10, -10).is_empty());
+}
+
+#[test]
+fn dx_and_dy_may_have_opposite_signs() {
+    let r = region(&[(0, 0, 10, 10)]);
+    assert_eq!(r.size(2, -3), region(&[(-2, 3, 12, 7)]));
+    assert_eq!(r.size(-3, 2), region(&[(3, -2, 7, 12)]));
+
+    // The short bar is shrunk away, even though it meets the tall one once
+    // they are grown.
+    let bars = region(&[(0, 0, 10, 4), (12, 0, 22, 20)]);
+    assert_eq!(bars.size(1, -3), region(&[(11, 3, 23, 17)]));
+}
+
+#[test]
+fn growing_closes_holes() {
+    let ring = region(&[(0, 0, 30, 30)]).difference(&region(&[(10, 10, 20, 20)]));
+    assert_eq!(ring.polys(0)[0].holes.len(), 1);
+    assert_eq!(ring.size(4, 4).polys(0)[0].holes.len(), 1);
+    let grown = ring.size(5, 5);
+    assert_eq!(grown, region(&[(-5, -5, 35, 35)]));
+    assert!(grown.polys(0)[0].holes.is_empty());
+}
+
+#[test]
+fn shrinking_splits_polygons() {
+    // Two squares joined by a 4 wide neck.
+    let dumbbell = poly(
+        &[
+            (0, 0),
+            (20, 0),
+            (20, 8),
+            (40, 8),
+            (40, 0),
+            (60, 0),
+            (60, 20),
+            (40, 20),
+            (40, 12),
+            (20, 12),
+            (20, 20),
+            (0, 20),
+        ],
+        5,
+    );
+    assert_eq!(dumbbell.size(-1, -1).unwrap().len(), 1);
+    let split = dumbbell.size(-2, -2).unwrap();
+    assert_eq!(split.len(), 2);
+    assert!(split.iter().all(|p| p.outline.layer == 5));
+    assert_eq!(
+        Region::from_poly(&split[0].outline)
+            .unwrap()
+            .union(&Region::from_poly(&split[1].outline).unwrap()),
+        region(&[(2, 2, 18, 18), (42, 2, 58, 18)])
+    );
+}
+
+#[test]
+fn sizing_stops_at_the_ends_of_the_coordinate_range() {
+    let (min, max) = (i32::MIN, i32::MAX);
+    let r = rect(min + 5, -10, max - 5, 10, 0);
+    assert_eq!(r.size(10, 0), Some(rect(min, -10, max, 10, 0)));
+    assert_eq!(r.size(min, 0), None);
+    assert_eq!(r.size(0, max), Some(rect(min + 5, min, max - 5, max, 0)));
+
+    let edge = region(&[(min, 0, min + 10, 10), (max - 10, 0, max, 10)]);
+    assert_eq!(
+        edge.size(100, 0),
+        region(&[(min, 0, min + 110, 10), (max - 110, 0, max, 10)])
+    );
+    assert_eq!(
+        edge.size(-2, -2),
+        region(&[(min + 2, 2, min + 8, 8), (max - 8, 2, max - 2, 8)])
+    );
+    assert!(edge.size(min, min).is_empty());
+    assert!(edge.size(min, 1).is_empty());
+    // Growing by `i32::MAX` only reaches `-i32::MAX` below zero.
+    assert_eq!(edge.size(max, max), region(&[(min, min + 1, max, max)]));
+
+    let whole = region(&[(min, min, max, max)]);
+    assert_eq!(
+        whole.size(-1, -1),
+        region(&[(min + 1, min + 1, max - 1, max - 1)])
+    );
+    assert!(whole.size(min, min).is_empty());
+}
@@ -0,0 +1,369 @@
+mod common;
+
+use common::rect;
+use doug_geometry::boolean::Region;
+use doug_geometry::library::{Cell, Instance, Library, LibraryErr};
+use doug_geometry::shapes::{
+    CoordinateErr, Path, PathEndCap, PathLike, Point, PointLike, Rect, Shape, VarWidthPath,
+    VarWidthPathLike,
+};
+use doug_geometry::transform::{Rotation, Transform, Transformable};
+
+const ROTATIONS: [Rotation; 4] = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+
+/// Every orientation, with and without magnification, moved off the origin.
+fn transforms() -> Vec<Transform> {
+    let mut transforms = Vec::new();
+    for rotation in ROTATIONS {
+        for mirror_x in [false, true] {
+            for magnification in [1, 2, 3] {
+                transforms.push(Transform {
+                    offset: Point::new(17, -40),
+                    rotation,
+                    mirror_x,
+                    magnification,
+                });
+            }
+        }
+    }
+    transforms
+}
+
+#[test]
+fn orientations_of_a_point() {
+    let p = Point::new(3, 1);
+    let rotated: Vec<Point> = ROTATIONS
+        .iter()
+        .map(|&r| Transform::rotate(r).apply(p))
+        .collect();
+    assert_eq!(
+        rotated,
+        vec![
+            Point::new(3, 1),
+            Point::new(-1, 3),
+            Point::new(-3, -1),
+            Point::new(1, -3)
+        ]
+    );
+    assert_eq!(Transform::mirror_x().apply(p), Point::new(3, -1));
+    assert_eq!(Transform::mirror_y().apply(p), Point::new(-3, 1));
+    assert_eq!(Transform::magnify(4).apply(p), Point::new(12, 4));
+    assert_eq!(
+        Transform::translate(Point::new(10, 20)).apply(p),
+        Point::new(13, 21)
+    );
+
+    // Mirroring comes before magnification and rotation, which come before
+    // the offset.
+    let sref = Transform {
+        offset: Point::new(100, 0),
+        rotation: Rotation::R90,
+        mirror_x: true,
+        magnification: 2,
+    };
+    assert_eq!(sref.apply(p), Point::new(102, 6));
+}
+
+#[test]
+fn composed_transforms_apply_one_after_the_other() {
+    let points = [Point::new(0, 0), Point::new(5, -3), Point::new(-7, 11)];
+    for first in transforms() {
+        for second in transforms() {
+            let composed = first.then(&second);
+            for &p in points.iter() {
+                assert_eq!(
+                    composed.apply(p),
+                    second.apply(first.apply(p)),
+                    "{first:?} {second:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn inverses_undo_transforms() {
+    let p = Point::new(5, -3);
+    for transform in transforms() {
+        let Some(inverse) = transform.inverse() else {
+            assert_ne!(transform.magnification, 1);
+            continue;
+        };
+        assert_eq!(inverse.apply(transform.apply(p)), p, "{transform:?}");
+        assert_eq!(transform.apply(inverse.apply(p)), p, "{transform:?}");
+        assert_eq!(transform.then(&inverse), Transform::identity());
+    }
+
+    // The inverse of a half turn by i32::MIN would need an offset of
+    // i32::MAX + 1.
+    let far = Transform::translate(Point::new(i32::MIN, 0));
+    assert_eq!(far.inverse(), None);
+}
+
+#[test]
+fn overflow_is_reported() {
+    let far = Transform::translate(Point::new(i32::MAX, 0));
+    assert_eq!(
+        far.try_apply(Point::new(1, 0)),
+        Err(CoordinateErr::Overflow)
+    );
+    assert_eq!(
+        Transform::magnify(3).try_apply(Point::new(0, i32::MIN / 2)),
+        Err(CoordinateErr::Overflow)
+    );
+    assert_eq!(
+        Transform::magnify(1 << 20).try_then(&Transform::magnify(1 << 20)),
+        Err(CoordinateErr::Overflow)
+    );
+    assert_eq!(
+        Transform::translate(Point::new(i32::MAX, 0)).try_then(&far),
+        Err(CoordinateErr::Overflow)
+    );
+}
+
+#[test]
+#[should_panic(expected = "overflows")]
+fn applying_an_overflowing_transform_panics() {
+    Transform::translate(Point::new(i32::MAX, 0)).apply(Point::new(1, 0));
+}
+
+#[test]
+#[should_panic(expected = "Magnifications must be positive")]
+fn zero_magnification_is_rejected() {
+    Transform::magnify(0);
+}
+
+#[test]
+fn transformed_rects_are_normalized() {
+    let r = rect(0, 0, 10, 20, 0).transform(&Transform::rotate(Rotation::R90));
+    assert_eq!(r, rect(-20, 0, 0, 10, 0));
+    let r = rect(0, 0, 10, 20, 0).transform(&Transform::mirror_x());
+    assert_eq!(r, rect(0, -20, 10, 0, 0));
+}
+
+/// The region covered by the transformed segment rects of a path.
+fn transformed_region(rects: &[Rect], transform: &Transform) -> Region {
+    let rects: Vec<Rect> = rects.iter().map(|r| r.transform(transform)).collect();
+    Region::from_rects(rects.iter())
+}
+
+#[test]
+fn odd_width_paths_cover_their_transformed_area() {
+    // A small linear congruential generator, so that failures are reproducible.
+    let mut state = 0x5851_f42d_4c95_7f2d_u64;
+    let mut range = |n: i32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % n as u64) as i32
+    };
+
+    for _ in 0..100 {
+        let mut p = Point::new(range(50), range(50));
+        let mut points = vec![p];
+        let mut horizontal = range(2) == 0;
+        for _ in 0..range(4) + 1 {
+            let length = (range(40) + 1) * if range(2) == 0 { 1 } else { -1 };
+            p = if horizontal {
+                Point::new(p.x + length, p.y)
+            } else {
+                Point::new(p.x, p.y + length)
+            };
+            points.push(p);
+            horizontal = !horizontal;
+        }
+        let end_cap = [
+            PathEndCap::Flush,
+            PathEndCap::HalfWidth,
+            PathEndCap::Custom {
+                begin: range(9) - 2,
+                end: range(9) - 2,
+            },
+        ][range(3) as usize];
+        let path = Path {
+            points: points.clone(),
+            width: 2 * range(6) as u32 + 1,
+            layer: 0,
+            end_cap,
+        };
+        let Ok(rects) = path.segment_rects() else {
+            continue;
+        };
+
+        for transform in transforms() {
+            let moved = path.transform(&transform);
+            assert_eq!(
+                Region::from_shapes([&Shape::Path(moved.clone())]).unwrap(),
+                transformed_region(&rects, &transform),
+                "{path:?} {transform:?} {moved:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn variable_width_paths_cover_their_transformed_area() {
+    let all_odd = VarWidthPath {
+        points: vec![Point::new(0, 0), Point::new(50, 0), Point::new(50, 40)],
+        widths: vec![11, 7],
+        layer: 0,
+        end_cap: PathEndCap::Flush,
+    };
+    let mixed = VarWidthPath {
+        widths: vec![11, 8],
+        ..all_odd.clone()
+    };
+
+    for transform in transforms() {
+        let moved = Shape::VarWidthPath(all_odd.clone()).transform(&transform);
+        assert!(matches!(moved, Shape::VarWidthPath(_)), "{moved:?}");
+        assert_eq!(
+            Region::from_shapes([&moved]).unwrap(),
+            transformed_region(&all_odd.segment_rects().unwrap(), &transform),
+            "{transform:?}"
+        );
+
+        // Mixed widths cannot all be shifted, so they become polygons where
+        // they would need to be.
+        let moved = Shape::VarWidthPath(mixed.clone()).transform(&transform);
+        assert_eq!(
+            Region::from_shapes([&moved]).unwrap(),
+            transformed_region(&mixed.segment_rects().unwrap(), &transform),
+            "{transform:?} {moved:?}"
+        );
+    }
+}
+
+#[test]
+fn odd_width_diagonal_paths_become_polygons() {
+    let path = Path {
+        points: vec![Point::new(0, 0), Point::new(40, 40)],
+        width: 9,
+        layer: 0,
+        end_cap: PathEndCap::Flush,
+    };
+    let mirrored = Shape::Path(path.clone()).transform(&Transform::mirror_x());
+    assert_eq!(
+        mirrored,
+        Shape::Poly(path.as_poly().unwrap().transform(&Transform::mirror_x()))
+    );
+
+    // Even widths need no shift, so they stay paths.
+    let even = Path { width: 10, ..path };
+    let moved = Shape::Path(even).transform(&Transform::mirror_x());
+    assert!(matches!(moved, Shape::Path(_)), "{moved:?}");
+}
+
+#[test]
+fn paths_are_shifted_where_axes_are_reversed() {
+    let path = Path {
+        points: vec![Point::new(0, 0), Point::new(100, 0)],
+        width: 11,
+        layer: 0,
+        end_cap: PathEndCap::Flush,
+    };
+    // The extra unit is above the path, so once mirrored the path moves down
+    // to keep it below.
+    let mirrored = path.transform(&Transform::mirror_x());
+    assert_eq!(
+        mirrored.points,
+        vec![Point::new(0, -1), Point::new(100, -1)]
+    );
+    assert_eq!(mirrored.end_cap, PathEndCap::Flush);
+
+    // Once turned around, the path runs towards -x and is moved back along
+    // itself by a unit, so its flush ends are adjusted to stay in place.
+    let turned = path.transform(&Transform::rotate(Rotation::R180));
+    assert_eq!(
+        turned.points,
+        vec![Point::new(-1, -1), Point::new(-101, -1)]
+    );
+    assert_eq!(turned.end_cap, PathEndCap::Custom { begin: 1, end: -1 });
+
+    let even = Path { width: 10, ..path };
+    let turned = even.transform(&Transform::rotate(Rotation::R180));
+    assert_eq!(turned.points, vec![Point::new(0, 0), Point::new(-100, 0)]);
+    assert_eq!(turned.end_cap, PathEndCap::Flush);
+    assert_eq!(
+        even.transform(&Transform::magnify(3)).width,
+        30,
+        "widths are magnified"
+    );
+}
+
+#[test]
+fn point_like_helpers_agree() {
+    let transform = Transform {
+        offset: Point::new(1, 2),
+        rotation: Rotation::R270,
+        mirror_x: true,
+        magnification: 1,
+    };
+    let points = [Point::new(1, 0), Point::new(0, 1)];
+    let moved: Vec<Point> = transform.apply_all(&points).collect();
+    assert_eq!(
+        moved,
+        points
+            .iter()
+            .map(|&p| transform.apply(p))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(moved[0], Point::new(1, 1));
+    assert_eq!(moved[1].x(), 0);
+}
+
+#[test]
+fn diagonal_paths_are_moved_exactly() {
+    for width in [4, 5, 8, 13] {
+        let path = Path {
+            points: vec![Point::new(0, 0), Point::new(40, 40), Point::new(80, 40)],
+            width,
+            layer: 0,
+            end_cap: PathEndCap::HalfWidth,
+        };
+        // Magnifying a diagonal outline magnifies its rounding too, so only
+        // the orientations are compared.
+        for transform in transforms().into_iter().filter(|t| t.magnification == 1) {
+            let mut moved = match Shape::Path(path.clone()).transform(&transform) {
+                Shape::Path(moved) => moved.as_poly().unwrap().points,
+                Shape::Poly(moved) => moved.points,
+                moved => panic!("{moved:?}"),
+            };
+            let mut expected = path.as_poly().unwrap().transform(&transform).points;
+            // The outlines may start at different points, and be wound
+            // differently once mirrored.
+            moved.sort_by_key(|p| (p.x, p.y));
+            expected.sort_by_key(|p| (p.x, p.y));
+            assert_eq!(moved, expected, "{width} {transform:?}");
+        }
+    }
+}
+
+#[test]
+fn placements_without_magnification_are_rejected() {
+    let library = Library {
+        cells: vec![
+            Cell {
+                name: "top".to_string(),
+                instances: vec![Instance {
+                    cell: "leaf".to_string(),
+                    transform: Transform {
+                        magnification: 0,
+                        ..Transform::identity()
+                    },
+                }],
+                ..Cell::default()
+            },
+            Cell {
+                name: "leaf".to_string(),
+                shapes: vec![Shape::Rect(rect(0, 0, 10, 10, 0))],
+                ..Cell::default()
+            },
+        ],
+        ..Library::default()
+    };
+    assert_eq!(
+        library.flatten("top", None),
+        Err(LibraryErr::ZeroMagnification("leaf".to_string()))
+    );
+}
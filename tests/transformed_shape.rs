@@ -0,0 +1,179 @@
+mod common;
+
+use common::{path, points, poly, rect};
+use doug_geometry::bbox::CalculateBoundingBox;
+use doug_geometry::library::{Cell, Instance, Library, LibraryErr};
+use doug_geometry::shapes::{
+    ArchivedShape, PathEndCap, Point, PointLike, Shape, ShapeLike, Shapes, VarWidthPath,
+};
+use doug_geometry::transform::{Rotation, Transform, Transformable, TransformedShape};
+
+fn shapes() -> Vec<Shape> {
+    vec![
+        Shape::Rect(rect(0, 0, 10, 20, 1)),
+        Shape::Poly(poly(
+            &[(0, 0), (30, 0), (30, 10), (10, 10), (10, 30), (0, 30)],
+            2,
+        )),
+        Shape::Path(path(
+            &[(0, 0), (50, 0), (50, 40)],
+            7,
+            3,
+            PathEndCap::Custom { begin: 2, end: 5 },
+        )),
+        Shape::VarWidthPath(VarWidthPath {
+            points: points(&[(0, 0), (0, 60), (-30, 60)]),
+            widths: vec![10, 6],
+            layer: 4,
+            end_cap: PathEndCap::HalfWidth,
+        }),
+    ]
+}
+
+fn transforms() -> Vec<Transform> {
+    let mut transforms = Vec::new();
+    for rotation in [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270] {
+        for mirror_x in [false, true] {
+            for magnification in [1, 3] {
+                transforms.push(Transform {
+                    offset: Point::new(-7, 12),
+                    rotation,
+                    mirror_x,
+                    magnification,
+                });
+            }
+        }
+    }
+    transforms
+}
+
+fn archive(shapes: Vec<Shape>) -> rkyv::AlignedVec {
+    let shapes = Shapes {
+        bbox: shapes.bbox(),
+        shapes,
+        units: Default::default(),
+    };
+    rkyv::to_bytes::<_, 1024>(&shapes).unwrap()
+}
+
+#[test]
+fn views_agree_with_transformed_shapes() {
+    let bytes = archive(shapes());
+    let archived = unsafe { rkyv::archived_root::<Shapes>(&bytes[..]) };
+
+    for transform in transforms() {
+        for (shape, archived) in shapes().iter().zip(archived.shapes.iter()) {
+            let view = TransformedShape::new(archived, transform);
+            let moved = shape.transform(&transform);
+            assert_eq!(view.to_shape(), moved, "{transform:?}");
+            assert_eq!(view.layer(), shape.layer());
+            assert_eq!(view.bbox(), moved.bbox(), "{shape:?} {transform:?}");
+
+            match &moved {
+                Shape::Rect(r) => {
+                    assert_eq!(view.rect(), Some(*r));
+                    // The corners are not normalized.
+                    let Shape::Rect(original) = shape else {
+                        unreachable!()
+                    };
+                    assert_eq!(
+                        view.points().collect::<Vec<_>>(),
+                        vec![transform.apply(original.p0), transform.apply(original.p1)]
+                    );
+                    assert_eq!(view.end_cap(), None);
+                }
+                Shape::Poly(p) => {
+                    assert_eq!(view.rect(), None);
+                    assert_eq!(view.points().collect::<Vec<_>>(), p.points);
+                    assert_eq!(view.widths().count(), 0);
+                }
+                Shape::Path(p) => {
+                    assert_eq!(view.points().collect::<Vec<_>>(), p.points);
+                    assert_eq!(view.widths().collect::<Vec<_>>(), vec![p.width; 2]);
+                    assert_eq!(view.end_cap(), Some(p.end_cap), "{transform:?}");
+                }
+                Shape::VarWidthPath(p) => {
+                    assert_eq!(view.points().collect::<Vec<_>>(), p.points);
+                    assert_eq!(view.widths().collect::<Vec<_>>(), p.widths);
+                    assert_eq!(view.end_cap(), Some(p.end_cap));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn transformed_points_are_lazy_views_of_archived_points() {
+    let bytes = archive(shapes());
+    let archived = unsafe { rkyv::archived_root::<Shapes>(&bytes[..]) };
+    let ArchivedShape::Poly(poly) = &archived.shapes[1] else {
+        panic!("{:?}", archived.shapes[1]);
+    };
+
+    let transform = Transform::rotate(Rotation::R90);
+    let moved = transform.apply_all(&poly.points);
+    assert_eq!(moved.len(), 6);
+    assert_eq!(
+        moved.collect::<Vec<_>>(),
+        points(&[(0, 0), (0, 30), (-10, 30), (-10, 10), (-30, 10), (-30, 0)])
+    );
+}
+
+fn library() -> Library {
+    let leaf = Cell {
+        name: "leaf".to_string(),
+        shapes: shapes(),
+        ..Cell::default()
+    };
+    let instances = transforms()
+        .into_iter()
+        .enumerate()
+        .map(|(ix, transform)| Instance {
+            cell: "leaf".to_string(),
+            transform: Transform {
+                offset: Point::new(ix as i32 * 1000, 0),
+                ..transform
+            },
+        })
+        .collect();
+    let top = Cell {
+        name: "top".to_string(),
+        instances,
+        ..Cell::default()
+    };
+    Library {
+        cells: vec![top, leaf],
+        ..Library::default()
+    }
+}
+
+#[test]
+fn archived_views_flatten_like_libraries() {
+    let library = library();
+    let flat = library.flatten("top", None).unwrap();
+
+    let bytes = rkyv::to_bytes::<_, 4096>(&library).unwrap();
+    let archived = unsafe { rkyv::archived_root::<Library>(&bytes[..]) };
+    let viewed: Vec<Shape> = archived
+        .flatten_view("top")
+        .unwrap()
+        .map(|view| view.to_shape())
+        .collect();
+    assert_eq!(viewed, flat.shapes);
+
+    let bbox = archived
+        .flatten_view("top")
+        .unwrap()
+        .map(|view| view.bbox())
+        .reduce(|mut a, b| {
+            a.union(&b);
+            a
+        })
+        .unwrap();
+    assert_eq!(bbox, flat.bbox);
+
+    assert_eq!(
+        archived.flatten_view("missing").err(),
+        Some(LibraryErr::UnknownCell("missing".to_string()))
+    );
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use common::{bbox, path, poly, rect};
+use doug_geometry::shapes::{PathEndCap, Point, PointLike, Shape, Shapes, VarWidthPath};
+use doug_geometry::units::{OffGridVertex, Units, UnitsErr};
+
+#[test]
+fn microns_convert_to_the_nearest_dbu() {
+    let units = Units::new(1000, 5);
+    assert_eq!(units.microns_to_dbu(1.5), Ok(1500));
+    assert_eq!(units.microns_to_dbu(-0.0004), Ok(0));
+    assert_eq!(units.microns_to_dbu(0.0006), Ok(1));
+    assert_eq!(units.dbu_to_microns(-2500), -2.5);
+
+    assert_eq!(units.microns_to_dbu(f64::NAN), Err(UnitsErr::NotFinite));
+    assert_eq!(
+        units.microns_to_dbu(f64::INFINITY),
+        Err(UnitsErr::NotFinite)
+    );
+    assert_eq!(units.microns_to_dbu(3e6), Err(UnitsErr::Overflow));
+    assert_eq!(units.microns_to_dbu(-2147483.648), Ok(i32::MIN));
+
+    // User units are separate from microns.
+    let units = units.with_user_unit(100);
+    assert_eq!(units.user_to_dbu(2.0), Ok(200));
+    assert_eq!(units.dbu_to_user(250), 2.5);
+    assert_eq!(units.microns_to_dbu(2.0), Ok(2000));
+}
+
+#[test]
+#[should_panic(expected = "Units and grids must be positive")]
+fn zero_grids_are_rejected() {
+    Units::new(1000, 0);
+}
+
+#[test]
+fn values_snap_to_the_nearest_grid_line() {
+    let units = Units::new(1000, 10);
+    assert_eq!(units.snap(14), 10);
+    assert_eq!(units.snap(15), 20);
+    assert_eq!(units.snap(-15), -10);
+    assert_eq!(units.snap(-16), -20);
+    assert!(units.is_on_grid(Point::new(-30, 40)));
+    assert!(!units.is_on_grid(Point::new(-30, 41)));
+
+    // The grid lines past the ends of the i32 range are not used.
+    assert_eq!(units.snap(i32::MAX), 2147483640);
+    assert_eq!(units.snap(i32::MIN), -2147483640);
+}
+
+#[test]
+fn snapped_shapes_keep_their_distinct_vertices() {
+    let units = Units::new(1000, 10);
+    assert_eq!(
+        units.snap_shape(&Shape::Rect(rect(1, -1, 19, 26, 1))),
+        Some(Shape::Rect(rect(0, 0, 20, 30, 1)))
+    );
+
+    // The second and last points snap onto the ones before them.
+    let snapped = units.snap_shape(&Shape::Poly(poly(
+        &[(0, 0), (3, 2), (40, 0), (40, 40), (2, 1)],
+        1,
+    )));
+    assert_eq!(
+        snapped,
+        Some(Shape::Poly(poly(&[(0, 0), (40, 0), (40, 40)], 1)))
+    );
+
+    let snapped = units.snap_shape(&Shape::Path(path(
+        &[(0, 0), (2, 3), (50, 0), (51, 1)],
+        10,
+        1,
+        PathEndCap::Flush,
+    )));
+    assert_eq!(
+        snapped,
+        Some(Shape::Path(path(
+            &[(0, 0), (50, 0)],
+            10,
+            1,
+            PathEndCap::Flush
+        )))
+    );
+
+    // Variable width paths lose the widths of the segments which become
+    // empty.
+    let var_width = Shape::VarWidthPath(VarWidthPath {
+        points: vec![
+            Point::new(0, 0),
+            Point::new(2, 2),
+            Point::new(50, 0),
+            Point::new(50, 48),
+        ],
+        widths: vec![10, 20, 30],
+        layer: 1,
+        end_cap: PathEndCap::Flush,
+    });
+    assert_eq!(
+        units.snap_shape(&var_width),
+        Some(Shape::VarWidthPath(VarWidthPath {
+            points: vec![Point::new(0, 0), Point::new(50, 0), Point::new(50, 50)],
+            widths: vec![20, 30],
+            layer: 1,
+            end_cap: PathEndCap::Flush,
+        }))
+    );
+}
+
+#[test]
+fn shapes_smaller_than_the_grid_collapse() {
+    let units = Units::new(1000, 10);
+    assert_eq!(units.snap_shape(&Shape::Rect(rect(0, 0, 40, 4, 1))), None);
+    // Three distinct points, all on one line.
+    assert_eq!(
+        units.snap_shape(&Shape::Poly(poly(&[(0, 0), (10, 1), (20, 0)], 1))),
+        None
+    );
+    assert_eq!(
+        units.snap_shape(&Shape::Poly(poly(&[(0, 0), (4, 0), (4, 4)], 1))),
+        None
+    );
+    assert_eq!(
+        units.snap_shape(&Shape::Path(path(
+            &[(0, 0), (3, 3)],
+            10,
+            1,
+            PathEndCap::Flush
+        ))),
+        None
+    );
+
+    let mut shapes = Shapes {
+        bbox: bbox(0, 0, 104, 104),
+        shapes: vec![
+            Shape::Rect(rect(0, 0, 4, 4, 1)),
+            Shape::Rect(rect(6, 6, 54, 44, 1)),
+            Shape::Rect(rect(100, 100, 104, 104, 1)),
+        ],
+        units,
+    };
+    assert_eq!(shapes.snap_to_grid(), vec![0, 2]);
+    assert_eq!(shapes.shapes, vec![Shape::Rect(rect(10, 10, 50, 40, 1))]);
+    assert_eq!(shapes.bbox, bbox(10, 10, 50, 40));
+}
+
+#[test]
+fn off_grid_vertices_are_reported_by_shape_and_vertex() {
+    let shapes = Shapes {
+        bbox: bbox(0, 0, 100, 100),
+        shapes: vec![
+            Shape::Rect(rect(0, 0, 10, 15, 1)),
+            Shape::Poly(poly(&[(0, 0), (20, 0), (20, 20), (1, 20)], 1)),
+            Shape::Path(path(&[(0, 0), (10, 0), (10, 10)], 10, 1, PathEndCap::Flush)),
+            Shape::Path(path(&[(5, 0), (10, 0), (10, 3)], 10, 1, PathEndCap::Flush)),
+        ],
+        units: Units::new(1000, 5),
+    };
+    let off_grid = |shape, vertex, x, y| OffGridVertex {
+        shape,
+        vertex,
+        point: Point::new(x, y),
+    };
+    assert_eq!(
+        shapes.off_grid_vertices(),
+        vec![off_grid(1, 3, 1, 20), off_grid(3, 2, 10, 3)]
+    );
+
+    let mut snapped = shapes;
+    assert_eq!(snapped.snap_to_grid(), Vec::<usize>::new());
+    assert_eq!(snapped.off_grid_vertices(), vec![]);
+}
+
+#[test]
+fn archives_keep_their_units() {
+    let shapes = Shapes {
+        bbox: bbox(0, 0, 10, 10),
+        shapes: vec![Shape::Rect(rect(0, 0, 10, 10, 1))],
+        units: Units::new(2000, 5).with_user_unit(1),
+    };
+    let bytes = rkyv::to_bytes::<_, 1024>(&shapes).unwrap();
+    let archived = unsafe { rkyv::archived_root::<Shapes>(&bytes[..]) };
+    assert_eq!(archived.units, shapes.units);
+    assert_eq!(archived.units.grid.get(), 5);
+}
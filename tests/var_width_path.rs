@@ -0,0 +1,173 @@
+mod common;
+
+use common::{points, rect};
+use doug_geometry::bbox::CalculateBoundingBox;
+use doug_geometry::boolean::{Region, RegionErr};
+use doug_geometry::shapes::{
+    PathEndCap, PathErr, Point, PointLike, Shape, VarWidthPath, VarWidthPathLike,
+};
+
+fn path(p: &[(i32, i32)], widths: &[u32], end_cap: PathEndCap) -> VarWidthPath {
+    VarWidthPath {
+        points: points(p),
+        widths: widths.to_vec(),
+        layer: 3,
+        end_cap,
+    }
+}
+
+#[test]
+fn change_of_width_leaves_a_step() {
+    let path = path(&[(0, 0), (100, 0), (200, 0)], &[20, 10], PathEndCap::Flush);
+    assert_eq!(
+        path.segment_rects(),
+        Ok(vec![rect(0, -10, 100, 10, 3), rect(100, -5, 200, 5, 3)])
+    );
+
+    let poly = path.as_poly().unwrap();
+    assert_eq!(poly.layer, 3);
+    assert_eq!(poly.points.len(), 8);
+    let region = Region::from_poly(&poly).unwrap();
+    assert_eq!(region.area(), 100 * 20 + 100 * 10);
+    assert_eq!(
+        region,
+        Region::from_shapes([&Shape::VarWidthPath(path)]).unwrap()
+    );
+}
+
+#[test]
+fn corners_are_filled_by_the_segment_turned_into() {
+    // The first segment runs on by half the width of the second, and the
+    // second starts back by half the width of the first.
+    let path = path(
+        &[(0, 0), (100, 0), (100, 100)],
+        &[20, 10],
+        PathEndCap::Flush,
+    );
+    assert_eq!(
+        path.segment_rects(),
+        Ok(vec![rect(0, -10, 105, 10, 3), rect(95, -10, 105, 100, 3)])
+    );
+    assert_eq!(path.as_poly().unwrap().points.len(), 6);
+}
+
+#[test]
+fn end_caps_extend_the_first_and_last_segments() {
+    // The extra unit of an odd width goes to the +x and +y sides, so a half
+    // width cap on the +x end is one longer.
+    let half_width = path(&[(0, 0), (100, 0)], &[11], PathEndCap::HalfWidth);
+    assert_eq!(
+        half_width.segment_rects(),
+        Ok(vec![rect(-5, -5, 106, 6, 3)])
+    );
+
+    let custom = path(
+        &[(0, 0), (0, 100), (50, 100)],
+        &[10, 20],
+        PathEndCap::Custom { begin: 7, end: 3 },
+    );
+    assert_eq!(
+        custom.segment_rects(),
+        Ok(vec![rect(-5, -7, 5, 110, 3), rect(-5, 90, 53, 110, 3)])
+    );
+}
+
+#[test]
+fn repeated_points_are_dropped_with_their_widths() {
+    let path = path(&[(0, 0), (0, 0), (100, 0)], &[50, 20], PathEndCap::Flush);
+    assert_eq!(path.segment_rects(), Ok(vec![rect(0, -10, 100, 10, 3)]));
+}
+
+#[test]
+fn loops_enclosing_a_hole_have_no_poly() {
+    let path = path(
+        &[(0, 0), (100, 0), (100, 100), (0, 100), (0, 0)],
+        &[10, 10, 10, 10],
+        PathEndCap::HalfWidth,
+    );
+    assert_eq!(path.as_poly(), Err(PathErr::EnclosesHole));
+    let region = Region::from_shapes([&Shape::VarWidthPath(path)]).unwrap();
+    assert_eq!(region.components().len(), 1);
+}
+
+#[test]
+fn malformed_paths_are_rejected() {
+    let wrong_widths = path(&[(0, 0), (100, 0)], &[10, 10], PathEndCap::Flush);
+    assert_eq!(
+        wrong_widths.segment_rects(),
+        Err(PathErr::WrongNumberOfWidths)
+    );
+    assert_eq!(wrong_widths.as_poly(), Err(PathErr::WrongNumberOfWidths));
+
+    let diagonal = path(&[(0, 0), (100, 100)], &[10], PathEndCap::Flush);
+    assert_eq!(diagonal.segment_rects(), Err(PathErr::NotRectilinear));
+
+    let no_segments = path(&[(5, 5), (5, 5)], &[10], PathEndCap::Flush);
+    assert_eq!(no_segments.segment_rects(), Err(PathErr::NotEnoughPoints));
+
+    assert_eq!(
+        VarWidthPath::new(vec![Point::new(0, 0)], vec![10], 0, PathEndCap::Flush),
+        Err(PathErr::WrongNumberOfWidths)
+    );
+    assert!(VarWidthPath::new(
+        vec![Point::new(0, 0), Point::new(0, 10)],
+        vec![10],
+        0,
+        PathEndCap::Flush
+    )
+    .is_ok());
+}
+
+#[test]
+fn regions_reject_malformed_paths() {
+    let square = Shape::Rect(rect(0, 0, 10, 10, 3));
+    let shapes = [
+        square.clone(),
+        Shape::VarWidthPath(path(&[(0, 0), (100, 0)], &[], PathEndCap::Flush)),
+        Shape::VarWidthPath(path(&[(0, 0), (100, 100)], &[10], PathEndCap::Flush)),
+    ];
+    assert_eq!(
+        Region::from_shapes(shapes[..2].iter()),
+        Err(RegionErr::InvalidPath(PathErr::WrongNumberOfWidths))
+    );
+    assert_eq!(
+        Region::from_shapes([&shapes[0], &shapes[2]]),
+        Err(RegionErr::NotRectilinear)
+    );
+
+    let (region, left_out) = Region::from_rectilinear_shapes(shapes.iter());
+    assert_eq!(region, Region::from_shapes([&square]).unwrap());
+    assert_eq!(left_out, vec![1, 2]);
+}
+
+#[test]
+fn bounding_boxes() {
+    let corner = path(
+        &[(0, 0), (100, 0), (100, 100)],
+        &[20, 10],
+        PathEndCap::Flush,
+    );
+    let bbox = corner.bbox();
+    assert_eq!(bbox.min(), Point::new(0, -10));
+    assert_eq!(bbox.max(), Point::new(105, 100));
+
+    // Malformed paths are approximated by squares around their points.
+    let diagonal = path(&[(0, 0), (100, 100)], &[30], PathEndCap::Flush);
+    let bbox = diagonal.bbox();
+    assert_eq!(bbox.min(), Point::new(-15, -15));
+    assert_eq!(bbox.max(), Point::new(115, 115));
+}
+
+#[test]
+fn archived_paths_have_the_same_rects() {
+    let path = path(
+        &[(0, 0), (100, 0), (100, 100)],
+        &[20, 11],
+        PathEndCap::HalfWidth,
+    );
+    let bytes = rkyv::to_bytes::<_, 256>(&path).unwrap();
+    let archived = unsafe { rkyv::archived_root::<VarWidthPath>(&bytes[..]) };
+    assert_eq!(archived.segment_rects(), path.segment_rects());
+    assert_eq!(archived.as_poly(), path.as_poly());
+    assert_eq!(archived.bbox(), path.bbox());
+}